@@ -28,7 +28,7 @@ fn main() {
 	let username = input("Username: ");
 	let password = input("Password: ");
 
-	let mut reddit = App::new("orca_pm_example", "1.0", "/u/IntrepidPig").unwrap();
+	let reddit = App::new("orca_pm_example", "1.0", "/u/IntrepidPig").unwrap();
 	reddit.authorize_script(&id, &secret, &username, &password).unwrap();
 
 	println!("Please enter the details of the message.");
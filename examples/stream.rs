@@ -8,6 +8,6 @@ fn main() {
 	let reddit = App::new("orca_stream_example", "1.0", "/u/IntrepidPig").unwrap();
 
 	for comment in reddit.create_comment_stream("all") {
-		println!("{}: {}\n", comment.author, comment.body);
+		println!("{}: {}\n", comment.author.as_deref().unwrap_or("[deleted]"), comment.body);
 	}
 }
@@ -1,8 +1,14 @@
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
 use failure::Error;
 use hyper::{Body, Request};
 use json::Value;
 
-use App;
+use data::{can_crosspost_to, trophy_list, user_list_names, Gildings, KarmaBreakdown, NotificationCounts, SavedKind, Thing, Trophy};
+use net::{json_url, uri_params_from_map};
+use {App, RedditError};
 
 impl App {
 	/// Get info of the user currently authorized
@@ -11,8 +17,182 @@ impl App {
 	/// # Returns
 	/// A result with the json value of the user data
 	pub fn get_self(&self) -> Result<Value, Error> {
-		let req = Request::get("https://oauth.reddit.com/api/v1/me/.json").body(Body::empty()).unwrap();
+		let req = Request::get(json_url("https://oauth.reddit.com/api/v1/me")).body(Body::empty()).unwrap();
 
 		self.conn.run_auth_request(req)
 	}
+
+	/// Get the name of the profile subreddit of the user currently authorized, which posts to the
+	/// profile of the user are submitted to
+	///
+	/// Note: requires connection to be authorized
+	/// # Returns
+	/// The name of the subreddit, e.g. `u_IntrepidPig`
+	pub fn profile_subreddit(&self) -> Result<String, Error> {
+		let me = self.get_self()?;
+		match me["subreddit"]["display_name"].as_str() {
+			Some(sub) => Ok(sub.to_string()),
+			None => me["name"].as_str().map(|name| format!("u_{}", name)).ok_or_else(|| Error::from(RedditError::AuthError)),
+		}
+	}
+
+	/// Get the karma of the user currently authorized in each subreddit, along with the totals
+	///
+	/// Note: requires connection to be authorized
+	pub fn get_karma_breakdown(&self) -> Result<KarmaBreakdown, Error> {
+		let req = Request::get(json_url("https://oauth.reddit.com/api/v1/me/karma")).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		KarmaBreakdown::from_value(&resp, self)
+	}
+
+	/// Get the awards the user currently authorized gave to posts and comments
+	///
+	/// Note: requires connection to be authorized
+	pub fn get_gildings(&self) -> Result<Gildings, Error> {
+		let req = Request::get(json_url("https://oauth.reddit.com/api/v1/me/gildings")).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		Gildings::from_value(&resp, self)
+	}
+
+	/// Get the trophies shown on the profile of the user currently authorized
+	///
+	/// Note: requires connection to be authorized
+	pub fn get_my_trophies(&self) -> Result<Vec<Trophy>, Error> {
+		let req = Request::get(json_url("https://oauth.reddit.com/api/v1/me/trophies")).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		trophy_list(&resp, self)
+	}
+
+	/// Get the amount of unread messages and unread modmail of the user currently authorized. The
+	/// modmail counts are only requested if reddit reports new modmail.
+	///
+	/// Note: requires connection to be authorized
+	pub fn notification_counts(&self) -> Result<NotificationCounts, Error> {
+		let me = self.get_self()?;
+		if me["new_modmail_exists"].as_bool().unwrap_or(false) {
+			let req = Request::get(json_url("https://oauth.reddit.com/api/mod/conversations/unread/count")).body(Body::empty()).unwrap();
+			let modmail = self.conn.run_auth_request(req)?;
+			Ok(NotificationCounts::from_values(&me, Some(&modmail)))
+		} else {
+			Ok(NotificationCounts::from_values(&me, None))
+		}
+	}
+
+	/// Poll the notification counts of the user currently authorized, calling a callback with the
+	/// counts once at the start and then whenever they change. Failed polls are logged and retried
+	/// at the next interval.
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `interval` - Time waited between polls, in addition to the connection's ratelimiting
+	/// * `callback` - Called with the changed counts. Polling stops when it returns `false`
+	pub fn watch_notifications<F: FnMut(&NotificationCounts) -> bool>(&self, interval: Duration, mut callback: F) {
+		let mut last: Option<NotificationCounts> = None;
+		loop {
+			match self.notification_counts() {
+				Ok(counts) => {
+					if last != Some(counts) {
+						last = Some(counts);
+						if !callback(&counts) {
+							return;
+						}
+					}
+				}
+				Err(e) => warn!("Error from notification_counts, retrying: {}", e),
+			}
+			thread::sleep(interval);
+		}
+	}
+
+	/// Get the things the user currently authorized has saved
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `username` - Name of the user currently authorized
+	/// * `kind` - Optional kind of things to retrieve. Retrieves both posts and comments if `None`
+	/// # Returns
+	/// A result with a json listing of the saved things
+	pub fn get_saved(&self, username: &str, kind: Option<SavedKind>) -> Result<Value, Error> {
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		if let Some(kind) = kind {
+			let (key, value) = kind.param();
+			params.insert(key, value);
+		}
+
+		let req = Request::get(uri_params_from_map(&json_url(&format!("https://oauth.reddit.com/user/{}/saved", username)), &params)?).body(Body::empty()).unwrap();
+
+		self.conn.run_auth_request(req)
+	}
+
+	/// Get the names of the subreddits the user currently authorized moderates
+	///
+	/// Note: requires connection to be authorized
+	pub fn get_moderated_subreddits(&self) -> Result<Vec<String>, Error> {
+		self.get_my_subreddits("moderator", |_| true)
+	}
+
+	/// Get the names of the subreddits the user currently authorized can crosspost to, which are
+	/// the subscribed subreddits that allow crossposts and that the user is allowed to submit to
+	///
+	/// Note: requires connection to be authorized
+	pub fn crosspostable_subreddits(&self) -> Result<Vec<String>, Error> {
+		self.get_my_subreddits("subscriber", can_crosspost_to)
+	}
+
+	/// Get the names of the subreddits in one of the lists of the user currently authorized,
+	/// loading every page of it
+	/// # Arguments
+	/// * `list` - The list, e.g. `moderator` or `subscriber`
+	/// * `keep` - Function deciding from the data of a subreddit whether to include it
+	fn get_my_subreddits<F: Fn(&Value) -> bool>(&self, list: &str, keep: F) -> Result<Vec<String>, Error> {
+		let mut subs = Vec::new();
+		let mut after: Option<String> = None;
+
+		loop {
+			let mut params: HashMap<&str, &str> = HashMap::new();
+			params.insert("limit", "100");
+			if let Some(ref after) = after {
+				params.insert("after", after);
+			}
+
+			let req = Request::get(uri_params_from_map(&json_url(&format!("https://oauth.reddit.com/subreddits/mine/{}", list)), &params)?).body(Body::empty()).unwrap();
+			let resp = self.conn.run_auth_request(req)?;
+
+			if let Some(children) = resp["data"]["children"].as_array() {
+				subs.extend(children.iter().map(|sub| &sub["data"]).filter(|data| keep(data)).filter_map(|data| data["display_name"].as_str()).map(|name| name.to_string()));
+			}
+
+			match resp["data"]["after"].as_str() {
+				Some(next) => after = Some(next.to_string()),
+				None => break,
+			}
+		}
+
+		Ok(subs)
+	}
+
+	/// Get the names of the users the user currently authorized blocked
+	///
+	/// Note: requires connection to be authorized
+	pub fn get_blocked_users(&self) -> Result<Vec<String>, Error> {
+		self.get_pref_user_list("blocked")
+	}
+
+	/// Get the names of the users the user currently authorized trusts, whose messages are never
+	/// filtered
+	///
+	/// Note: requires connection to be authorized
+	pub fn get_trusted_users(&self) -> Result<Vec<String>, Error> {
+		self.get_pref_user_list("trusted")
+	}
+
+	fn get_pref_user_list(&self, list: &str) -> Result<Vec<String>, Error> {
+		let req = Request::get(json_url(&format!("https://oauth.reddit.com/prefs/{}", list))).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		user_list_names(&resp)
+	}
 }
@@ -1,7 +1,13 @@
+use std::collections::HashMap;
+use std::mem;
 use std::sync::Arc;
+use std::time::Instant;
 
-use net::auth::OAuth;
-use {App, ResponseGenFn, Scopes};
+use hyper::{Body, Request};
+
+use net::auth::{scope_infos, OAuth};
+use net::{check_ratelimit, json_url, SelfTestReport, SelfTestStep};
+use {App, Delegate, RedditError, ResponseGenFn, ScopeInfo, Scopes};
 
 use failure::Error;
 
@@ -12,12 +18,49 @@ impl App {
 	/// * `secret` - The app secret registered on Reddit
 	/// * `username` - The username of the user to authorize as
 	/// * `password` - The password of the user to authorize as
-	pub fn authorize_script(&mut self, id: &str, secret: &str, username: &str, password: &str) -> Result<(), Error> {
+	pub fn authorize_script(&self, id: &str, secret: &str, username: &str, password: &str) -> Result<(), Error> {
 		let auth = OAuth::create_script(&self.conn, id, secret, username, password)?;
-		self.conn.auth = Some(auth);
+		*self.conn.auth.borrow_mut() = Some(auth);
 		Ok(())
 	}
 
+	/// Sends the requests made by a function as another account, e.g. to attribute a moderation
+	/// action to the moderator who asked for it instead of to the account of the app. The
+	/// authorization, ratelimit state, comment cooldowns, duplicate guard and comment cooldown
+	/// waiting of the app are restored afterwards, even if the function panics. Tokens the
+	/// delegate's authorization is refreshed to, cooldowns observed and settings changed by the
+	/// function are kept in the delegate.
+	/// # Arguments
+	/// * `delegate` - The account to send the requests as
+	/// * `f` - Function sending the requests with the app
+	/// # Returns
+	/// The result of the function
+	pub fn on_behalf_of<R, F: FnOnce(&App) -> R>(&self, delegate: &mut Delegate, f: F) -> R {
+		struct Restore<'a> {
+			app: &'a App,
+			delegate: &'a mut Delegate,
+		}
+
+		impl<'a> Drop for Restore<'a> {
+			fn drop(&mut self) {
+				self.app.swap_delegate(self.delegate);
+			}
+		}
+
+		self.swap_delegate(delegate);
+		let _restore = Restore { app: self, delegate };
+		f(self)
+	}
+
+	/// Exchanges the state of the app that belongs to its account with the one of a delegate.
+	/// Exchanging them again restores both.
+	fn swap_delegate(&self, delegate: &mut Delegate) {
+		self.conn.swap_delegate(delegate);
+		mem::swap(&mut *self.comment_cooldowns.borrow_mut(), &mut delegate.comment_cooldowns);
+		delegate.duplicate_guard = self.duplicate_guard.replace(delegate.duplicate_guard);
+		delegate.wait_for_comment_cooldown = self.wait_for_comment_cooldown.replace(delegate.wait_for_comment_cooldown);
+	}
+
 	/// Authorize this app as an installed app
 	/// # Arguments
 	/// * `conn` - A reference to the connection to authorize
@@ -30,9 +73,67 @@ impl App {
 	/// that an error occurred within the function.
 	/// * `scopes` - A reference to a Scopes instance representing the capabilites you are requesting
 	/// as an installed app.
-	pub fn authorize_installed_app<I: Into<Option<Arc<ResponseGenFn>>>>(&mut self, id: &str, redirect: &str, response_gen: I, scopes: &Scopes) -> Result<(), Error> {
+	pub fn authorize_installed_app<I: Into<Option<Arc<ResponseGenFn>>>>(&self, id: &str, redirect: &str, response_gen: I, scopes: &Scopes) -> Result<(), Error> {
 		let auth = OAuth::create_installed_app(&self.conn, id, redirect, response_gen, scopes)?;
-		self.conn.auth = Some(auth);
+		*self.conn.auth.borrow_mut() = Some(auth);
 		Ok(())
 	}
+
+	/// Checks that the app can reach reddit and use its authorization, e.g. to log what's wrong
+	/// when a bot starts. Runs each step even if an earlier one failed:
+	/// * `anonymous_read` - An unauthorized request to `www.reddit.com`, which fails if reddit
+	/// can't be reached or blocks the user agent
+	/// * `token` - Gets a new token for scripts and installed apps that can refresh their token,
+	/// which fails for a wrong client id or secret. Other installed apps only check that their
+	/// token hasn't expired. Expiration is measured with a monotonic clock, so it doesn't depend
+	/// on the system clock being right
+	/// * `authorized_read` - An authorized request for the user currently authorized
+	/// * `ratelimit` - Checks the ratelimit headers of the authorized request
+	///
+	/// Note: requires connection to be authorized for all steps but the first to pass
+	pub fn self_test(&self) -> SelfTestReport {
+		let anonymous_read = SelfTestStep::run("anonymous_read", || {
+			let req = Request::get(json_url("https://www.reddit.com/api/v1/scopes")).body(Body::empty()).unwrap();
+			self.conn.run_request(req).map(|_| ())
+		});
+
+		let token = SelfTestStep::run("token", || match *self.conn.auth.borrow() {
+			Some(OAuth::InstalledApp { ref refresh_token, ref expire_instant, .. }) if refresh_token.borrow().is_none() => {
+				if expire_instant.get().is_some_and(|expire| Instant::now() > expire) {
+					Err(format_err!("The token expired and can't be refreshed"))
+				} else {
+					Ok(())
+				}
+			}
+			Some(ref auth) => auth.refresh(&self.conn),
+			None => Err(format_err!("The connection is not authorized")),
+		});
+
+		let authorized_read = SelfTestStep::run("authorized_read", || match self.get_self()?["name"].as_str() {
+			Some(_) => Ok(()),
+			None => Err(Error::from(RedditError::AuthError)),
+		});
+
+		let limits = self.conn.ratelimit_state();
+		let reset = limits.remaining.map(|_| limits.reset_time.saturating_duration_since(Instant::now()));
+		let ratelimit = SelfTestStep::run("ratelimit", || check_ratelimit(limits.remaining, reset));
+
+		SelfTestReport {
+			steps: vec![anonymous_read, token, authorized_read, ratelimit],
+			ratelimit_used: limits.reqs,
+			ratelimit_remaining: limits.remaining,
+			ratelimit_reset: reset,
+		}
+	}
+
+	/// Get the OAuth scopes reddit supports, for instance to show users what an installed app
+	/// will be allowed to do
+	/// # Returns
+	/// A result with a map of the id of each scope, like `"identity"`, to its info
+	pub fn available_scopes(&self) -> Result<HashMap<String, ScopeInfo>, Error> {
+		let req = Request::get(json_url(&format!("{}/api/v1/scopes", self.conn.read_host()))).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_read_request(req)?;
+		scope_infos(&resp)
+	}
 }
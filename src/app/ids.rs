@@ -0,0 +1,129 @@
+use failure::Error;
+
+use RedditError;
+
+/// Longest id reddit can hand out, which is `u64::MAX` in base36
+const MAX_ID_LEN: usize = 13;
+
+/// The kinds of things reddit identifies with ids. The fullname of a thing is the prefix of its
+/// kind followed by its id, e.g. `t3_7le01h` for the post with the id `7le01h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Kind {
+	Comment,
+	Account,
+	Link,
+	Message,
+	Subreddit,
+	Award,
+}
+
+impl Kind {
+	/// Every kind, for endpoints that take the fullname of any thing
+	pub(crate) const ALL: [Kind; 6] = [Kind::Comment, Kind::Account, Kind::Link, Kind::Message, Kind::Subreddit, Kind::Award];
+
+	/// The prefix of the fullnames of things of the kind
+	pub(crate) fn prefix(self) -> &'static str {
+		match self {
+			Kind::Comment => "t1_",
+			Kind::Account => "t2_",
+			Kind::Link => "t3_",
+			Kind::Message => "t4_",
+			Kind::Subreddit => "t5_",
+			Kind::Award => "t6_",
+		}
+	}
+
+	fn name(self) -> &'static str {
+		match self {
+			Kind::Comment => "comment",
+			Kind::Account => "account",
+			Kind::Link => "post",
+			Kind::Message => "message",
+			Kind::Subreddit => "subreddit",
+			Kind::Award => "award",
+		}
+	}
+}
+
+/// Whether a string is an id as reddit hands them out, which is a lowercase base36 number
+pub(crate) fn is_id(id: &str) -> bool {
+	!id.is_empty() && id.len() <= MAX_ID_LEN && id.bytes().all(|b| b.is_ascii_digit() || b.is_ascii_lowercase())
+}
+
+/// Splits a fullname into the kind and id of the thing
+pub(crate) fn parse_fullname(fullname: &str) -> Option<(Kind, &str)> {
+	Kind::ALL.iter().find_map(|&kind| fullname.strip_prefix(kind.prefix()).filter(|id| is_id(id)).map(|id| (kind, id)))
+}
+
+/// The id of a thing for endpoints that take ids, mostly in their path like `/comments/{id}`.
+/// The fullname of a thing of the kind is converted to its id.
+/// # Arguments
+/// * `param` - Name of the parameter the id was passed as, for the error
+/// * `kind` - Kind of thing the endpoint takes
+/// * `id` - Id or fullname of the thing
+pub(crate) fn bare_id<'a>(param: &str, kind: Kind, id: &'a str) -> Result<&'a str, Error> {
+	if is_id(id) {
+		return Ok(id);
+	}
+	match parse_fullname(id) {
+		Some((found, bare)) if found == kind => Ok(bare),
+		_ => Err(invalid(param, &format!("the id or fullname of a {}", kind.name()), id)),
+	}
+}
+
+/// The fullname of a thing for endpoints that take fullnames, like `/api/info`, which silently
+/// find nothing for ids. An id is converted to the fullname of the thing of the kind with it.
+/// # Arguments
+/// * `param` - Name of the parameter the fullname was passed as, for the error
+/// * `kind` - Kind of thing the endpoint takes
+/// * `id` - Id or fullname of the thing
+pub(crate) fn fullname(param: &str, kind: Kind, id: &str) -> Result<String, Error> {
+	bare_id(param, kind, id).map(|bare| format!("{}{}", kind.prefix(), bare))
+}
+
+/// The fullname of a thing for endpoints that take fullnames of several kinds of things, like
+/// commenting on posts and comments. Ids are only converted if there is a single kind, since
+/// which thing an id belongs to is ambiguous otherwise.
+/// # Arguments
+/// * `param` - Name of the parameter the fullname was passed as, for the error
+/// * `kinds` - Kinds of things the endpoint takes
+/// * `id` - Fullname of the thing
+pub(crate) fn fullname_of(param: &str, kinds: &[Kind], id: &str) -> Result<String, Error> {
+	if let [kind] = *kinds {
+		return fullname(param, kind, id);
+	}
+	match parse_fullname(id) {
+		Some((found, _)) if kinds.contains(&found) => Ok(id.to_string()),
+		_ => {
+			let names: Vec<&str> = kinds.iter().map(|kind| kind.name()).collect();
+			Err(invalid(param, &format!("the fullname of a {}", names.join(" or ")), id))
+		}
+	}
+}
+
+/// An id of something that has no fullname, like a new modmail conversation
+/// # Arguments
+/// * `param` - Name of the parameter the id was passed as, for the error
+/// * `id` - Id of the thing
+pub(crate) fn plain_id<'a>(param: &str, id: &'a str) -> Result<&'a str, Error> {
+	if is_id(id) {
+		Ok(id)
+	} else {
+		Err(invalid(param, "an id", id))
+	}
+}
+
+/// The ids of things joined with commas, for endpoints that take lists of ids like
+/// `/api/morechildren`. Fullnames of things of the kind are converted to their ids.
+pub(crate) fn bare_id_list(param: &str, kind: Kind, ids: &[&str]) -> Result<String, Error> {
+	let ids = ids.iter().map(|id| bare_id(param, kind, id)).collect::<Result<Vec<&str>, Error>>()?;
+	Ok(ids.join(","))
+}
+
+fn invalid(param: &str, expected: &str, got: &str) -> Error {
+	Error::from(RedditError::BadRequest {
+		request: format!("Parameter {} needs {}, got {:?}", param, expected, got),
+		response: "not sent".to_string(),
+		status: None,
+	})
+}
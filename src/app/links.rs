@@ -1,57 +1,105 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::thread;
+use std::time::Instant;
 
+use chrono::{self, Utc};
 use failure::Error;
-use hyper::Request;
-use json::Value;
+use hyper::{Body, Request};
 use url::form_urlencoded;
 
-use data::{Comment, Listing};
+use app::ids::{self, Kind};
+use app::pacing::{paced, ratelimit_wait, PACING_RETRIES};
+use data::{find_duplicate, Comment, DuplicateGuard, Listing, Post, PostRequirements, SubmitCheck, SubmitKind, SubmitResult, Thing};
+use net::{check_json_errors, json_url, uri_params_from_map};
 use {App, RedditError};
 
 impl App {
-	/// Comment on a thing. The `thing` can be a post, a comment, or a private message
+	/// Comment on a thing. The `thing` can be a post, a comment, or a private message. Fails with
+	/// `RedditError::RateLimited` during the comment cooldown of the account, unless waiting for
+	/// the cooldown is turned on with `set_wait_for_comment_cooldown`. The cooldowns of subreddits
+	/// are tracked for `estimated_comment_wait`.
 	/// # Arguments
 	/// * `text` - The body of the comment
-	/// * `thing` - Fullname of the thing to comment on
+	/// * `thing` - Fullname of the post, comment or message to comment on
 	pub fn comment(&self, text: &str, thing: &str) -> Result<(), Error> {
-		let body = form_urlencoded::Serializer::new(String::new()).append_pair("text", text).append_pair("thing_id", thing).finish();
+		let thing = &ids::fullname_of("thing", &[Kind::Link, Kind::Comment, Kind::Message], thing)?;
+		let mut tries = 0;
+		loop {
+			let wait = match self.send_comment(text, thing) {
+				Err(e) => match ratelimit_wait(&e) {
+					Some(wait) => wait,
+					None => return Err(e),
+				},
+				Ok(()) => return Ok(()),
+			};
 
-		let req = Request::post("https://oauth.reddit.com/api/comment").body(body.into()).unwrap();
+			// The subreddit isn't in the response to a ratelimited comment
+			let sub = self.subreddit_of(thing);
+			if let Some(ref sub) = sub {
+				self.record_comment_cooldown(sub, |cooldown| cooldown.record_ratelimit(wait, Instant::now()));
+			}
+			if !self.wait_for_comment_cooldown.get() || tries >= PACING_RETRIES {
+				return Err(Error::from(RedditError::RateLimited { wait }));
+			}
+
+			let wait = sub.and_then(|sub| self.estimated_comment_wait(&sub)).unwrap_or(wait);
+			debug!("Waiting {:?} for the comment cooldown", wait);
+			thread::sleep(wait);
+			tries += 1;
+		}
+	}
 
-		self.conn.run_auth_request(req)?;
+	fn send_comment(&self, text: &str, thing: &str) -> Result<(), Error> {
+		let body = form_urlencoded::Serializer::new(String::new()).append_pair("api_type", "json").append_pair("text", text).append_pair("thing_id", thing).finish();
+
+		let req = Request::post(json_url("https://oauth.reddit.com/api/comment")).body(body.into()).unwrap();
+		let req_str = format!("{:?}", req);
+
+		let resp = self.conn.run_auth_request(req)?;
+		check_json_errors(&req_str, &resp)?;
+
+		if let Some(sub) = resp["json"]["data"]["things"][0]["data"]["subreddit"].as_str() {
+			self.record_comment_cooldown(sub, |cooldown| cooldown.record_success(Instant::now()));
+		}
 		Ok(())
 	}
 
-	/// Load more comments from a comment tree that is not completely loaded. This function at the moment can only be called
-	/// internally due to requiring `morechildren_id` that is not available in the `Thread` type.
+	/// Get the subreddit a thing is in, `None` for things outside of subreddits like private
+	/// messages or if it can't be loaded
+	fn subreddit_of(&self, fullname: &str) -> Option<String> {
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		params.insert("id", fullname);
+
+		let req = Request::get(uri_params_from_map(&json_url("https://oauth.reddit.com/api/info"), &params).ok()?).body(Body::empty()).unwrap();
+		let resp = self.conn.run_auth_request(req).ok()?;
+		resp["data"]["children"][0]["data"]["subreddit"].as_str().map(|sub| sub.to_string())
+	}
+
+	/// Comment on several things, spacing the comments by the comment cooldown of the account.
+	/// Comments that are ratelimited are retried after the time reddit asks to wait.
 	/// # Arguments
-	/// * `link_id` - The id of the post that has the comments that are being loaded
+	/// * `replies` - Pairs of the fullname of the thing to comment on and the body of the comment
+	/// # Returns
+	/// The result of each comment, in order, so comments that were posted are known even if others
+	/// failed
+	pub fn comment_many(&self, replies: &[(&str, &str)]) -> Vec<Result<(), Error>> {
+		paced(replies, |&(thing, text)| self.comment(text, thing))
+	}
+
+	/// Load more comments from a comment tree that is not completely loaded. The ids needed are
+	/// the `id` and `children` of a `MoreComments` stub.
+	/// # Arguments
+	/// * `link_id` - The id or fullname of the post that has the comments that are being loaded
 	/// * `morechildren_id` - The id of the morechildren object that is being loaded
-	/// * `comments` - Slice of `&str`s that are the ids of the comments to be loaded
+	/// * `comments` - Slice of `&str`s that are the ids or fullnames of the comments to be loaded
 	pub fn more_children(&self, link_id: &str, morechildren_id: &str, comments: &[&str]) -> Result<Listing<Comment>, Error> {
-		let mut string = String::from("t3_");
-		let link_id = if !link_id.starts_with("t3_") {
-			string.push_str(link_id);
-			&string
-		} else {
-			link_id
-		};
+		// The endpoint takes the fullname of the post, but ids of the comments
+		let link_id = &ids::fullname("link_id", Kind::Link, link_id)?;
+		let morechildren_id = ids::bare_id("morechildren_id", Kind::Comment, morechildren_id)?;
 
 		let limit = 5;
 		// Break requests into chunks of `limit`
-		let mut chunks: Vec<String> = Vec::new();
-		let mut chunk_buf = String::new();
-		for (i, id) in comments.iter().enumerate() {
-			if i != 0 && i % limit == 0 {
-				chunk_buf.pop(); // Removes trailing comma
-				chunks.push(chunk_buf);
-				chunk_buf = String::new();
-			}
-
-			chunk_buf.push_str(&format!("{},", id));
-		}
-		chunk_buf.pop(); // Removes trailing comma on unfinished chunk
-		chunks.push(chunk_buf);
+		let chunks = comments.chunks(limit).map(|chunk| ids::bare_id_list("comments", Kind::Comment, chunk)).collect::<Result<Vec<String>, Error>>()?;
 
 		trace!("Chunks are {:?}", chunks);
 
@@ -67,8 +115,8 @@ impl App {
 			trace!("Getting more children {} from {}", chunk, link_id);
 
 			//let mut req = Request::new(Method::Get, Url::parse_with_params("https://www.reddit.com/api/morechildren/.json", params)?.into_string().parse()?);
-			let req = Request::post("https://www.reddit.com/api/morechildren/.json").body(body.into()).unwrap();
-			let data = self.conn.run_request(req)?;
+			let req = Request::post(json_url(&format!("{}/api/morechildren", self.conn.read_host()))).body(body.into()).unwrap();
+			let data = self.conn.run_read_request(req)?;
 
 			trace!("Scanning {}", data);
 
@@ -94,8 +142,9 @@ impl App {
 	/// # Arguments
 	/// * `sticky` - boolean value. True to set post as sticky, false to unset post as sticky
 	/// * `slot` - Optional slot number to fill (can only be 1 or 2, and will error otherwise)
-	/// * `id` - _fullname_ of the post to sticky
+	/// * `id` - Id or fullname of the post to sticky
 	pub fn set_sticky(&self, sticky: bool, slot: Option<i32>, id: &str) -> Result<(), Error> {
+		let id = &ids::fullname("id", Kind::Link, id)?;
 		let mut body = form_urlencoded::Serializer::new(String::new());
 		body.append_pair("state", if sticky { "1" } else { "0" });
 
@@ -104,6 +153,7 @@ impl App {
 				return Err(Error::from(RedditError::BadRequest {
 					request: "Sticky's are limited to slots 1 and 2".to_string(),
 					response: "not sent".to_string(),
+					status: None,
 				}));
 			}
 			let numstr = num.to_string();
@@ -112,13 +162,37 @@ impl App {
 
 		body.append_pair("id", id);
 
-		let req = Request::post("https://oauth.reddit.com/api/set_subreddit_sticky/.json").body(body.finish().into()).unwrap();
+		let req = Request::post(json_url("https://oauth.reddit.com/api/set_subreddit_sticky")).body(body.finish().into()).unwrap();
 
 		self.conn.run_auth_request(req).ok();
 
 		Ok(())
 	}
 
+	/// Mark a post as original content or unmark it. Fails with `RedditError::Forbidden` if the
+	/// user is neither the author of the post nor a moderator of its subreddit
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `fullname` - Id or fullname of the post
+	/// * `is_oc` - True to mark the post as original content, false to unmark it
+	pub fn set_oc(&self, fullname: &str, is_oc: bool) -> Result<(), Error> {
+		// Only posts can be marked as original content
+		let fullname = &ids::fullname("fullname", Kind::Link, fullname)?;
+
+		let body = form_urlencoded::Serializer::new(String::new())
+			.append_pair("api_type", "json")
+			.append_pair("fullname", fullname)
+			.append_pair("should_set_oc", if is_oc { "true" } else { "false" })
+			.finish();
+
+		let req = Request::post(json_url("https://oauth.reddit.com/api/set_original_content")).body(body.into()).unwrap();
+		let req_str = format!("{:?}", req);
+
+		let resp = self.conn.run_auth_request(req)?;
+		check_json_errors(&req_str, &resp)
+	}
+
 	/// Submit a self post
 	/// # Arguments
 	/// * `sub` - Name of the subreddit to submit a post to
@@ -126,9 +200,10 @@ impl App {
 	/// * `text` - Body of the post
 	/// * `sendreplies` - Whether replies should be forwarded to the inbox of the submitter
 	/// # Returns
-	/// A result with reddit's json response to the submission
-	pub fn submit_self(&self, sub: &str, title: &str, text: &str, sendreplies: bool) -> Result<Value, Error> {
+	/// A result with the fullname and url of the new post
+	pub fn submit_self(&self, sub: &str, title: &str, text: &str, sendreplies: bool) -> Result<SubmitResult, Error> {
 		let body = form_urlencoded::Serializer::new(String::new())
+			.append_pair("api_type", "json")
 			.append_pair("sr", sub)
 			.append_pair("kind", "self")
 			.append_pair("title", title)
@@ -136,8 +211,120 @@ impl App {
 			.append_pair("sendreplies", if sendreplies { "true" } else { "false" })
 			.finish();
 
-		let req = Request::post("https://oauth.reddit.com/api/submit/.json").body(body.into()).unwrap();
+		self.submit(sub, title, body)
+	}
+
+	/// Submit a link post
+	/// # Arguments
+	/// * `sub` - Name of the subreddit to submit a post to
+	/// * `title` - Title of the post
+	/// * `url` - Url the post links to
+	/// * `sendreplies` - Whether replies should be forwarded to the inbox of the submitter
+	/// # Returns
+	/// A result with the fullname and url of the new post
+	pub fn submit_link(&self, sub: &str, title: &str, url: &str, sendreplies: bool) -> Result<SubmitResult, Error> {
+		let body = form_urlencoded::Serializer::new(String::new())
+			.append_pair("api_type", "json")
+			.append_pair("sr", sub)
+			.append_pair("kind", "link")
+			.append_pair("title", title)
+			.append_pair("url", url)
+			.append_pair("sendreplies", if sendreplies { "true" } else { "false" })
+			.finish();
+
+		self.submit(sub, title, body)
+	}
+
+	/// Submit a self post to the profile of the user currently authorized
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `title` - Title of the post
+	/// * `text` - Body of the post
+	/// * `sendreplies` - Whether replies should be forwarded to the inbox of the submitter
+	/// # Returns
+	/// A result with the fullname and url of the new post
+	pub fn submit_self_to_profile(&self, title: &str, text: &str, sendreplies: bool) -> Result<SubmitResult, Error> {
+		self.submit_self(&self.profile_subreddit()?, title, text, sendreplies)
+	}
+
+	/// Submit a link post to the profile of the user currently authorized
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `title` - Title of the post
+	/// * `url` - Url the post links to
+	/// * `sendreplies` - Whether replies should be forwarded to the inbox of the submitter
+	/// # Returns
+	/// A result with the fullname and url of the new post
+	pub fn submit_link_to_profile(&self, title: &str, url: &str, sendreplies: bool) -> Result<SubmitResult, Error> {
+		self.submit_link(&self.profile_subreddit()?, title, url, sendreplies)
+	}
+
+	fn submit(&self, sub: &str, title: &str, body: String) -> Result<SubmitResult, Error> {
+		if let Some(guard) = self.duplicate_guard.get() {
+			if let Some(existing) = self.find_duplicate_submission(sub, title, guard)? {
+				return Err(Error::from(RedditError::AlreadySubmitted { existing: Box::new(existing) }));
+			}
+		}
+
+		let req = Request::post(json_url("https://oauth.reddit.com/api/submit")).body(body.into()).unwrap();
+		let req_str = format!("{:?}", req);
+
+		let resp = self.conn.run_auth_request(req)?;
+		check_json_errors(&req_str, &resp)?;
+		SubmitResult::from_value(&resp, self)
+	}
+
+	fn find_duplicate_submission(&self, sub: &str, title: &str, guard: DuplicateGuard) -> Result<Option<Post>, Error> {
+		let me = self.get_self()?;
+		let name = match me["name"].as_str() {
+			Some(name) => name,
+			None => return Err(Error::from(RedditError::AuthError)),
+		};
+
+		let limit = guard.limit.min(100).to_string();
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		params.insert("sort", "new");
+		params.insert("limit", &limit);
+		let req = Request::get(uri_params_from_map(&json_url(&format!("https://oauth.reddit.com/user/{}/submitted", name)), &params)?).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		// No bound if the lookback is too long to subtract from the current time
+		let since = chrono::Duration::from_std(guard.lookback).ok().and_then(|lookback| Utc::now().checked_sub_signed(lookback));
+		find_duplicate(&resp, sub, title, since, self)
+	}
+
+	/// Get the requirements a subreddit places on new posts, like title lengths and allowed domains
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	pub fn get_post_requirements(&self, sub: &str) -> Result<PostRequirements, Error> {
+		let req = Request::get(json_url(&format!("https://oauth.reddit.com/api/v1/{}/post_requirements", sub))).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		PostRequirements::from_value(&resp, self)
+	}
+
+	/// Predict whether the user currently authorized can submit a kind of post to a subreddit,
+	/// without spending a (heavily ratelimited) submission on finding out. This checks suspension,
+	/// bans, approved submitter restrictions, the allowed kinds of posts and the body requirements
+	/// of the subreddit. Karma and account age limits usually live in AutoModerator rules, which
+	/// are not visible through the api and can't be checked.
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	/// * `kind` - Kind of the post to submit
+	/// # Returns
+	/// A result with `SubmitCheck::Denied` and the reason if the submission would be rejected
+	pub fn can_submit(&self, sub: &str, kind: SubmitKind) -> Result<SubmitCheck, Error> {
+		let account = self.get_self()?;
+		let req = Request::get(json_url(&format!("https://oauth.reddit.com/r/{}/about", sub))).body(Body::empty()).unwrap();
+		let about = self.conn.run_auth_request(req)?;
+		let requirements = self.get_post_requirements(sub)?;
 
-		self.conn.run_auth_request(req)
+		Ok(SubmitCheck::evaluate(sub, kind, &about, &account, &requirements))
 	}
 }
@@ -1,68 +1,309 @@
 use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
 use failure::Error;
+use hyper::header::{self, HeaderValue};
 use hyper::{Body, Request};
 use json::Value;
 use url::{form_urlencoded, Url};
 
-use data::{Comment, Comments, Listing, Post, Thing};
-use net::uri_params_from_map;
-use {App, Sort};
+use app::ids::{self, Kind};
+use data::{
+	check_listing, comment_listing, empty_listing_error, info_available, posts_since, rank_by_score, AwardInfo, Comment, CommentTree, Comments, HistoryCursor, Listing, Pages, Post, PostComments, PostStats, SearchWindow, SortTime, SubredditHistory, Target, Thing, MAX_CROSSPOST_DEPTH,
+	SEARCH_RESULT_CEILING,
+};
+use export::JsonlWriter;
+use net::{json_url, uri_params_from_map};
+use {App, RedditError, Sort};
 
 impl App {
 	/// Loads a thing and casts it to the type of anything as long as it implements the Thing trait. Experimental
 	/// # Arguments
-	/// * `fullame` - fullname of the thing
+	/// * `fullame` - Id or fullname of the post
 	pub fn load_post(&self, fullname: &str) -> Result<Post, Error> {
-		let mut params: HashMap<&str, &str> = HashMap::new();
-		params.insert("names", fullname);
+		// `/by_id` takes fullnames
+		let fullname = ids::fullname("fullname", Kind::Link, fullname)?;
 
-		let req = Request::get(format!("https://www.reddit.com/by_id/{}/.json", fullname)).body(Body::empty()).unwrap();
-		let response = self.conn.run_request(req)?;
+		let req = Request::get(json_url(&format!("{}/by_id/{}", self.conn.read_host(), fullname))).body(Body::empty()).unwrap();
+		let response = self.conn.run_read_request(req)?;
 
 		Post::from_value(&response, self)
 	}
 
+	/// Get the comment count, score and upvote ratio of a post without loading its comments, for
+	/// instance to poll the engagement of a post
+	/// # Arguments
+	/// * `post` - The id or fullname of the post
+	pub fn get_post_stats(&self, post: &str) -> Result<PostStats, Error> {
+		// `/api/info` finds nothing for ids, it takes fullnames
+		let fullname = ids::fullname("post", Kind::Link, post)?;
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		params.insert("id", &fullname);
+
+		let req = Request::get(uri_params_from_map(&json_url(&format!("{}/api/info", self.conn.read_host())), &params)?).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_read_request(req)?;
+		let thing = &resp["data"]["children"][0];
+		if thing.is_null() {
+			return Err(Error::from(RedditError::NotFound { request: fullname }));
+		}
+
+		PostStats::from_value(thing, self)
+	}
+
+	/// Loads a post with all of its comments as a `CommentTree`, for instance to archive it
+	/// # Arguments
+	/// * `fullname` - Id or fullname of the post
+	pub fn get_comment_tree_archive(&self, fullname: &str) -> Result<CommentTree, Error> {
+		Ok(CommentTree::new(self.load_post(fullname)?))
+	}
+
+	/// Walk all posts submitted to a subreddit in a window of time, oldest first. Unlike listings,
+	/// which end after about 1000 posts, this reaches every post reddit's search finds. The walk
+	/// can be saved with `SubredditHistory::cursor` and continued with `SubredditHistory::resume`.
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	/// * `from` - Start of the window, inclusive
+	/// * `to` - End of the window, inclusive
+	/// # Returns
+	/// An iterator over the posts, without their comments loaded
+	pub fn walk_subreddit_history(&self, sub: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> SubredditHistory<'_> {
+		SubredditHistory::new(self, sub, HistoryCursor::new(from, to))
+	}
+
 	/// Get the posts in a subreddit sorted in a specific way
 	/// # Arguments
-	/// * `sub` - Name of subreddit to query
+	/// * `target` - Subreddit to query, or a `Target` like `r/all`
+	/// * `sort` - Sort method of query. `Sort::Best` is only available for `Target::Frontpage`
+	/// # Returns
+	/// A result containing a json listing of posts, which is empty if the subreddit has no posts.
+	/// Fails with `RedditError::SubredditPrivate`, `RedditError::SubredditBanned` or
+	/// `RedditError::SubredditNotFound` if the posts can't be retrieved because of the state of the
+	/// subreddit, and with `RedditError::BadResponse` if the response is not a listing
+	pub fn get_posts<T: Into<Target>>(&self, target: T, sort: Sort) -> Result<Value, Error> {
+		self.get_regional_posts(target, sort, None)
+	}
+
+	/// Get the posts in `r/popular` or `r/all` that are popular in a region
+	/// # Arguments
+	/// * `target` - `Target::Popular` or `Target::All`
 	/// * `sort` - Sort method of query
+	/// * `geo_filter` - Optional region, one of `GEO_FILTERS` like `"GLOBAL"` or `"DE"`. Fails with
+	/// `RedditError::BadRequest` for other regions, or if given for other targets
+	/// # Returns
+	/// A result containing a json listing of posts
+	pub fn get_regional_posts<T: Into<Target>>(&self, target: T, sort: Sort, geo_filter: Option<&str>) -> Result<Value, Error> {
+		let target = target.into();
+		reject_front_page_sort(&target, sort)?;
+
+		let mut params = sort.param();
+		if let Some(geo_filter) = geo_filter {
+			target.check_geo_filter(geo_filter)?;
+			params.push(("geo_filter", geo_filter));
+		}
+		if self.include_hidden.get() {
+			params.push(("show", "all"));
+		}
+
+		let req = Request::get(Url::parse_with_params(&json_url(&format!("{}{}", self.conn.read_host(), target.path())), params)?.into_string()).body(Body::empty()).unwrap();
+		let req_str = req.uri().to_string();
+
+		let resp = check_listing(self.conn.run_read_request(req)?, &req_str)?;
+		self.check_subreddit_listing(&target, resp)
+	}
+
+	/// The url of the newest posts of a target, 100 at a time, which `BotRunner` polls. The sort
+	/// is part of the path, since reddit ignores the `sort` parameter of subreddit listings
+	pub(crate) fn new_posts_url(&self, target: &Target) -> Result<Url, Error> {
+		let mut params = vec![("limit", "100")];
+		if self.include_hidden.get() {
+			params.push(("show", "all"));
+		}
+		Ok(Url::parse_with_params(&json_url(&format!("{}{}/{}", self.conn.read_host(), target.path(), Sort::New.path())), params)?)
+	}
+
+	/// Get a listing of the newest posts of a target, see `new_posts_url`
+	pub(crate) fn get_new_posts(&self, target: &Target) -> Result<Value, Error> {
+		let url = self.new_posts_url(target)?;
+		let req = Request::get(url.as_str()).body(Body::empty()).unwrap();
+
+		let resp = check_listing(self.conn.run_read_request(req)?, url.as_str())?;
+		self.check_subreddit_listing(target, resp)
+	}
+
+	/// Fails with the reason a subreddit can't be read if its listing is empty, see
+	/// `empty_listing_error`
+	fn check_subreddit_listing(&self, target: &Target, resp: Value) -> Result<Value, Error> {
+		let sub = match *target {
+			Target::Subreddit(ref sub) if resp["data"]["children"].as_array().is_some_and(|children| children.is_empty()) => sub,
+			_ => return Ok(resp),
+		};
+
+		let req = Request::get(json_url(&format!("{}/r/{}/about", self.conn.read_host(), sub))).body(Body::empty()).unwrap();
+		match empty_listing_error(&resp, sub, self.conn.run_read_request(req)) {
+			Some(e) => Err(e),
+			None => Ok(resp),
+		}
+	}
+
+	/// Get a iterator of all posts of a subreddit sorted in a specific way, loading pages as
+	/// needed
+	/// # Arguments
+	/// * `target` - Subreddit to query, or a `Target` like `r/all`
+	/// * `sort` - Sort method of query. `Sort::Best` is only available for `Target::Frontpage`
+	/// # Returns
+	/// A result with the iterator over the json of the posts
+	pub fn get_all_posts<T: Into<Target>>(&self, target: T, sort: Sort) -> Result<Pages<'_>, Error> {
+		let target = target.into();
+		reject_front_page_sort(&target, sort)?;
+
+		let mut params: Vec<(String, String)> = sort.param().into_iter().filter(|(key, _)| *key != "sort").map(|(key, value)| (key.to_string(), value.to_string())).collect();
+		if self.include_hidden.get() {
+			params.push(("show".to_string(), "all".to_string()));
+		}
+
+		Ok(Pages::new(self, &json_url(&format!("{}{}/{}", self.conn.read_host(), target.path(), sort.path())), params))
+	}
+
+	/// Get the highest scoring posts of a subreddit in a window of time reddit sorts by, like the
+	/// last day. Loads as many pages as needed for `n` posts
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	/// * `window` - The window of time the posts were submitted in
+	/// * `n` - Amount of posts to get
+	/// # Returns
+	/// At most `n` posts, highest score first, with posts of the same score ordered oldest first.
+	/// The comments of the posts are not loaded
+	pub fn top_posts(&self, sub: &str, window: SortTime, n: usize) -> Result<Vec<Post>, Error> {
+		let mut posts = Vec::new();
+		for post in self.get_all_posts(sub, Sort::Top(window))?.take(n) {
+			posts.push(Post::from_data(&post?["data"], self, MAX_CROSSPOST_DEPTH)?);
+		}
+
+		rank_by_score(&mut posts);
+		Ok(posts)
+	}
+
+	/// Get the highest scoring posts of a subreddit that were submitted after a time, for windows
+	/// reddit doesn't sort by, like the last 6 hours. Every post of the window is loaded from the
+	/// new posts of the subreddit and sorted by score, so long windows of busy subreddits need many
+	/// requests (and reddit doesn't list more than about 1000 new posts).
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	/// * `since` - The start of the window
+	/// * `n` - Amount of posts to get
+	/// # Returns
+	/// At most `n` posts, ordered like `top_posts`. The comments of the posts are not loaded
+	pub fn top_posts_since(&self, sub: &str, since: DateTime<Utc>, n: usize) -> Result<Vec<Post>, Error> {
+		let mut posts = posts_since(self.get_all_posts(sub, Sort::New)?, since, self)?;
+
+		rank_by_score(&mut posts);
+		posts.truncate(n);
+		Ok(posts)
+	}
+
+	/// Writes the json of the posts of a subreddit to an export, continuing after the resume token
+	/// of the export if it has one. The export is flushed after every page of posts, so an
+	/// interrupted export can be continued with a writer for the same path.
+	/// # Arguments
+	/// * `target` - Subreddit to export, or a `Target` like `r/all`
+	/// * `sort` - Sort method of the posts
+	/// * `limit` - Optional maximum amount of posts to write
+	/// * `writer` - The export to write the posts to
+	/// # Returns
+	/// The amount of posts written
+	pub fn export_subreddit_posts<T: Into<Target>>(&self, target: T, sort: Sort, limit: Option<usize>, writer: &mut JsonlWriter<Value>) -> Result<usize, Error> {
+		let resume = writer.resume_token().map(|t| t.to_string());
+		let mut pages = self.get_all_posts(target, sort)?.start_after(resume.as_deref());
+
+		let mut written = 0;
+		let mut loaded = pages.loaded_count();
+		while written < limit.unwrap_or(usize::MAX) {
+			let post = match pages.next() {
+				Some(post) => post?,
+				None => break,
+			};
+			// Loading a new page means all posts of the previous one were written
+			if pages.loaded_count() != loaded {
+				writer.flush()?;
+				loaded = pages.loaded_count();
+			}
+			match post["data"]["name"].as_str() {
+				Some(name) => writer.write_named(name, &post)?,
+				None => writer.write(&post)?,
+			}
+			written += 1;
+		}
+
+		writer.flush()?;
+		Ok(written)
+	}
+
+	/// Search for the posts submitted to a subreddit in a window of time, using the cloudsearch
+	/// syntax. Reddit returns at most `SEARCH_RESULT_CEILING` results for a search, so if the window
+	/// contains more posts the result is not complete, and only covers the window from its `start`
+	/// to `end`. The rest can be found by searching the remaining window, e.g. by bisecting it.
+	/// # Arguments
+	/// * `target` - Subreddit to search, or a `Target` like `r/all`
+	/// * `start` - Start of the window, inclusive
+	/// * `end` - End of the window, inclusive
+	pub fn search_by_time<T: Into<Target>>(&self, target: T, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<SearchWindow, Error> {
+		let target = target.into();
+		let mut params = vec![
+			("q".to_string(), format!("timestamp:{}..{}", start.timestamp(), end.timestamp())),
+			("syntax".to_string(), "cloudsearch".to_string()),
+			("sort".to_string(), "new".to_string()),
+			("include_over_18".to_string(), "on".to_string()),
+		];
+		if let Target::Subreddit(_) = target {
+			params.push(("restrict_sr".to_string(), "on".to_string()));
+		}
+
+		let mut posts = Vec::new();
+		for post in Pages::new(self, &json_url(&format!("{}{}/search", self.conn.read_host(), target.path())), params).take(SEARCH_RESULT_CEILING) {
+			posts.push(post?);
+		}
+
+		Ok(SearchWindow::new(posts, start, end))
+	}
+
+	/// Get the posts on the front page of the user currently authorized sorted in a specific way
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `sort` - Sort method of query. `Sort::Best` is only available here
 	/// # Returns
 	/// A result containing a json listing of posts
-	pub fn get_posts(&self, sub: &str, sort: Sort) -> Result<Value, Error> {
-		let req = Request::get(
-			Url::parse_with_params(
-				&format!(
-					"https://www.reddit.com/r/{}/.\
-					 json",
-					sub
-				),
-				sort.param(),
-			)?
-			.into_string(),
-		)
-		.body(Body::empty())
-		.unwrap();
-
-		self.conn.run_request(req)
+	pub fn get_front_page(&self, sort: Sort) -> Result<Value, Error> {
+		let mut params = sort.param();
+		if self.include_hidden.get() {
+			params.push(("show", "all"));
+		}
+
+		let url = json_url(&format!("https://oauth.reddit.com/{}", sort.path()));
+		let req = Request::get(Url::parse_with_params(&url, params)?.into_string()).body(Body::empty()).unwrap();
+		let req_str = req.uri().to_string();
+
+		check_listing(self.conn.run_auth_request(req)?, &req_str)
 	}
 
 	/// Get a iterator of all comments in order of being posted
 	/// # Arguments
-	/// * `sub` - Name of the subreddit to pull comments from. Can be 'all' to pull from all of reddit
-	pub fn create_comment_stream(&self, sub: &str) -> Comments {
-		Comments::new(self, sub)
+	/// * `target` - Name of the subreddit to pull comments from, or a `Target` like `r/all`. Can
+	/// be 'all' to pull from all of reddit
+	pub fn create_comment_stream<T: Into<Target>>(&self, target: T) -> Comments {
+		Comments::new(self, target)
 	}
 
 	/// Gets the most recent comments in a subreddit. This function is also usually called internally but
 	/// can be called if a one time retrieval of recent comments from a subreddit is necessary
 	/// # Arguments
-	/// * `sub` - Subreddit to load recent comments from
+	/// * `target` - Subreddit to load recent comments from, or a `Target` like `r/all`
 	/// * `limit` - Optional limit to amount of comments loaded
 	/// * `before` - Optional comment to be the starting point for the next comments loaded
 	/// # Returns
 	/// A listing of comments that should be flat (no replies)
-	pub fn get_recent_comments(&self, sub: &str, limit: Option<i32>, before: Option<&str>) -> Result<Listing<Comment>, Error> {
+	pub fn get_recent_comments<T: Into<Target>>(&self, target: T, limit: Option<i32>, before: Option<&str>) -> Result<Listing<Comment>, Error> {
 		let limit_str;
 		let mut params: HashMap<&str, &str> = HashMap::new();
 		if let Some(limit) = limit {
@@ -73,10 +314,15 @@ impl App {
 			params.insert("before", before);
 		}
 
-		let req = Request::get(uri_params_from_map(&format!("https://www.reddit.com/r/{}/comments.json", sub), &params)?).body(Body::empty()).unwrap();
+		let target = target.into();
+		let req = Request::get(uri_params_from_map(&json_url(&format!("{}{}/comments", self.conn.read_host(), target.path())), &params)?).body(Body::empty()).unwrap();
+		let req_str = req.uri().to_string();
 
-		let resp = self.conn.run_request(req)?;
-		let comments = Listing::from_value(&resp["data"]["children"], "", self)?;
+		let resp = check_listing(self.conn.run_read_request(req)?, &req_str)?;
+		// Polling with `before` often finds nothing new, which says nothing about the subreddit
+		let resp = if before.is_none() { self.check_subreddit_listing(&target, resp)? } else { resp };
+		let mut comments = Listing::from_value(&resp["data"]["children"], "", self)?;
+		comments.read_envelope(&resp["data"]);
 
 		Ok(comments)
 	}
@@ -84,19 +330,173 @@ impl App {
 	/// Loads the comment tree of a post, returning a listing of the Comment enum, which can be
 	/// either Loaded or NotLoaded
 	/// # Arguments
-	/// * `post` - The name of the post to retrieve the tree from
+	/// * `post` - The id or fullname of the post to retrieve the tree from
 	/// # Returns
 	/// A fully populated listing of commments (no `more` values)
 	pub fn get_comment_tree(&self, post: &str) -> Result<Listing<Comment>, Error> {
 		// TODO add sorting and shit
+		let post = ids::bare_id("post", Kind::Link, post)?;
 
 		let max_int = "2147483648";
 		let body = form_urlencoded::Serializer::new(String::new()).append_pair("limit", max_int).append_pair("depth", max_int).finish();
-		let req = Request::get(format!("https://www.reddit.com/comments/{}/.json", post)).body(body.into()).unwrap();
+		let data = self.load_comment_listing(|| Request::get(json_url(&format!("{}/comments/{}", self.conn.read_host(), post))).body(body.clone().into()).unwrap())?;
 
-		let data = self.conn.run_request(req)?;
-		let data = data[1]["data"]["children"].clone();
+		Listing::from_value(&data, post, self)
+	}
+
+	/// Loads the newest comments of a post, with the replies to them that were loaded
+	/// # Arguments
+	/// * `post` - The id or fullname of the post
+	/// * `limit` - Optional limit to amount of comments loaded
+	pub fn get_newest_comments(&self, post: &str, limit: Option<i32>) -> Result<Listing<Comment>, Error> {
+		let post = ids::bare_id("post", Kind::Link, post)?;
+		let limit_str;
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		params.insert("sort", "new");
+		if let Some(limit) = limit {
+			limit_str = limit.to_string();
+			params.insert("limit", &limit_str);
+		}
+
+		let uri = uri_params_from_map(&json_url(&format!("{}/comments/{}", self.conn.read_host(), post)), &params)?;
+		let data = self.load_comment_listing(|| Request::get(uri.clone()).body(Body::empty()).unwrap())?;
 
 		Listing::from_value(&data, post, self)
 	}
+
+	/// Get a iterator of the comments of a post in order of being posted, polling the post for
+	/// new comments
+	/// # Arguments
+	/// * `post` - The id or fullname of the post
+	/// # Returns
+	/// The stream of comments, or `RedditError::BadRequest` if `post` is not the id or fullname of
+	/// a post, which would otherwise fail every poll
+	pub fn watch_post_comments(&self, post: &str) -> Result<PostComments<'_>, Error> {
+		Ok(PostComments::new(self, ids::bare_id("post", Kind::Link, post)?))
+	}
+
+	/// Loads the part of the comment tree of a post that is rooted at a specific comment
+	/// # Arguments
+	/// * `post_id` - The id or fullname of the post the comment is in
+	/// * `comment_id` - The id or fullname of the comment to use as the root of the tree
+	/// * `depth` - Optional maximum depth of the tree to load
+	/// # Returns
+	/// A listing containing the comment, with its replies populated
+	pub fn get_comment_subtree(&self, post_id: &str, comment_id: &str, depth: Option<u8>) -> Result<Listing<Comment>, Error> {
+		let post_id = ids::bare_id("post_id", Kind::Link, post_id)?;
+		let comment_id = ids::bare_id("comment_id", Kind::Comment, comment_id)?;
+		let depth_str;
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		params.insert("comment", comment_id);
+		if let Some(depth) = depth {
+			depth_str = depth.to_string();
+			params.insert("depth", &depth_str);
+		}
+
+		let uri = uri_params_from_map(&json_url(&format!("{}/comments/{}", self.conn.read_host(), post_id)), &params)?;
+		let data = self.load_comment_listing(|| Request::get(uri.clone()).body(Body::empty()).unwrap())?;
+
+		Listing::from_value(&data, post_id, self)
+	}
+
+	/// Loads the part of the comment tree of a post that leads to a specific comment, starting a
+	/// few parents above it
+	/// # Arguments
+	/// * `link_id` - The id or fullname of the post the comment is in
+	/// * `comment_id` - The id or fullname of the comment
+	/// * `context` - Amount of parents of the comment to include, at most 8
+	/// # Returns
+	/// A listing containing the highest parent loaded, with the replies leading to the comment
+	/// and the replies of the comment populated
+	pub fn get_comment_context(&self, link_id: &str, comment_id: &str, context: u32) -> Result<Listing<Comment>, Error> {
+		if context > 8 {
+			return Err(Error::from(RedditError::BadRequest {
+				request: format!("Context of {} parents, reddit includes at most 8", context),
+				response: "not sent".to_string(),
+				status: None,
+			}));
+		}
+
+		let post_id = ids::bare_id("link_id", Kind::Link, link_id)?;
+		let comment_id = ids::bare_id("comment_id", Kind::Comment, comment_id)?;
+		let context_str = context.to_string();
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		params.insert("comment", comment_id);
+		params.insert("context", &context_str);
+
+		let uri = uri_params_from_map(&json_url(&format!("{}/comments/{}", self.conn.read_host(), post_id)), &params)?;
+		let data = self.load_comment_listing(|| Request::get(uri.clone()).body(Body::empty()).unwrap())?;
+
+		Listing::from_value(&data, post_id, self)
+	}
+
+	/// Sends a request for the comments of a post and returns the children of the comment listing.
+	/// If the post is behind an over 18 gate the request is repeated once with the gate confirmed.
+	fn load_comment_listing<F: Fn() -> Request<Body>>(&self, make_req: F) -> Result<Value, Error> {
+		let first = self.conn.run_read_request(make_req()).and_then(|resp| comment_listing(&resp, &make_req().uri().to_string()));
+		match first {
+			Err(ref e) if is_nsfw_gate(e) => {
+				let mut req = make_req();
+				req.headers_mut().insert(header::COOKIE, HeaderValue::from_static("over18=1"));
+				let request = req.uri().to_string();
+				self.conn.run_read_request(req).and_then(|resp| comment_listing(&resp, &request))
+			}
+			other => other,
+		}
+	}
+
+	/// Get the awards that are available to be given to a post or comment. The awards depend on
+	/// the subreddit the thing is in.
+	/// # Arguments
+	/// * `fullname` - Fullname of the post or comment
+	pub fn get_available_awards(&self, fullname: &str) -> Result<Vec<AwardInfo>, Error> {
+		let fullname = &ids::fullname_of("fullname", &[Kind::Link, Kind::Comment], fullname)?;
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		params.insert("id", fullname);
+
+		let req = Request::get(uri_params_from_map(&json_url(&format!("{}/api/info", self.conn.read_host())), &params)?).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_read_request(req)?;
+		let thing = &resp["data"]["children"][0]["data"];
+		if thing.is_null() {
+			return Err(Error::from(RedditError::NotFound { request: fullname.to_string() }));
+		}
+
+		match thing["all_awardings"].as_array() {
+			Some(awards) => awards.iter().map(|award| AwardInfo::from_value(award, self)).collect(),
+			None => Ok(Vec::new()),
+		}
+	}
+
+	/// Check whether a post or comment still publicly exists, i.e. it wasn't removed or deleted
+	/// # Arguments
+	/// * `fullname` - Fullname of the thing
+	pub fn is_available(&self, fullname: &str) -> Result<bool, Error> {
+		let fullname = &ids::fullname_of("fullname", &Kind::ALL, fullname)?;
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		params.insert("id", fullname);
+
+		let req = Request::get(uri_params_from_map(&json_url(&format!("{}/api/info", self.conn.read_host())), &params)?).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_read_request(req)?;
+		info_available(&resp, self)
+	}
+}
+
+fn is_nsfw_gate(e: &Error) -> bool {
+	matches!(e.downcast_ref::<RedditError>(), Some(RedditError::NsfwGate { .. }))
+}
+
+/// Fails with `RedditError::BadRequest` for sorts that are only available for the front page,
+/// since reddit silently falls back to hot for subreddits
+fn reject_front_page_sort(target: &Target, sort: Sort) -> Result<(), Error> {
+	if sort == Sort::Best && *target != Target::Frontpage {
+		return Err(Error::from(RedditError::BadRequest {
+			request: "The best sort is only available for the front page".to_string(),
+			response: "not sent".to_string(),
+			status: None,
+		}));
+	}
+
+	Ok(())
 }
@@ -1,19 +1,174 @@
+use std::collections::HashMap;
+
+use chrono::{self, Utc};
 use failure::Error;
-use hyper::Request;
+use hyper::{Body, Request};
 use url::form_urlencoded;
 
-use App;
+use app::ids::{self, Kind};
+use app::pacing::{paced, PACING_RETRIES};
+use data::{find_sent, Listing, Message, MessageThread, SendOutcome, Thing};
+use net::{json_url, uri_params_from_map};
+use {App, RedditError};
 
 impl App {
 	/// Send a private message to a user
 	/// # Arguments
-	/// * `to` - Name of the user to send a message to
+	/// * `to` - Name of the user to send a message to. Can be `/r/<subreddit>` to send a message to
+	/// the moderators of a subreddit
 	/// * `subject` - Subject of the message
 	/// * `body` - Body of the message
 	pub fn message(&self, to: &str, subject: &str, body: &str) -> Result<(), Error> {
-		let form = form_urlencoded::Serializer::new(String::new()).append_pair("to", to).append_pair("subject", subject).append_pair("text", body).finish();
+		self.compose(to, subject, body, None)
+	}
+
+	/// Send the same private message to several users. Reddit has no messages with several
+	/// recipients, so a message is sent to each of them, spaced by the cooldown of the account
+	/// when reddit ratelimits. A failed message doesn't stop the others from being sent.
+	/// # Arguments
+	/// * `recipients` - Names of the users to send the message to. Can contain `/r/<subreddit>`
+	/// to send the message to the moderators of a subreddit
+	/// * `subject` - Subject of the message
+	/// * `body` - Body of the message
+	/// # Returns
+	/// Each recipient with the result of sending the message to it, in order
+	pub fn message_many(&self, recipients: &[&str], subject: &str, body: &str) -> Vec<(String, Result<(), Error>)> {
+		let results = paced(recipients, |to| self.compose(to, subject, body, None));
+		recipients.iter().map(|to| to.to_string()).zip(results).collect()
+	}
+
+	/// Send a private message as a subreddit the user currently authorized moderates. Fails with
+	/// `RedditError::Forbidden` without sending the message if the user is not a moderator of the
+	/// subreddit, or if reddit refuses to send as the subreddit (e.g. without the mail permission).
+	/// # Arguments
+	/// * `sub` - Name of the subreddit to send the message as
+	/// * `to` - Name of the user to send a message to. Can be `/r/<subreddit>` to send a message to
+	/// the moderators of a subreddit
+	/// * `subject` - Subject of the message
+	/// * `body` - Body of the message
+	pub fn message_as_subreddit(&self, sub: &str, to: &str, subject: &str, body: &str) -> Result<(), Error> {
+		let sub = sub.trim_start_matches('/').trim_start_matches("r/");
+		if !self.get_moderated_subreddits()?.iter().any(|modded| modded.eq_ignore_ascii_case(sub)) {
+			return Err(Error::from(RedditError::Forbidden {
+				request: format!("Messages can only be sent as subreddits you moderate, which doesn't include {}", sub),
+			}));
+		}
+
+		self.compose(to, subject, body, Some(sub))
+	}
+
+	/// Send a private message, making sure it isn't sent twice when an attempt fails in a way
+	/// that leaves open whether reddit delivered it, like a timeout. Before such an attempt is
+	/// retried, the sent messages of the user currently authorized are checked for a message with
+	/// the same recipient and subject from the last few minutes, and the message isn't sent again
+	/// if one is found.
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `to` - Name of the user to send a message to. Can be `/r/<subreddit>` to send a message to
+	/// the moderators of a subreddit
+	/// * `subject` - Subject of the message
+	/// * `body` - Body of the message
+	/// # Returns
+	/// Whether the message was sent, or found among the sent messages
+	pub fn message_idempotent(&self, to: &str, subject: &str, body: &str) -> Result<SendOutcome, Error> {
+		let mut tries = 0;
+		loop {
+			match self.compose(to, subject, body, None) {
+				Ok(()) => return Ok(SendOutcome::Sent),
+				Err(ref e) if tries < PACING_RETRIES && delivery_unknown(e) => {
+					warn!("Sending message to {} failed, checking the sent messages before retrying: {}", to, e);
+				}
+				Err(e) => return Err(e),
+			}
+
+			let since = Utc::now() - chrono::Duration::minutes(5);
+			if let Some(existing) = find_sent(&self.get_sent(Some(25))?, to, subject, since) {
+				return Ok(SendOutcome::AlreadySent { existing: Box::new(existing) });
+			}
+			tries += 1;
+		}
+	}
+
+	/// Get a conversation of private messages with all of its replies
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `message_id` - Id or fullname of the first message of the conversation
+	pub fn get_message_thread(&self, message_id: &str) -> Result<MessageThread, Error> {
+		let id = ids::bare_id("message_id", Kind::Message, message_id)?;
+		let req = Request::get(json_url(&format!("https://oauth.reddit.com/message/messages/{}", id))).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		MessageThread::from_value(&resp, self)
+	}
+
+	/// Get the comments mentioning the user currently authorized, newest first
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `limit` - Optional limit to amount of mentions loaded
+	pub fn get_mentions(&self, limit: Option<i32>) -> Result<Listing<Message>, Error> {
+		self.get_message_folder("mentions", limit)
+	}
+
+	/// Get the private messages the user currently authorized sent, newest first
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `limit` - Optional limit to amount of messages loaded
+	pub fn get_sent(&self, limit: Option<i32>) -> Result<Listing<Message>, Error> {
+		self.get_message_folder("sent", limit)
+	}
+
+	fn get_message_folder(&self, folder: &str, limit: Option<i32>) -> Result<Listing<Message>, Error> {
+		let limit_str;
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		if let Some(limit) = limit {
+			limit_str = limit.to_string();
+			params.insert("limit", &limit_str);
+		}
+
+		let req = Request::get(uri_params_from_map(&json_url(&format!("https://oauth.reddit.com/message/{}", folder)), &params)?).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		let mut messages = Listing::new();
+		messages.read_envelope(&resp["data"]);
+		if let Some(children) = resp["data"]["children"].as_array() {
+			for child in children {
+				messages.push_parsed(Message::from_value(child, self));
+			}
+		}
 
-		let req = Request::post("https://oauth.reddit.com/api/compose/.json").body(form.into()).unwrap();
+		Ok(messages)
+	}
+
+	/// Delete a message from the inbox of the user currently authorized. The message is only
+	/// removed from this user's view, not for the other participants. Fails with
+	/// `RedditError::BadRequest` without sending a request if `fullname` isn't the id or fullname
+	/// of a message, e.g. if it is the fullname of a comment reply in the inbox
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `fullname` - Id or fullname of the message to delete
+	pub fn delete_message(&self, fullname: &str) -> Result<(), Error> {
+		let fullname = &ids::fullname("fullname", Kind::Message, fullname)?;
+		let form = form_urlencoded::Serializer::new(String::new()).append_pair("id", fullname).finish();
+
+		let req = Request::post(json_url("https://oauth.reddit.com/api/del_msg")).body(form.into()).unwrap();
+
+		self.conn.run_auth_request(req)?;
+		Ok(())
+	}
+
+	fn compose(&self, to: &str, subject: &str, body: &str, from_sr: Option<&str>) -> Result<(), Error> {
+		let mut form = form_urlencoded::Serializer::new(String::new());
+		form.append_pair("to", to).append_pair("subject", subject).append_pair("text", body);
+		if let Some(sub) = from_sr {
+			form.append_pair("from_sr", sub);
+		}
+
+		let req = Request::post(json_url("https://oauth.reddit.com/api/compose")).body(form.finish().into()).unwrap();
 
 		match self.conn.run_auth_request(req) {
 			Ok(_) => Ok(()),
@@ -21,3 +176,13 @@ impl App {
 		}
 	}
 }
+
+/// Whether a request may have reached reddit even though it failed, so retrying it could do its
+/// action twice
+fn delivery_unknown(e: &Error) -> bool {
+	match e.downcast_ref::<RedditError>() {
+		Some(&RedditError::Timeout { .. }) | Some(&RedditError::ConnectionFailed { .. }) | Some(&RedditError::BadResponse { .. }) => true,
+		Some(&RedditError::BadRequest { status: Some(status), .. }) => status >= 500,
+		_ => false,
+	}
+}
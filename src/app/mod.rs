@@ -1,19 +1,33 @@
 mod account;
 mod auth;
+pub(crate) mod ids;
 mod links;
 mod listings;
 mod messages;
+mod moderation;
+mod modmail;
+pub(crate) mod pacing;
+mod subreddits;
 mod users;
 
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
 use failure::Error;
 
-use net::{Connection, LimitMethod};
+use data::{CommentCooldown, DuplicateGuard};
+use net::{Connection, LimitMethod, UserAgent};
 
 /// A reddit object
 /// ## Usage:
 /// To create a new instance, use `Reddit::new()`
 pub struct App {
 	pub(crate) conn: Connection,
+	pub(crate) duplicate_guard: Cell<Option<DuplicateGuard>>,
+	pub(crate) comment_cooldowns: RefCell<HashMap<String, CommentCooldown>>,
+	pub(crate) wait_for_comment_cooldown: Cell<bool>,
+	pub(crate) include_hidden: Cell<bool>,
 }
 
 impl App {
@@ -25,7 +39,28 @@ impl App {
 	/// # Returns
 	/// A new reddit object
 	pub fn new(appname: &str, appversion: &str, appauthor: &str) -> Result<App, Error> {
-		Ok(App { conn: Connection::new(appname, appversion, appauthor)? })
+		Ok(App {
+			conn: Connection::new(appname, appversion, appauthor)?,
+			duplicate_guard: Cell::new(None),
+			comment_cooldowns: RefCell::new(HashMap::new()),
+			wait_for_comment_cooldown: Cell::new(false),
+			include_hidden: Cell::new(false),
+		})
+	}
+
+	/// Create a new reddit instance with a specific user agent
+	/// # Arguments
+	/// * `useragent` - The user agent to identify the app with
+	/// # Returns
+	/// A new reddit object
+	pub fn with_user_agent(useragent: &UserAgent) -> Result<App, Error> {
+		Ok(App {
+			conn: Connection::with_user_agent(useragent)?,
+			duplicate_guard: Cell::new(None),
+			comment_cooldowns: RefCell::new(HashMap::new()),
+			wait_for_comment_cooldown: Cell::new(false),
+			include_hidden: Cell::new(false),
+		})
 	}
 
 	/// Sets the method to use for ratelimiting.
@@ -34,4 +69,71 @@ impl App {
 	pub fn set_ratelimiting(&self, limit: LimitMethod) {
 		self.conn.set_limit(limit);
 	}
+
+	/// Sets whether to check the newest submissions of the account for a submission with the same
+	/// title to the same subreddit before submitting, in which case `RedditError::AlreadySubmitted`
+	/// is returned instead of submitting again. This prevents duplicates when a submission is
+	/// retried after it succeeded but its response was lost. Off by default.
+	/// # Arguments
+	/// * `guard` - Settings of the check, or `None` to turn it off
+	pub fn set_duplicate_guard(&self, guard: Option<DuplicateGuard>) {
+		self.duplicate_guard.set(guard);
+	}
+
+	/// Sets whether `comment` waits out the comment cooldown of the account and retries when reddit
+	/// ratelimits a comment, instead of failing with `RedditError::RateLimited`. Off by default.
+	pub fn set_wait_for_comment_cooldown(&self, wait: bool) {
+		self.wait_for_comment_cooldown.set(wait);
+	}
+
+	/// Sets whether listings of posts include the posts the account hid or that reddit filters
+	/// for it, by sending `show=all`. This only changes listings loaded with authorization. Off by
+	/// default.
+	pub fn set_include_hidden(&self, include_hidden: bool) {
+		self.include_hidden.set(include_hidden);
+	}
+
+	/// Estimates how long to wait before the account can comment in a subreddit again, from the
+	/// ratelimits and successful comments orca observed there
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	/// # Returns
+	/// `None` if no wait is expected, which includes subreddits nothing was observed in
+	pub fn estimated_comment_wait(&self, sub: &str) -> Option<Duration> {
+		self.comment_cooldown(sub).and_then(|cooldown| cooldown.estimated_wait_at(Instant::now()))
+	}
+
+	/// Get what orca observed of the comment cooldown in a subreddit
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	pub fn comment_cooldown(&self, sub: &str) -> Option<CommentCooldown> {
+		self.comment_cooldowns.borrow().get(&sub.to_lowercase()).cloned()
+	}
+
+	/// Forgets the observed comment cooldowns, for instance after the account gained karma
+	/// # Arguments
+	/// * `sub` - Name of the subreddit to forget the cooldown of, or `None` to forget all of them
+	pub fn reset_comment_cooldowns(&self, sub: Option<&str>) {
+		let mut cooldowns = self.comment_cooldowns.borrow_mut();
+		match sub {
+			Some(sub) => {
+				cooldowns.remove(&sub.to_lowercase());
+			}
+			None => cooldowns.clear(),
+		}
+	}
+
+	pub(crate) fn record_comment_cooldown<F: FnOnce(&mut CommentCooldown)>(&self, sub: &str, record: F) {
+		record(self.comment_cooldowns.borrow_mut().entry(sub.to_lowercase()).or_default());
+	}
+
+	/// Get a reference to the connection used by this app
+	pub fn conn(&self) -> &Connection {
+		&self.conn
+	}
+
+	/// Get a mutable reference to the connection used by this app, for configuring it
+	pub fn conn_mut(&mut self) -> &mut Connection {
+		&mut self.conn
+	}
 }
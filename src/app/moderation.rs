@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use failure::Error;
+use hyper::{Body, Request};
+use json::Value;
+use url::form_urlencoded;
+
+use app::ids::{self, Kind};
+use data::{mod_permissions_of, ActionOutcome, BannedUser, FlairChoices, Listing, ModPermission, ModqueueStream, Pages, Thing};
+use net::{check_json_errors, json_url, uri_params_from_map};
+use {App, RedditError};
+
+impl App {
+	/// Get the posts and comments in the modqueue of a subreddit, newest first
+	///
+	/// Note: requires connection to be authorized as a moderator of the subreddit
+	/// # Arguments
+	/// * `sub` - Name of the subreddit. Can be "mod" to get the modqueues of all subreddits the
+	/// user moderates
+	/// * `limit` - Optional limit to amount of items loaded
+	/// # Returns
+	/// A result with a json listing of the items
+	pub fn get_modqueue(&self, sub: &str, limit: Option<i32>) -> Result<Value, Error> {
+		let limit_str;
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		if let Some(limit) = limit {
+			limit_str = limit.to_string();
+			params.insert("limit", &limit_str);
+		}
+
+		let req = Request::get(uri_params_from_map(&json_url(&format!("https://oauth.reddit.com/r/{}/about/modqueue", sub)), &params)?).body(Body::empty()).unwrap();
+
+		self.conn.run_auth_request(req)
+	}
+
+	/// Get the reported posts and comments of a subreddit, newest first. Use `sort_by_reports` to
+	/// sort them by their amount of reports
+	///
+	/// Note: requires connection to be authorized as a moderator of the subreddit
+	/// # Arguments
+	/// * `sub` - Name of the subreddit. Can be "mod" to get the reports of all subreddits the user
+	/// moderates
+	/// * `limit` - Optional limit to amount of items loaded
+	/// # Returns
+	/// A result with a json listing of the items
+	pub fn get_reports(&self, sub: &str, limit: Option<i32>) -> Result<Value, Error> {
+		let limit_str;
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		if let Some(limit) = limit {
+			limit_str = limit.to_string();
+			params.insert("limit", &limit_str);
+		}
+
+		let req = Request::get(uri_params_from_map(&json_url(&format!("https://oauth.reddit.com/r/{}/about/reports", sub)), &params)?).body(Body::empty()).unwrap();
+
+		self.conn.run_auth_request(req)
+	}
+
+	/// Get a iterator of the posts and comments arriving in the modqueue of a subreddit
+	///
+	/// Note: requires connection to be authorized as a moderator of the subreddit
+	/// # Arguments
+	/// * `sub` - Name of the subreddit. Can be "mod" to stream the modqueues of all subreddits
+	/// the user moderates
+	pub fn modqueue_stream(&self, sub: &str) -> ModqueueStream<'_> {
+		ModqueueStream::new(self, sub)
+	}
+
+	/// Get the moderator permissions of the user currently authorized in a subreddit, for instance
+	/// to check for the `Posts` permission before removing posts
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	/// # Returns
+	/// A result with the permissions, which are empty if the user is not a moderator
+	pub fn my_mod_permissions(&self, sub: &str) -> Result<Vec<ModPermission>, Error> {
+		let me = self.get_self()?;
+		let name = match me["name"].as_str() {
+			Some(name) => name.to_string(),
+			None => return Err(Error::from(RedditError::AuthError)),
+		};
+
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		params.insert("user", &name);
+		let req = Request::get(uri_params_from_map(&json_url(&format!("https://oauth.reddit.com/r/{}/about/moderators", sub)), &params)?).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		Ok(mod_permissions_of(&resp, &name))
+	}
+
+	/// Approve a post or comment
+	///
+	/// Note: requires connection to be authorized as a moderator of the subreddit the thing is in
+	/// # Arguments
+	/// * `fullname` - Fullname of the post or comment to approve
+	pub fn approve(&self, fullname: &str) -> Result<ActionOutcome, Error> {
+		let fullname = &ids::fullname_of("fullname", &[Kind::Link, Kind::Comment], fullname)?;
+		let body = form_urlencoded::Serializer::new(String::new()).append_pair("id", fullname).finish();
+
+		let req = Request::post(json_url("https://oauth.reddit.com/api/approve")).body(body.into()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		Ok(ActionOutcome::from_response(&resp))
+	}
+
+	/// Remove a post or comment
+	///
+	/// Note: requires connection to be authorized as a moderator of the subreddit the thing is in
+	/// # Arguments
+	/// * `fullname` - Fullname of the post or comment to remove
+	/// * `spam` - Whether to mark the thing as spam, which trains the subreddit's spam filter
+	pub fn remove(&self, fullname: &str, spam: bool) -> Result<ActionOutcome, Error> {
+		let fullname = &ids::fullname_of("fullname", &[Kind::Link, Kind::Comment], fullname)?;
+		let body = form_urlencoded::Serializer::new(String::new()).append_pair("id", fullname).append_pair("spam", if spam { "true" } else { "false" }).finish();
+
+		let req = Request::post(json_url("https://oauth.reddit.com/api/remove")).body(body.into()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		Ok(ActionOutcome::from_response(&resp))
+	}
+
+	/// Get all users banned from a subreddit, loading every page of the list
+	///
+	/// Note: requires connection to be authorized as a moderator of the subreddit
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	/// * `user` - Optional name of a user to search the list for
+	pub fn get_banned_users(&self, sub: &str, user: Option<&str>) -> Result<Listing<BannedUser>, Error> {
+		self.get_user_list(sub, "banned", user)
+	}
+
+	/// Get all users muted in a subreddit, loading every page of the list. The `note` and
+	/// `days_left` of muted users are always empty.
+	///
+	/// Note: requires connection to be authorized as a moderator of the subreddit
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	/// * `user` - Optional name of a user to search the list for
+	pub fn get_muted_users(&self, sub: &str, user: Option<&str>) -> Result<Listing<BannedUser>, Error> {
+		self.get_user_list(sub, "muted", user)
+	}
+
+	fn get_user_list(&self, sub: &str, list: &str, user: Option<&str>) -> Result<Listing<BannedUser>, Error> {
+		let params = user.map(|user| vec![("user".to_string(), user.to_string())]).unwrap_or_default();
+
+		let mut users = Listing::new();
+		let mut pages = Pages::new(self, &json_url(&format!("https://oauth.reddit.com/r/{}/about/{}", sub, list)), params);
+		for user in pages.by_ref() {
+			users.push_parsed(BannedUser::from_value(&user?, self));
+		}
+		users.dist = pages.dist();
+
+		Ok(users)
+	}
+
+	/// Ban a user from a subreddit. Nothing is sent if the user is already banned.
+	///
+	/// Note: requires connection to be authorized as a moderator of the subreddit
+	/// # Arguments
+	/// * `sub` - Name of the subreddit to ban the user from
+	/// * `user` - Name of the user to ban
+	/// * `duration` - Optional length of the ban, rounded up to whole days (1-999). The ban is
+	/// permanent if `None`
+	/// * `note` - Note about the ban, visible only to moderators
+	/// # Returns
+	/// `ActionOutcome::Unchanged` if the user was already banned
+	pub fn ban_user(&self, sub: &str, user: &str, duration: Option<Duration>, note: &str) -> Result<ActionOutcome, Error> {
+		if self.get_banned_users(sub, Some(user))?.children.iter().any(|ban| ban.name.eq_ignore_ascii_case(user)) {
+			return Ok(ActionOutcome::Unchanged);
+		}
+
+		let days_str;
+		let mut body = form_urlencoded::Serializer::new(String::new());
+		body.append_pair("api_type", "json").append_pair("type", "banned").append_pair("name", user).append_pair("note", note);
+		if let Some(duration) = duration {
+			days_str = ban_days(duration).to_string();
+			body.append_pair("duration", &days_str);
+		}
+
+		let req = Request::post(json_url(&format!("https://oauth.reddit.com/r/{}/api/friend", sub))).body(body.finish().into()).unwrap();
+		let req_str = format!("{:?}", req);
+
+		let resp = self.conn.run_auth_request(req)?;
+		check_json_errors(&req_str, &resp)?;
+		Ok(ActionOutcome::from_response(&resp))
+	}
+
+	/// Get the flairs the user currently authorized can choose for a post in a subreddit, as
+	/// reddit offers them when submitting. Unlike the flair templates moderators manage, this
+	/// only includes the flairs users can assign and tells which of them can be edited.
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	/// * `link_fullname` - Optional id or fullname of a post, to get its current flair and the
+	/// flairs it can be changed to
+	pub fn get_flair_choices(&self, sub: &str, link_fullname: Option<&str>) -> Result<FlairChoices, Error> {
+		let mut body = form_urlencoded::Serializer::new(String::new());
+		if let Some(link) = link_fullname {
+			body.append_pair("link", &ids::fullname("link_fullname", Kind::Link, link)?);
+		}
+
+		let req = Request::post(json_url(&format!("https://oauth.reddit.com/r/{}/api/flairselector", sub))).body(body.finish().into()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		FlairChoices::from_value(&resp, self)
+	}
+
+	/// Apply a flair template to a post
+	///
+	/// Note: requires connection to be authorized as the author of the post or a moderator of the subreddit
+	/// # Arguments
+	/// * `sub` - Name of the subreddit the post is in
+	/// * `fullname` - Id or fullname of the post to flair
+	/// * `template_id` - Id of the flair template to apply
+	pub fn select_flair(&self, sub: &str, fullname: &str, template_id: &str) -> Result<(), Error> {
+		let fullname = &ids::fullname("fullname", Kind::Link, fullname)?;
+		let body = form_urlencoded::Serializer::new(String::new()).append_pair("api_type", "json").append_pair("link", fullname).append_pair("flair_template_id", template_id).finish();
+
+		let req = Request::post(json_url(&format!("https://oauth.reddit.com/r/{}/api/selectflair", sub))).body(body.into()).unwrap();
+		let req_str = format!("{:?}", req);
+
+		let resp = self.conn.run_auth_request(req)?;
+		check_json_errors(&req_str, &resp)
+	}
+
+	/// Apply a flair template to a post and then approve it. Stops if flairing fails without
+	/// approving the post.
+	///
+	/// Note: requires connection to be authorized as a moderator of the subreddit
+	/// # Arguments
+	/// * `sub` - Name of the subreddit the post is in
+	/// * `fullname` - Id or fullname of the post
+	/// * `template_id` - Id of the flair template to apply
+	/// # Returns
+	/// `RedditError::StepFailed` with the step `"flair"` or `"approve"` and the error it failed with
+	/// if either step fails
+	pub fn flair_and_approve(&self, sub: &str, fullname: &str, template_id: &str) -> Result<(), Error> {
+		let fullname = &ids::fullname("fullname", Kind::Link, fullname)?;
+		self.select_flair(sub, fullname, template_id).map_err(|e| RedditError::StepFailed { step: "flair".to_string(), error: e })?;
+		self.approve(fullname).map_err(|e| RedditError::StepFailed { step: "approve".to_string(), error: e })?;
+		Ok(())
+	}
+}
+
+/// Converts the length of a ban to the whole days reddit accepts
+fn ban_days(duration: Duration) -> u64 {
+	let day = 60 * 60 * 24;
+	let secs = duration.as_secs() + if duration.subsec_nanos() > 0 { 1 } else { 0 };
+	secs.div_ceil(day).clamp(1, 999)
+}
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use failure::Error;
+use hyper::{Body, Method, Request};
+use url::Url;
+
+use app::ids;
+use data::ModmailPage;
+use net::{json_url, uri_params_from_map};
+use App;
+
+impl App {
+	/// Get a page of new modmail conversations of the subreddits the user currently authorized
+	/// moderates
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `state` - Optional state of the conversations to get, e.g. `all`, `new`, `inprogress`,
+	/// `archived` or `highlighted`. Defaults to `all`
+	/// * `after` - Optional cursor from a previous page to continue from. This is the id of a
+	/// conversation, not a fullname
+	/// * `limit` - Maximum number of conversations on the page, at most 100
+	/// # Returns
+	/// A result with the page and the cursor of the next page
+	pub fn get_modmail_conversations(&self, state: Option<&str>, after: Option<&str>, limit: usize) -> Result<ModmailPage, Error> {
+		let limit = limit.min(100);
+		let limit_str = limit.to_string();
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		params.insert("state", state.unwrap_or("all"));
+		params.insert("limit", &limit_str);
+		if let Some(after) = after {
+			params.insert("after", after);
+		}
+
+		let req = Request::get(uri_params_from_map(&json_url("https://oauth.reddit.com/api/mod/conversations"), &params)?).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		ModmailPage::from_response(&resp, limit, self)
+	}
+
+	/// Highlight or unhighlight a new modmail conversation
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `id` - Id of the conversation
+	/// * `highlighted` - Whether the conversation should be highlighted
+	pub fn modmail_highlight(&self, id: &str, highlighted: bool) -> Result<(), Error> {
+		let id = ids::plain_id("id", id)?;
+		let url = Url::parse(&json_url(&format!("https://oauth.reddit.com/api/mod/conversations/{}/highlight", id)))?;
+		let method = if highlighted { Method::POST } else { Method::DELETE };
+
+		self.conn.run_auth_request_with::<()>(method, url, None)?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,87 @@
+use std::thread;
+use std::time::Duration;
+
+use failure::Error;
+use rand::{self, Rng, SeedableRng, XorShiftRng};
+
+use RedditError;
+
+/// Times an item of a paced batch is retried after being ratelimited
+pub(crate) const PACING_RETRIES: usize = 3;
+
+/// Failures after which the delay between retries stops growing
+const BACKOFF_MAX_FAILS: u32 = 10;
+
+/// The delays between retries of a request that keeps failing, e.g. while a stream polls during
+/// an outage of reddit. The delay grows by a second with every failure, up to ten seconds, and is
+/// scaled by a random factor between 0.5 and 1.5. Without that, bots that failed at the same time
+/// would keep retrying in lockstep and hit reddit all at once when it recovers.
+pub(crate) struct Backoff {
+	fails: u32,
+	rng: XorShiftRng,
+}
+
+impl Backoff {
+	pub(crate) fn new() -> Backoff {
+		Backoff::with_seed(rand::random())
+	}
+
+	/// Creates a backoff whose jitter is the same for the same seed, e.g. in tests
+	pub(crate) fn with_seed(seed: u32) -> Backoff {
+		// The generator needs a seed that isn't all zeros
+		Backoff {
+			fails: 0,
+			rng: XorShiftRng::from_seed([seed, seed ^ 0x9e37_79b9, 1, 2]),
+		}
+	}
+
+	/// Get the delay before the next retry and count the failure
+	pub(crate) fn next_delay(&mut self) -> Duration {
+		self.fails = (self.fails + 1).min(BACKOFF_MAX_FAILS);
+		let base = Duration::from_secs(u64::from(self.fails));
+		base.mul_f64(0.5 + self.rng.gen::<f64>())
+	}
+}
+
+/// Runs an action for each item of a batch, such as posting several comments. When reddit
+/// ratelimits an item, waits for the time reddit asks for and retries the item, then spaces the
+/// following items by that time, since it is the cooldown of the account. Other failures are
+/// returned without retrying, and don't stop the batch.
+/// # Returns
+/// The result of the action for each item, in order
+pub(crate) fn paced<I, T, F: FnMut(&I) -> Result<T, Error>>(items: &[I], mut action: F) -> Vec<Result<T, Error>> {
+	let mut spacing: Option<Duration> = None;
+	let mut results = Vec::with_capacity(items.len());
+
+	for (i, item) in items.iter().enumerate() {
+		if let (Some(spacing), true) = (spacing, i > 0) {
+			thread::sleep(spacing);
+		}
+
+		let mut tries = 0;
+		let result = loop {
+			match action(item) {
+				Err(e) => match ratelimit_wait(&e) {
+					Some(wait) if tries < PACING_RETRIES => {
+						debug!("Ratelimited in a paced batch, waiting {:?}", wait);
+						spacing = Some(wait);
+						tries += 1;
+						thread::sleep(wait);
+					}
+					_ => break Err(e),
+				},
+				ok => break ok,
+			}
+		};
+		results.push(result);
+	}
+
+	results
+}
+
+pub(crate) fn ratelimit_wait(e: &Error) -> Option<Duration> {
+	match e.downcast_ref::<RedditError>() {
+		Some(RedditError::RateLimited { wait }) => Some(*wait),
+		_ => None,
+	}
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use failure::Error;
+use hyper::{Body, Request};
+use json;
+
+use data::{subreddit_assets, subreddits_by_name, AssetManifest, AutocompleteEntry, SettingChange, Subreddit, SubredditSettings, Thing, Widgets};
+use net::{check_json_errors, json_url, subreddit_from_location, uri_params_from_map};
+use url::form_urlencoded;
+use url::percent_encoding::{utf8_percent_encode, PATH_SEGMENT_ENCODE_SET};
+use {App, RedditError};
+
+impl App {
+	/// Get the settings of a subreddit, as they would be edited by a moderator
+	///
+	/// Note: requires connection to be authorized as a moderator of the subreddit
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	pub fn get_subreddit_settings(&self, sub: &str) -> Result<SubredditSettings, Error> {
+		let req = Request::get(json_url(&format!("https://oauth.reddit.com/r/{}/about/edit", sub))).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		SubredditSettings::from_value(&resp, self)
+	}
+
+	/// Get a random subreddit, like reddit's random button does
+	/// # Arguments
+	/// * `nsfw` - Whether to pick a random NSFW subreddit instead
+	pub fn get_random_subreddit(&self, nsfw: bool) -> Result<Subreddit, Error> {
+		let req = Request::get(json_url(&format!("{}/r/{}", self.conn.read_host(), if nsfw { "randnsfw" } else { "random" }))).body(Body::empty()).unwrap();
+		let req_str = format!("{:?}", req);
+
+		// Reddit picks the subreddit by redirecting to it
+		let location = match self.conn.run_read_request(req).map_err(|e| e.downcast::<RedditError>()) {
+			Err(Ok(RedditError::Redirected { location, .. })) => location,
+			Err(Ok(e)) => return Err(Error::from(e)),
+			Err(Err(e)) => return Err(e),
+			Ok(resp) => {
+				return Err(Error::from(RedditError::BadResponse {
+					request: req_str,
+					response: json::to_string(&resp).unwrap(),
+				}))
+			}
+		};
+		let sub = match subreddit_from_location(&location) {
+			Some(sub) => sub,
+			None => return Err(Error::from(RedditError::BadResponse { request: req_str, response: location })),
+		};
+
+		let req = Request::get(json_url(&format!("{}/r/{}/about", self.conn.read_host(), sub))).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_read_request(req)?;
+		Subreddit::from_value(&resp, self)
+	}
+
+	/// Get the about of several subreddits with a request per 100 subreddits, instead of a request per
+	/// subreddit like `/r/{sub}/about`
+	/// # Arguments
+	/// * `names` - Names of the subreddits
+	/// # Returns
+	/// The subreddits in the order of `names`. Names reddit finds no subreddit for are left out
+	pub fn get_subreddits_about(&self, names: &[&str]) -> Result<Vec<Subreddit>, Error> {
+		let mut resps = Vec::new();
+		for chunk in names.chunks(100) {
+			let mut params: HashMap<&str, &str> = HashMap::new();
+			let joined = chunk.join(",");
+			params.insert("sr_name", &joined);
+
+			let req = Request::get(uri_params_from_map(&json_url(&format!("{}/api/info", self.conn.read_host())), &params)?).body(Body::empty()).unwrap();
+			resps.push(self.conn.run_read_request(req)?);
+		}
+
+		subreddits_by_name(&resps, names, self)
+	}
+
+	/// Download the icon, banners, header and stylesheet images of a subreddit to a directory, e.g.
+	/// to back up its style. Stylesheet images are saved in a `stylesheet` directory inside it.
+	/// The images are downloaded with `Connection::download`, so they don't use up the ratelimit.
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	/// * `dest` - Directory to save the images in, which is created if it doesn't exist
+	/// # Returns
+	/// The images that were saved and the ones that failed. Only fails as a whole if the about of
+	/// the subreddit couldn't be fetched
+	pub fn download_subreddit_assets(&self, sub: &str, dest: &Path) -> Result<AssetManifest, Error> {
+		let req = Request::get(json_url(&format!("{}/r/{}/about", self.conn.read_host(), sub))).body(Body::empty()).unwrap();
+		let about = self.conn.run_read_request(req)?;
+		let req = Request::get(json_url(&format!("{}/r/{}/about/stylesheet", self.conn.read_host(), sub))).body(Body::empty()).unwrap();
+		let (stylesheet, stylesheet_error) = match self.conn.run_read_request(req) {
+			Ok(stylesheet) => (stylesheet, None),
+			Err(e) => {
+				warn!("Failed to list the stylesheet images of /r/{}: {}", sub, e);
+				(json::Value::Null, Some(e))
+			}
+		};
+
+		let mut manifest = AssetManifest {
+			saved: Vec::new(),
+			failed: Vec::new(),
+			stylesheet_error,
+		};
+		for asset in subreddit_assets(&about, &stylesheet) {
+			let path = dest.join(&asset.file_name);
+			let saved = self.conn.download(&asset.url).and_then(|content| {
+				if let Some(dir) = path.parent() {
+					fs::create_dir_all(dir)?;
+				}
+				fs::write(&path, content)?;
+				Ok(())
+			});
+			match saved {
+				Ok(()) => manifest.saved.push((asset, path)),
+				Err(e) => {
+					warn!("Failed to save {} of /r/{}: {}", asset.url, sub, e);
+					manifest.failed.push((asset, e));
+				}
+			}
+		}
+
+		Ok(manifest)
+	}
+
+	/// Whether the user currently authorized is subscribed to a subreddit
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	pub fn is_subscribed(&self, sub: &str) -> Result<bool, Error> {
+		let req = Request::get(json_url(&format!("https://oauth.reddit.com/r/{}/about", sub))).body(Body::empty()).unwrap();
+		let req_str = format!("{:?}", req);
+
+		let resp = self.conn.run_auth_request(req)?;
+		// The field is null when reddit doesn't consider the request authorized
+		match resp["data"]["user_is_subscriber"].as_bool() {
+			Some(subscribed) => Ok(subscribed),
+			None => Err(Error::from(RedditError::BadResponse {
+				request: req_str,
+				response: json::to_string(&resp).unwrap(),
+			})),
+		}
+	}
+
+	/// Change the settings of a subreddit to the desired settings. The current settings are
+	/// loaded first, and settings missing from `desired` or only present in its `raw` settings
+	/// keep their current values.
+	///
+	/// Note: requires connection to be authorized as a moderator of the subreddit
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	/// * `desired` - The settings the subreddit should have
+	/// * `dry_run` - Whether to only compute the changes without applying them
+	/// # Returns
+	/// The changes that were applied, or would be applied on a dry run
+	pub fn apply_subreddit_settings(&self, sub: &str, desired: &SubredditSettings, dry_run: bool) -> Result<Vec<SettingChange>, Error> {
+		let current = self.get_subreddit_settings(sub)?;
+		let changes = current.diff(desired);
+		if dry_run || changes.is_empty() {
+			return Ok(changes);
+		}
+
+		let body = form_urlencoded::Serializer::new(String::new()).extend_pairs(current.site_admin_form(&changes)).finish();
+		let req = Request::post(json_url("https://oauth.reddit.com/api/site_admin")).body(body.into()).unwrap();
+		let req_str = format!("{:?}", req);
+
+		let resp = self.conn.run_auth_request(req)?;
+		check_json_errors(&req_str, &resp)?;
+		Ok(changes)
+	}
+
+	/// Get the widgets of a subreddit, which new reddit shows in the sidebar
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	pub fn get_widgets(&self, sub: &str) -> Result<Widgets, Error> {
+		let req = Request::get(json_url(&format!("https://oauth.reddit.com/r/{}/api/widgets", sub))).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		Widgets::from_value(&resp, self)
+	}
+
+	/// Get subreddits (and optionally user profiles) with names starting with a query, for
+	/// instance to suggest subreddits while a name is being typed
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `query` - The start of the name to complete
+	/// * `include_over18` - Whether to include NSFW subreddits
+	/// * `include_profiles` - Whether to include user profiles
+	pub fn subreddit_autocomplete(&self, query: &str, include_over18: bool, include_profiles: bool) -> Result<Vec<AutocompleteEntry>, Error> {
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		params.insert("query", query);
+		params.insert("include_over_18", if include_over18 { "true" } else { "false" });
+		params.insert("include_profiles", if include_profiles { "true" } else { "false" });
+
+		let req = Request::get(uri_params_from_map(&json_url("https://oauth.reddit.com/api/subreddit_autocomplete_v2"), &params)?).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_auth_request(req)?;
+		let mut entries = Vec::new();
+		if let Some(children) = resp["data"]["children"].as_array() {
+			for child in children {
+				// Skip kinds other than subreddits and accounts
+				match child["kind"].as_str() {
+					Some("t5") | Some("t2") => entries.push(AutocompleteEntry::from_value(child, self)?),
+					_ => trace!("Skipping autocomplete entry of unknown kind {}", child["kind"]),
+				}
+			}
+		}
+
+		Ok(entries)
+	}
+
+	/// Get subreddits that are similar to a set of subreddits
+	/// # Arguments
+	/// * `seeds` - Names of the subreddits to find similar subreddits to
+	/// * `omit` - Names of subreddits to leave out of the recommendations
+	/// # Returns
+	/// The names of the recommended subreddits, which is empty if reddit has no recommendations
+	pub fn recommend_subreddits(&self, seeds: &[&str], omit: &[&str]) -> Result<Vec<String>, Error> {
+		let seeds = utf8_percent_encode(&seeds.join(","), PATH_SEGMENT_ENCODE_SET).to_string();
+		let omit = omit.join(",");
+		let mut params: HashMap<&str, &str> = HashMap::new();
+		if !omit.is_empty() {
+			params.insert("omit", &omit);
+		}
+
+		let req = Request::get(uri_params_from_map(&json_url(&format!("{}/api/recommend/sr/{}", self.conn.read_host(), seeds)), &params)?).body(Body::empty()).unwrap();
+
+		let resp = self.conn.run_read_request(req)?;
+		Ok(match resp.as_array() {
+			Some(subs) => subs.iter().filter_map(|sub| sub["sr_name"].as_str()).map(|name| name.to_string()).collect(),
+			None => Vec::new(),
+		})
+	}
+}
@@ -2,6 +2,8 @@ use failure::Error;
 use hyper::{Body, Request};
 use json::Value;
 
+use data::{Thing, UserData};
+use net::json_url;
 use App;
 
 impl App {
@@ -11,8 +13,19 @@ impl App {
 	/// # Returns
 	/// A json value containing the user info
 	pub fn get_user(&self, name: &str) -> Result<Value, Error> {
-		let req = Request::get(format!("https://www.reddit.com/user/{}/about/.json", name)).body(Body::empty()).unwrap();
+		let req = Request::get(json_url(&format!("{}/user/{}/about", self.conn.read_host(), name))).body(Body::empty()).unwrap();
 
-		self.conn.run_request(req)
+		self.conn.run_read_request(req)
+	}
+
+	/// Gets information about multiple users. Reddit has no endpoint to do this in one request, so
+	/// the users are requested one after another, ratelimited like any other request.
+	/// # Arguments
+	/// * `names` - usernames of the users to query
+	/// # Returns
+	/// A vector with the result of each lookup in the same order as `names`, so that one user that
+	/// can't be found or parsed doesn't fail the whole batch
+	pub fn get_users(&self, names: &[&str]) -> Vec<Result<UserData, Error>> {
+		names.iter().map(|name| self.get_user(name).and_then(|user| UserData::from_value(&user, self))).collect()
 	}
 }
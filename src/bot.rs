@@ -0,0 +1,209 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use failure::Error;
+use json::Value;
+
+use data::{Comment, Message, Post, SeenNames, StreamHandle, MAX_CROSSPOST_DEPTH};
+use {App, Target};
+
+pub(crate) type Handler<T> = Box<dyn FnMut(&App, T) -> Result<(), Error>>;
+
+/// What a handler of a `BotRunner` is called for
+enum Source {
+	Comments(Target, Handler<Comment>),
+	Posts(Target, Handler<Post>),
+	Mentions(Handler<Message>),
+}
+
+struct Registration {
+	source: Source,
+	seen: SeenNames,
+	primed: bool,
+}
+
+/// Runs the main loop of a bot: polls the comments, posts and mentions that handlers are
+/// registered for and calls the handlers with every new item. Every source is polled once per
+/// round, starting with a different source each round, so all sources share the ratelimit of the
+/// connection fairly. Errors and panics of handlers are passed to the error handler instead of
+/// ending the loop.
+///
+/// Only items that arrive after the runner starts are handled, the items present at the first
+/// poll of a source are skipped.
+pub struct BotRunner {
+	app: App,
+	sources: Vec<Registration>,
+	on_error: Box<dyn FnMut(&Error)>,
+	interval: Duration,
+	handle: StreamHandle,
+	next: usize,
+}
+
+impl BotRunner {
+	/// Creates a runner without handlers. Errors are logged until an error handler is set
+	/// # Arguments
+	/// * `app` - The app to poll with, which is usually authorized
+	pub fn new(app: App) -> BotRunner {
+		BotRunner {
+			app,
+			sources: Vec::new(),
+			on_error: Box::new(|e| warn!("Error in bot handler: {}", e)),
+			interval: Duration::from_secs(10),
+			handle: StreamHandle::new(),
+			next: 0,
+		}
+	}
+
+	/// Get the app the runner polls with
+	pub fn app(&self) -> &App {
+		&self.app
+	}
+
+	/// Calls a handler with every new comment of a subreddit
+	/// # Arguments
+	/// * `target` - Subreddit to watch, or a `Target` like `r/all`
+	/// * `handler` - Function called with the app and the comment
+	pub fn on_comment<T: Into<Target>, F: FnMut(&App, Comment) -> Result<(), Error> + 'static>(&mut self, target: T, handler: F) -> &mut BotRunner {
+		self.register(Source::Comments(target.into(), Box::new(handler)))
+	}
+
+	/// Calls a handler with every new post of a subreddit. The comments of the posts are not
+	/// loaded
+	/// # Arguments
+	/// * `target` - Subreddit to watch, or a `Target` like `r/all`
+	/// * `handler` - Function called with the app and the post
+	pub fn on_post<T: Into<Target>, F: FnMut(&App, Post) -> Result<(), Error> + 'static>(&mut self, target: T, handler: F) -> &mut BotRunner {
+		self.register(Source::Posts(target.into(), Box::new(handler)))
+	}
+
+	/// Calls a handler with every new mention of the user currently authorized
+	///
+	/// Note: requires connection to be authorized
+	/// # Arguments
+	/// * `handler` - Function called with the app and the mention
+	pub fn on_mention<F: FnMut(&App, Message) -> Result<(), Error> + 'static>(&mut self, handler: F) -> &mut BotRunner {
+		self.register(Source::Mentions(Box::new(handler)))
+	}
+
+	/// Sets the function called with the errors of polling and of handlers, including handlers
+	/// that panicked
+	pub fn on_error<F: FnMut(&Error) + 'static>(&mut self, handler: F) -> &mut BotRunner {
+		self.on_error = Box::new(handler);
+		self
+	}
+
+	/// Sets the time waited between rounds of polling. Defaults to 10 seconds. Requests are
+	/// ratelimited by the connection in addition to this.
+	pub fn set_interval(&mut self, interval: Duration) {
+		self.interval = interval;
+	}
+
+	/// Get a handle that stops the runner, which can be sent to other threads. The runner stops
+	/// after the handler that is running finishes.
+	pub fn handle(&self) -> StreamHandle {
+		self.handle.clone()
+	}
+
+	/// Polls the sources and calls the handlers until the runner is stopped
+	pub fn run(&mut self) {
+		while !self.handle.is_stopped() {
+			self.poll_round();
+
+			let start = Instant::now();
+			while !self.handle.is_stopped() {
+				match self.interval.checked_sub(start.elapsed()) {
+					Some(left) if left > Duration::from_secs(0) => thread::sleep(left.min(Duration::from_millis(100))),
+					_ => break,
+				}
+			}
+		}
+	}
+
+	/// Polls every source once and calls the handlers with the new items
+	pub fn poll_round(&mut self) {
+		let count = self.sources.len();
+		for index in poll_order(self.next, count) {
+			if self.handle.is_stopped() {
+				return;
+			}
+			self.poll(index);
+		}
+		if count > 0 {
+			self.next = (self.next + 1) % count;
+		}
+	}
+
+	fn register(&mut self, source: Source) -> &mut BotRunner {
+		self.sources.push(Registration { source, seen: SeenNames::new(), primed: false });
+		self
+	}
+
+	fn poll(&mut self, index: usize) {
+		let app = &self.app;
+		let on_error = &mut self.on_error;
+		let handle = &self.handle;
+		let registration = &mut self.sources[index];
+		let seen = &mut registration.seen;
+		let primed = registration.primed;
+
+		let result = match registration.source {
+			Source::Comments(ref target, ref mut handler) => app
+				.get_recent_comments(target.clone(), Some(100), None)
+				.map(|comments| deliver(app, comments.children.into_iter().rev().map(|c| (c.name.clone(), c)), seen, primed, handle, handler, on_error)),
+			Source::Posts(ref target, ref mut handler) => app.get_new_posts(target).map(|listing| {
+				let posts = new_posts(&listing, app, on_error);
+				deliver(app, posts, seen, primed, handle, handler, on_error)
+			}),
+			Source::Mentions(ref mut handler) => app.get_mentions(Some(100)).map(|mentions| deliver(app, mentions.children.into_iter().rev().map(|m| (m.name.clone(), m)), seen, primed, handle, handler, on_error)),
+		};
+
+		match result {
+			Ok(()) => registration.primed = true,
+			Err(e) => on_error(&e),
+		}
+	}
+}
+
+/// The order the sources are polled in during a round, starting with the source at `next`
+pub(crate) fn poll_order(next: usize, count: usize) -> Vec<usize> {
+	(0..count).map(|i| (next + i) % count).collect()
+}
+
+/// Parses the posts of a listing of new posts, oldest first. Posts that fail to parse are passed
+/// to the error handler and skipped, so one bad post doesn't keep the others from being handled
+pub(crate) fn new_posts(listing: &Value, app: &App, on_error: &mut Box<dyn FnMut(&Error)>) -> Vec<(String, Post)> {
+	let mut posts = Vec::new();
+	if let Some(children) = listing["data"]["children"].as_array() {
+		for child in children.iter().rev() {
+			match Post::from_data(&child["data"], app, MAX_CROSSPOST_DEPTH) {
+				Ok(post) => posts.push((format!("t3_{}", post.id), post)),
+				Err(e) => on_error(&e),
+			}
+		}
+	}
+	posts
+}
+
+/// Calls a handler with the items that weren't seen before, oldest first. The items of the first
+/// poll are only remembered
+pub(crate) fn deliver<T, I: IntoIterator<Item = (String, T)>>(app: &App, items: I, seen: &mut SeenNames, primed: bool, handle: &StreamHandle, handler: &mut Handler<T>, on_error: &mut Box<dyn FnMut(&Error)>) {
+	for (name, item) in items {
+		if seen.insert(name) && primed && !handle.is_stopped() {
+			if let Err(e) = run_handler(app, handler, item) {
+				on_error(&e);
+			}
+		}
+	}
+}
+
+/// Calls a handler, turning a panic into an error
+pub(crate) fn run_handler<T>(app: &App, handler: &mut Handler<T>, item: T) -> Result<(), Error> {
+	match panic::catch_unwind(AssertUnwindSafe(|| handler(app, item))) {
+		Ok(result) => result,
+		Err(payload) => {
+			let msg = payload.downcast_ref::<&str>().map(|msg| msg.to_string()).or_else(|| payload.downcast_ref::<String>().cloned()).unwrap_or_else(|| "unknown panic".to_string());
+			Err(format_err!("Handler panicked: {}", msg))
+		}
+	}
+}
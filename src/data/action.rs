@@ -0,0 +1,38 @@
+use json::Value;
+
+/// The outcome of a moderation action
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionOutcome {
+	/// The action was applied
+	Applied {
+		/// Ids of things reddit reported as created or changed by the action, if any
+		ids: Vec<String>,
+	},
+	/// The target was already in the requested state, so nothing was sent
+	Unchanged,
+}
+
+impl ActionOutcome {
+	/// Creates an `Applied` outcome from reddit's response to an action, collecting any ids
+	/// returned in it
+	pub fn from_response(resp: &Value) -> ActionOutcome {
+		let mut ids = Vec::new();
+		for data in &[&resp["json"]["data"], resp] {
+			for key in &["id", "name"] {
+				if let Some(id) = data[*key].as_str() {
+					ids.push(id.to_string());
+				}
+			}
+		}
+
+		ActionOutcome::Applied { ids }
+	}
+
+	/// Whether the action changed anything
+	pub fn changed(&self) -> bool {
+		match *self {
+			ActionOutcome::Applied { .. } => true,
+			ActionOutcome::Unchanged => false,
+		}
+	}
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, Utc};
+use failure::Error;
+use json::{self, Map, Value};
+
+use data::{Comment, Listing, Post};
+use errors::ParseError;
+
+/// Version of the format written by `CommentTree::to_archive_json`
+pub const ARCHIVE_VERSION: u64 = 1;
+
+/// A post with its fully loaded comment tree as it was at a point in time, which can be saved as a
+/// single json document and loaded again without requests to reddit. The document uses a format of
+/// its own instead of reddit's json, so archives stay readable when reddit's json changes.
+///
+/// Only the public state of the post and comments is archived. Moderation info, the state of the
+/// user currently authorized, previews, polls and crossposts are left out and are empty after
+/// loading an archive.
+#[derive(Debug)]
+pub struct CommentTree {
+	/// The post, with its comments
+	pub post: Post,
+	/// The time the post and comments were fetched
+	pub fetched: DateTime<Utc>,
+	/// Amount of comments reddit counted on the post that are not in the tree, e.g. because they
+	/// were removed with their replies
+	pub missing: usize,
+}
+
+impl CommentTree {
+	/// Creates the tree of a post whose comments were just loaded
+	pub fn new(post: Post) -> CommentTree {
+		let loaded = post.comments.clone().traverse().len();
+		let missing = (post.num_comments.max(0) as usize).saturating_sub(loaded);
+
+		CommentTree { post, fetched: Utc::now(), missing }
+	}
+
+	/// Serializes the tree to a json document
+	pub fn to_archive_json(&self) -> String {
+		let mut doc = Map::new();
+		doc.insert("version".to_string(), Value::from(ARCHIVE_VERSION));
+		doc.insert("fetched".to_string(), Value::from(self.fetched.to_rfc3339()));
+		doc.insert("missing".to_string(), Value::from(self.missing as u64));
+		doc.insert("post".to_string(), archive_post(&self.post));
+
+		json::to_string(&Value::Object(doc)).unwrap()
+	}
+
+	/// Loads a tree from a json document written by `to_archive_json`
+	pub fn from_archive_json(archive: &str) -> Result<CommentTree, Error> {
+		let doc: Value = json::from_str(archive)?;
+		if doc["version"].as_u64() != Some(ARCHIVE_VERSION) {
+			return Err(format_err!("Unsupported archive version {}", doc["version"]));
+		}
+
+		Ok(CommentTree {
+			post: restore_post(&doc["post"])?,
+			fetched: field(&doc, "fetched", restore_time)?,
+			missing: field(&doc, "missing", |val| val.as_u64())? as usize,
+		})
+	}
+}
+
+fn archive_time(time: Option<DateTime<Utc>>) -> Value {
+	time.map(|time| Value::from(time.to_rfc3339())).unwrap_or(Value::Null)
+}
+
+fn archive_post(post: &Post) -> Value {
+	let mut val = Map::new();
+	val.insert("id".to_string(), Value::from(post.id.as_str()));
+	val.insert("title".to_string(), Value::from(post.title.as_str()));
+	val.insert("author".to_string(), post.author.as_ref().map(|t| Value::from(t.as_str())).unwrap_or(Value::Null));
+	val.insert("subreddit".to_string(), Value::from(post.subreddit.as_str()));
+	val.insert("ups".to_string(), Value::from(post.ups));
+	val.insert("downs".to_string(), Value::from(post.downs));
+	val.insert("score".to_string(), Value::from(post.score));
+	val.insert("num_comments".to_string(), Value::from(post.num_comments));
+	val.insert("url".to_string(), Value::from(post.url.as_str()));
+	val.insert("selftext".to_string(), Value::from(post.selftext.as_str()));
+	val.insert("created_utc".to_string(), archive_time(post.created_utc));
+	val.insert("edited".to_string(), archive_time(post.edited));
+	val.insert("stickied".to_string(), Value::from(post.stickied));
+	val.insert("gilded".to_string(), Value::from(post.gilded));
+	val.insert("locked".to_string(), Value::from(post.locked));
+	val.insert("archived".to_string(), Value::from(post.archived));
+	val.insert("comments".to_string(), archive_comments(&post.comments));
+
+	Value::Object(val)
+}
+
+fn archive_comments(comments: &Listing<Comment>) -> Value {
+	Value::Array(comments.children.iter().map(archive_comment).collect())
+}
+
+fn archive_comment(comment: &Comment) -> Value {
+	let mut val = Map::new();
+	val.insert("id".to_string(), Value::from(comment.id.as_str()));
+	val.insert("name".to_string(), Value::from(comment.name.as_str()));
+	val.insert("parent_id".to_string(), Value::from(comment.parent_id.as_str()));
+	val.insert("link_id".to_string(), Value::from(comment.link_id.as_str()));
+	val.insert("author".to_string(), comment.author.as_ref().map(|t| Value::from(t.as_str())).unwrap_or(Value::Null));
+	val.insert("ups".to_string(), Value::from(comment.ups));
+	val.insert("downs".to_string(), Value::from(comment.downs));
+	val.insert("score".to_string(), Value::from(comment.score));
+	val.insert("body".to_string(), Value::from(comment.body.as_str()));
+	val.insert("is_submitter".to_string(), Value::from(comment.is_submitter));
+	val.insert("stickied".to_string(), Value::from(comment.stickied));
+	val.insert("subreddit".to_string(), Value::from(comment.subreddit.as_str()));
+	val.insert("score_hidden".to_string(), Value::from(comment.score_hidden));
+	val.insert("created_utc".to_string(), archive_time(comment.created_utc));
+	val.insert("edited".to_string(), archive_time(comment.edited));
+	val.insert("permalink".to_string(), Value::from(comment.permalink.as_str()));
+	val.insert("replies".to_string(), archive_comments(&comment.replies));
+
+	Value::Object(val)
+}
+
+/// Reads a field of an archived thing, failing if it is missing or has the wrong type
+fn field<T, F: Fn(&Value) -> Option<T>>(val: &Value, key: &str, read: F) -> Result<T, Error> {
+	read(&val[key]).ok_or_else(|| {
+		Error::from(ParseError {
+			thing_type: format!("archived {}", key),
+			json: json::to_string_pretty(val).unwrap(),
+		})
+	})
+}
+
+fn restore_string(val: &Value) -> Option<String> {
+	val.as_str().map(|t| t.to_string())
+}
+
+fn restore_time(val: &Value) -> Option<DateTime<Utc>> {
+	val.as_str().and_then(|t| DateTime::parse_from_rfc3339(t).ok()).map(|t| t.with_timezone(&Utc))
+}
+
+/// Reads a field that may be null
+fn optional<T, F: Fn(&Value) -> Option<T>>(val: &Value, key: &str, read: F) -> Result<Option<T>, Error> {
+	if val[key].is_null() {
+		Ok(None)
+	} else {
+		field(val, key, read).map(Some)
+	}
+}
+
+fn restore_post(val: &Value) -> Result<Post, Error> {
+	Ok(Post {
+		id: field(val, "id", restore_string)?,
+		title: field(val, "title", restore_string)?,
+		author: optional(val, "author", restore_string)?,
+		subreddit: field(val, "subreddit", restore_string)?,
+		ups: field(val, "ups", Value::as_i64)?,
+		downs: field(val, "downs", Value::as_i64)?,
+		score: field(val, "score", Value::as_i64)?,
+		num_comments: field(val, "num_comments", Value::as_i64)?,
+		url: field(val, "url", restore_string)?,
+		selftext: field(val, "selftext", restore_string)?,
+		created_utc: optional(val, "created_utc", restore_time)?,
+		stickied: field(val, "stickied", Value::as_bool)?,
+		gilded: field(val, "gilded", Value::as_i64)?,
+		edited: optional(val, "edited", restore_time)?,
+		removed_by_category: None,
+		banned_by: None,
+		approved_by: None,
+		num_reports: 0,
+		mod_reports: None,
+		user_reports: None,
+		likes: None,
+		saved: false,
+		hidden: false,
+		suggested_sort: None,
+		flair_template_id: None,
+		preview: None,
+		thumbnail: None,
+		poll_data: None,
+		locked: field(val, "locked", Value::as_bool)?,
+		archived: field(val, "archived", Value::as_bool)?,
+		crosspost_parent: None,
+		crosspost_parent_list: Vec::new(),
+		comments: restore_comments(&val["comments"])?,
+	})
+}
+
+fn restore_comments(val: &Value) -> Result<Listing<Comment>, Error> {
+	let mut comments = Listing::new();
+	match val.as_array() {
+		Some(array) => {
+			for comment in array {
+				comments.children.push_back(restore_comment(comment)?);
+			}
+		}
+		None => {
+			return Err(Error::from(ParseError {
+				thing_type: "archived comments".to_string(),
+				json: json::to_string_pretty(val).unwrap(),
+			}))
+		}
+	}
+
+	Ok(comments)
+}
+
+fn restore_comment(val: &Value) -> Result<Comment, Error> {
+	Ok(Comment {
+		edited: optional(val, "edited", restore_time)?,
+		id: field(val, "id", restore_string)?,
+		parent_id: field(val, "parent_id", restore_string)?,
+		link_id: field(val, "link_id", restore_string)?,
+		author: optional(val, "author", restore_string)?,
+		ups: field(val, "ups", Value::as_i64)?,
+		downs: field(val, "downs", Value::as_i64)?,
+		score: field(val, "score", Value::as_i64)?,
+		body: field(val, "body", restore_string)?,
+		is_submitter: field(val, "is_submitter", Value::as_bool)?,
+		stickied: field(val, "stickied", Value::as_bool)?,
+		subreddit: field(val, "subreddit", restore_string)?,
+		score_hidden: field(val, "score_hidden", Value::as_bool)?,
+		name: field(val, "name", restore_string)?,
+		created_utc: optional(val, "created_utc", restore_time)?,
+		permalink: field(val, "permalink", restore_string)?,
+		removed_by_category: None,
+		banned_by: None,
+		approved_by: None,
+		num_reports: 0,
+		mod_reports: None,
+		user_reports: None,
+		likes: None,
+		saved: false,
+		replies: restore_comments(&val["replies"])?,
+	})
+}
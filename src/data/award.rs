@@ -0,0 +1,155 @@
+use chrono::{DateTime, Utc};
+use failure::Error;
+use json::{self, Value};
+
+use data::time::parse_timestamp;
+use data::Thing;
+use errors::ParseError;
+use App;
+
+/// A struct representing an award that can be given to posts and comments
+#[derive(Debug, Clone)]
+pub struct AwardInfo {
+	/// Id of the award
+	pub id: String,
+	/// Name of the award
+	pub name: String,
+	/// Price of the award in coins
+	pub coin_price: i64,
+	/// Url of the icon of the award
+	pub icon_url: String,
+}
+
+impl Thing for AwardInfo {
+	fn from_value(val: &Value, _app: &App) -> Result<AwardInfo, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "AwardInfo".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let id = match val["id"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let name = match val["name"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let coin_price = match val["coin_price"].as_i64() {
+			Some(t) => t,
+			None => out!(val),
+		};
+		let icon_url = match val["icon_url"].as_str() {
+			Some(t) => t.replace("&amp;", "&"),
+			None => out!(val),
+		};
+
+		Ok(AwardInfo { id, name, coin_price, icon_url })
+	}
+}
+
+/// An award the user currently authorized gave to a post or comment
+#[derive(Debug, Clone)]
+pub struct Gilding {
+	/// The award that was given
+	pub award: AwardInfo,
+	/// Fullname of the post or comment the award was given to, if reddit included it
+	pub thing: Option<String>,
+	/// The time the award was given
+	pub created_utc: Option<DateTime<Utc>>,
+}
+
+/// The awards the user currently authorized gave, newest first
+#[derive(Debug, Clone)]
+pub struct Gildings {
+	/// The awards that were given
+	pub given: Vec<Gilding>,
+}
+
+impl Gildings {
+	/// Get the amount of coins spent on the awards
+	pub fn total_coins(&self) -> i64 {
+		self.given.iter().map(|gilding| gilding.award.coin_price).sum()
+	}
+}
+
+impl Thing for Gildings {
+	fn from_value(val: &Value, app: &App) -> Result<Gildings, Error> {
+		let children = match val["data"]["children"].as_array() {
+			Some(children) => children,
+			None => {
+				return Err(Error::from(ParseError {
+					thing_type: "Gildings".to_string(),
+					json: json::to_string_pretty(val).unwrap(),
+				}))
+			}
+		};
+
+		let mut given = Vec::new();
+		for child in children {
+			let data = &child["data"];
+			given.push(Gilding {
+				award: AwardInfo::from_value(data, app)?,
+				thing: data["thing_id"].as_str().map(|t| t.to_string()),
+				created_utc: parse_timestamp(&data["created_utc"]),
+			});
+		}
+
+		Ok(Gildings { given })
+	}
+}
+
+/// A trophy shown on the profile of a user, e.g. for the age of the account
+#[derive(Debug, Clone)]
+pub struct Trophy {
+	/// Name of the trophy, e.g. `Three-Year Club`
+	pub name: String,
+	/// Description of why the trophy was granted, if it has one
+	pub description: Option<String>,
+	/// Url of the icon of the trophy
+	pub icon_url: Option<String>,
+	/// The time the trophy was granted, if reddit included it
+	pub granted_at: Option<DateTime<Utc>>,
+	/// Id of the award the trophy is for, if it was granted for an award
+	pub award_id: Option<String>,
+}
+
+impl Thing for Trophy {
+	fn from_value(val: &Value, _app: &App) -> Result<Trophy, Error> {
+		// Each trophy is wrapped in a t6 thing
+		let data = &val["data"];
+		let name = match data["name"].as_str() {
+			Some(t) => t.to_string(),
+			None => {
+				return Err(Error::from(ParseError {
+					thing_type: "Trophy".to_string(),
+					json: json::to_string_pretty(val).unwrap(),
+				}))
+			}
+		};
+		let string = |key: &str| data[key].as_str().filter(|t| !t.is_empty()).map(|t| t.replace("&amp;", "&"));
+
+		Ok(Trophy {
+			name,
+			description: string("description"),
+			icon_url: string("icon_70").or_else(|| string("icon_40")),
+			granted_at: parse_timestamp(&data["granted_at"]),
+			award_id: string("award_id"),
+		})
+	}
+}
+
+/// Parses the trophies of a `TrophyList` response
+pub(crate) fn trophy_list(val: &Value, app: &App) -> Result<Vec<Trophy>, Error> {
+	match val["data"]["trophies"].as_array() {
+		Some(trophies) => trophies.iter().map(|trophy| Trophy::from_value(trophy, app)).collect(),
+		None => Err(Error::from(ParseError {
+			thing_type: "TrophyList".to_string(),
+			json: json::to_string_pretty(val).unwrap(),
+		})),
+	}
+}
@@ -1,7 +1,12 @@
+use std::cmp::Reverse;
+
+use chrono::{DateTime, Utc};
 use json;
 use json::Value;
 
-use data::{Listing, Thing};
+use data::reports::{parse_mod_reports, parse_user_reports};
+use data::time::{parse_edited, parse_timestamp};
+use data::{Listing, Thing, UserLookup};
 use errors::ParseError;
 use failure::{err_msg, Error};
 use App;
@@ -12,24 +17,104 @@ use App;
 pub enum Thread {
 	/// A comment
 	Comment(Box<Comment>),
-	/// A vector of strings that are the ids of comments that need to be loaded
-	More(Vec<String>),
+	/// Comments that need to be loaded
+	More(MoreComments),
+}
+
+/// A stub in a comment tree standing for comments reddit didn't include, which reddit shows as
+/// "load more comments" or "continue this thread"
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoreComments {
+	/// Id of the stub, which is needed to load its comments with `App::more_children`
+	pub id: String,
+	/// Fullname of the comment or post the comments are replies to
+	pub parent_id: String,
+	/// Amount of comments the stub stands for, including their replies
+	pub count: u32,
+	/// Ids of the comments directly under the parent that the stub stands for
+	pub children: Vec<String>,
+}
+
+impl MoreComments {
+	/// Whether the stub is a "continue this thread" link, which stands for a thread that is too
+	/// deep to be included. Its comments can't be loaded with `App::more_children`, only by
+	/// loading the subtree of the parent, e.g. with `App::get_comment_subtree`
+	pub fn is_continue_thread(&self) -> bool {
+		self.count == 0 && self.children.is_empty()
+	}
+
+	/// Collects the stubs of a comment listing and of all replies in it, with the stubs standing
+	/// for the most comments first
+	/// # Arguments
+	/// * `listing_data` - The children of a comment listing, as reddit sends them
+	pub fn collect(listing_data: &Value, app: &App) -> Result<Vec<MoreComments>, Error> {
+		let mut stubs = Vec::new();
+		collect_more(listing_data, app, &mut stubs)?;
+		stubs.sort_by_key(|stub| Reverse(stub.count));
+		Ok(stubs)
+	}
+}
+
+fn collect_more(listing_data: &Value, app: &App, stubs: &mut Vec<MoreComments>) -> Result<(), Error> {
+	for item in listing_data.as_array().into_iter().flatten() {
+		match item["kind"].as_str() {
+			Some("more") => stubs.push(MoreComments::from_value(item, app)?),
+			Some("t1") => collect_more(&item["data"]["replies"]["data"]["children"], app, stubs)?,
+			_ => {}
+		}
+	}
+	Ok(())
+}
+
+impl Thing for MoreComments {
+	fn from_value(val: &Value, _app: &App) -> Result<MoreComments, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "MoreComments".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let data = &val["data"];
+		let id = match data["id"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let parent_id = match data["parent_id"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let children = match data["children"].as_array() {
+			Some(children) => children.iter().filter_map(|child| child.as_str()).map(|child| child.to_string()).collect(),
+			None => out!(val),
+		};
+
+		Ok(MoreComments {
+			id,
+			parent_id,
+			count: data["count"].as_u64().unwrap_or(0) as u32,
+			children,
+		})
+	}
 }
 
 /// A struct representing a reddit comment.
 /// Does not contain all fields possible in a comment yet.
 #[derive(Debug, Clone)]
 pub struct Comment {
-	/// The amount of seconds since the comment has been edited, if it has been.
-	pub edited: Option<f64>,
+	/// The time the comment was last edited, if it has been.
+	pub edited: Option<DateTime<Utc>>,
 	/// The id of the comment
 	pub id: String,
 	/// The id of the comments parent, can be either t1 or t3
 	pub parent_id: String,
-	/// The link that the comment is present in
+	/// The link that the comment is present in. Can be empty for deleted comments that are not
+	/// parsed as part of a listing
 	pub link_id: String,
-	/// The username of the author of the comment
-	pub author: String,
+	/// The username of the author of the comment, `None` if the author's account was deleted
+	pub author: Option<String>,
 	/// The amount of upvotes the comment has recieved
 	pub ups: i64,
 	/// The amount of downvotes the comment has recieved
@@ -49,10 +134,31 @@ pub struct Comment {
 	pub score_hidden: bool,
 	/// The fullname of the comment (includes the t1_ prefix)
 	pub name: String,
-	/// The unix time the comment was created.
-	pub created_utc: f64,
+	/// The time the comment was created. `None` if reddit didn't report it, which happens for
+	/// some deleted comments
+	pub created_utc: Option<DateTime<Utc>>,
 	/// The permalink path of this comment.
 	pub permalink: String,
+	/// The category of the removal of this comment, if it was removed. Only present when
+	/// authorized as a moderator
+	pub removed_by_category: Option<String>,
+	/// Name of the moderator that removed this comment. Only present when authorized as a moderator
+	pub banned_by: Option<String>,
+	/// Name of the moderator that approved this comment. Only present when authorized as a moderator
+	pub approved_by: Option<String>,
+	/// Amount of reports this comment has recieved. Always 0 unless authorized as a moderator
+	pub num_reports: i64,
+	/// Reports by moderators, as pairs of the reason and the moderator. `None` unless authorized as
+	/// a moderator
+	pub mod_reports: Option<Vec<(String, String)>>,
+	/// Reports by users, as pairs of the reason and the amount of reports. `None` unless
+	/// authorized as a moderator
+	pub user_reports: Option<Vec<(String, i64)>>,
+	/// Vote of the user currently authorized on this comment: `Some(true)` for an upvote,
+	/// `Some(false)` for a downvote and `None` if not voted or not authorized
+	pub likes: Option<bool>,
+	/// Whether the user currently authorized saved this comment. Always false unless authorized
+	pub saved: bool,
 	/// A listing of replies to this comment
 	pub replies: Listing<Comment>,
 }
@@ -70,11 +176,7 @@ impl Thing for Comment {
 		}
 
 		let val = &val["data"];
-		let edited = match val["edited"] {
-			Value::Bool(_) | Value::Null => None,
-			Value::Number(ref num) => num.as_f64(),
-			_ => return Err(format_err!("Unexpected value for \"edited\": {}", val["edited"])),
-		};
+		let edited = parse_edited(&val["edited"])?;
 		let id: String = match val["id"].as_str() {
 			Some(t) => t.to_string(),
 			None => out!(val),
@@ -83,13 +185,12 @@ impl Thing for Comment {
 			Some(t) => t.to_string(),
 			None => out!(val),
 		};
-		let link_id: String = match val["link_id"].as_str() {
-			Some(t) => t.to_string(),
-			None => out!(val),
-		};
-		let author: String = match val["author"].as_str() {
-			Some(t) => t.to_string(),
-			None => out!(val),
+		// Deleted comments sometimes come without a link id, in which case the listing they are in fills it in
+		let link_id: String = val["link_id"].as_str().unwrap_or("").to_string();
+		let author: Option<String> = match val["author"] {
+			Value::String(ref t) if t != "[deleted]" => Some(t.to_string()),
+			Value::String(_) | Value::Null => None,
+			_ => out!(val),
 		};
 		let ups: i64 = match val["ups"].as_i64() {
 			Some(t) => t,
@@ -103,9 +204,10 @@ impl Thing for Comment {
 			Some(t) => t,
 			None => out!(val),
 		};
-		let body: String = match val["body"].as_str() {
-			Some(t) => t.to_string(),
-			None => out!(val),
+		let body: String = match val["body"] {
+			Value::String(ref t) => t.to_string(),
+			Value::Null => String::new(),
+			_ => out!(val),
 		};
 		let is_submitter: bool = match val["is_submitter"].as_bool() {
 			Some(t) => t,
@@ -127,14 +229,21 @@ impl Thing for Comment {
 			Some(t) => t.to_string(),
 			None => out!(val),
 		};
-		let created_utc: f64 = match val["created_utc"].as_f64() {
-			Some(t) => t,
-			None => out!(val),
-		};
+		let created_utc = parse_timestamp(&val["created_utc"]);
 		let permalink: String = match val["permalink"].as_str() {
 			Some(t) => t.to_string(),
 			None => out!(val),
 		};
+		// Moderation info, only present when authorized as a moderator
+		let removed_by_category = val["removed_by_category"].as_str().map(|t| t.to_string());
+		let banned_by = val["banned_by"].as_str().map(|t| t.to_string());
+		let approved_by = val["approved_by"].as_str().map(|t| t.to_string());
+		let num_reports = val["num_reports"].as_i64().unwrap_or(0);
+		let mod_reports = parse_mod_reports(&val["mod_reports"]);
+		let user_reports = parse_user_reports(&val["user_reports"]);
+		// User state, only present when authorized
+		let likes = val["likes"].as_bool();
+		let saved = val["saved"].as_bool().unwrap_or(false);
 		let replies: Listing<Comment> = match val["replies"] {
 			Value::String(_) => Listing::new(),
 			Value::Object(_) => Listing::from_value(&val["replies"]["data"]["children"], &link_id, app).unwrap(),
@@ -158,7 +267,33 @@ impl Thing for Comment {
 			name,
 			created_utc,
 			permalink,
+			removed_by_category,
+			banned_by,
+			approved_by,
+			num_reports,
+			mod_reports,
+			user_reports,
+			likes,
+			saved,
 			replies,
 		})
 	}
 }
+
+impl Comment {
+	/// Whether the comment was removed by a moderator or by reddit
+	pub fn is_removed(&self) -> bool {
+		self.body == "[removed]"
+	}
+
+	/// Get the info of the author of the comment, without sending a request if the account of the
+	/// author was deleted
+	pub fn author_info(&self, app: &App) -> Result<UserLookup, Error> {
+		UserLookup::lookup(self.author.as_deref(), app)
+	}
+
+	/// Whether the comment (or the account of its author) was deleted by its author
+	pub fn is_deleted(&self) -> bool {
+		!self.is_removed() && (self.author.is_none() || self.body == "[deleted]")
+	}
+}
@@ -0,0 +1,38 @@
+use std::time::{Duration, Instant};
+
+/// What orca observed of the comment cooldown of the account currently authorized in a
+/// subreddit, which reddit applies to accounts with little karma there
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CommentCooldown {
+	/// When a comment in the subreddit was last ratelimited, and the wait reddit asked for
+	pub last_ratelimited: Option<(Instant, Duration)>,
+	/// When a comment in the subreddit last succeeded
+	pub last_success: Option<Instant>,
+	/// The longest wait reddit asked for in the subreddit, which is the closest known to the
+	/// length of the cooldown
+	pub cooldown: Option<Duration>,
+}
+
+impl CommentCooldown {
+	pub(crate) fn record_ratelimit(&mut self, wait: Duration, now: Instant) {
+		self.last_ratelimited = Some((now, wait));
+		self.cooldown = Some(self.cooldown.map_or(wait, |cooldown| cooldown.max(wait)));
+	}
+
+	pub(crate) fn record_success(&mut self, now: Instant) {
+		self.last_success = Some(now);
+	}
+
+	/// Estimates how long to wait before commenting in the subreddit is allowed again at a point
+	/// in time. `None` if no wait is expected
+	pub fn estimated_wait_at(&self, now: Instant) -> Option<Duration> {
+		let remaining = |since: Instant, wait: Duration| (since + wait).checked_duration_since(now).filter(|remaining| *remaining > Duration::from_secs(0));
+
+		let ratelimited = self.last_ratelimited.and_then(|(since, wait)| remaining(since, wait));
+		let cooling = match (self.last_success, self.cooldown) {
+			(Some(success), Some(cooldown)) => remaining(success, cooldown),
+			_ => None,
+		};
+		ratelimited.max(cooling)
+	}
+}
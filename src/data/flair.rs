@@ -0,0 +1,84 @@
+use failure::Error;
+use json::{self, Value};
+
+use data::Thing;
+use errors::ParseError;
+use App;
+
+/// A flair the user currently authorized can choose
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlairChoice {
+	/// Id of the flair template, which is passed to `App::select_flair`
+	pub template_id: String,
+	/// Text of the flair
+	pub text: String,
+	/// Css class of the flair on old reddit
+	pub css_class: String,
+	/// Whether the user can edit the text of the flair
+	pub text_editable: bool,
+}
+
+/// The flair that is currently applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedFlair {
+	/// Id of the flair template, `None` for flair set without a template
+	pub template_id: Option<String>,
+	/// Text of the flair
+	pub text: Option<String>,
+	/// Css class of the flair on old reddit
+	pub css_class: Option<String>,
+}
+
+/// The flairs of a subreddit as the user currently authorized sees them when submitting or
+/// editing a post, which only includes the flairs users can assign themselves
+#[derive(Debug, Clone)]
+pub struct FlairChoices {
+	/// The flair currently applied to the post, `None` if it has no flair or no post was given
+	pub current: Option<AppliedFlair>,
+	/// The flairs that can be chosen
+	pub choices: Vec<FlairChoice>,
+}
+
+impl Thing for FlairChoices {
+	fn from_value(val: &Value, _app: &App) -> Result<FlairChoices, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "FlairChoices".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let string = |val: &Value| val.as_str().filter(|t| !t.is_empty()).map(|t| t.to_string());
+
+		let mut choices = Vec::new();
+		match val["choices"].as_array() {
+			Some(array) => {
+				for choice in array {
+					let template_id = match string(&choice["flair_template_id"]) {
+						Some(t) => t,
+						None => out!(val),
+					};
+					choices.push(FlairChoice {
+						template_id,
+						text: choice["flair_text"].as_str().unwrap_or("").to_string(),
+						css_class: choice["flair_css_class"].as_str().unwrap_or("").to_string(),
+						text_editable: choice["flair_text_editable"].as_bool().unwrap_or(false),
+					});
+				}
+			}
+			None => out!(val),
+		}
+
+		let current = &val["current"];
+		let current = AppliedFlair {
+			template_id: string(&current["flair_template_id"]),
+			text: string(&current["flair_text"]),
+			css_class: string(&current["flair_css_class"]),
+		};
+		let current = if current.template_id.is_none() && current.text.is_none() && current.css_class.is_none() { None } else { Some(current) };
+
+		Ok(FlairChoices { current, choices })
+	}
+}
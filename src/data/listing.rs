@@ -4,10 +4,10 @@ use std::default::Default;
 use json;
 use json::Value;
 
-use data::{Comment, Thing};
+use data::{Comment, MoreComments, Thing};
 use App;
 
-use errors::ParseError;
+use errors::{DistMismatch, ParseError, RedditError};
 use failure::Error;
 
 /// A listing of Things. Has special implementations, currently just for Comments.
@@ -15,12 +15,56 @@ use failure::Error;
 pub struct Listing<T> {
 	/// The contents of the Listing
 	pub children: VecDeque<T>,
+	/// Amount of children reddit said it sent, if it did
+	pub dist: Option<u64>,
+	/// The region the listing was filtered for, if it was
+	pub geo_filter: Option<String>,
+	/// Modhash of the user, which reddit sometimes includes
+	pub modhash: Option<String>,
 }
 
 impl<T> Listing<T> {
 	/// Creates a new empty listing
 	pub fn new() -> Listing<T> {
-		Listing { children: VecDeque::new() }
+		Listing {
+			children: VecDeque::new(),
+			dist: None,
+			geo_filter: None,
+			modhash: None,
+		}
+	}
+
+	/// Reads the metadata of the listing from the `data` of a json listing
+	pub(crate) fn read_envelope(&mut self, data: &Value) {
+		let string = |key: &str| data[key].as_str().filter(|t| !t.is_empty()).map(|t| t.to_string());
+		self.dist = data["dist"].as_u64();
+		self.geo_filter = string("geo_filter");
+		self.modhash = string("modhash");
+	}
+
+	/// Adds a child parsed from a json listing. A child that failed to parse is skipped with a
+	/// warning instead of failing the whole listing, which `verify_dist` notices
+	pub(crate) fn push_parsed(&mut self, child: Result<T, Error>) {
+		match child {
+			Ok(child) => self.children.push_back(child),
+			Err(e) => warn!("Skipping a child of a listing that failed to parse: {}", e),
+		}
+	}
+
+	/// Checks that the listing has as many children as reddit said it sent, to notice children
+	/// that were skipped while parsing. Listings without a `dist` always pass, which includes
+	/// comment trees, where `more` stubs are replaced by the comments they stand for.
+	pub fn verify_dist(&self) -> Result<(), DistMismatch> {
+		match self.dist {
+			Some(dist) if dist != self.children.len() as u64 => Err(DistMismatch { dist, parsed: self.children.len() }),
+			_ => Ok(()),
+		}
+	}
+
+	/// Whether the listing has no children. A subreddit that exists but has no posts gives an
+	/// empty listing, while a subreddit that can't be read fails with an error instead
+	pub fn is_empty(&self) -> bool {
+		self.children.is_empty()
 	}
 }
 
@@ -77,29 +121,27 @@ impl Listing<Comment> {
 		}
 	}
 
-	/// Parses the listing from json, fetching more comments as necessary.
+	/// Parses the listing from json, fetching more comments as necessary. Comments that fail to
+	/// parse are skipped with a warning, so a single malformed comment doesn't lose the others.
 	pub fn from_value(listing_data: &Value, post_id: &str, app: &App) -> Result<Listing<Comment>, Error> {
 		let mut listing: Listing<Comment> = Listing::new();
 
 		if let Some(array) = listing_data.as_array() {
 			for item in array {
-				let kind = item["kind"].as_str().unwrap();
+				let kind = item["kind"].as_str().unwrap_or("");
 				if kind == "t1" {
-					listing.children.push_back(if let Ok(c) = Comment::from_value(item, app) {
+					listing.push_parsed(Comment::from_value(item, app).map(|mut c| {
+						if c.link_id.is_empty() && !post_id.is_empty() {
+							c.link_id = if post_id.starts_with("t3_") { post_id.to_string() } else { format!("t3_{}", post_id) };
+						}
 						c
-					} else {
-						return Err(Error::from(ParseError {
-							thing_type: "Listing<Comment>".to_string(),
-							json: json::to_string_pretty(listing_data).unwrap(),
-						}));
-					});
+					}));
 				} else if kind == "more" {
-					let more = item["data"]["children"].as_array().unwrap();
-					let more_id = item["data"]["id"].as_str().unwrap();
-					if !more.is_empty() {
-						debug!("Need some children {}", json::to_string_pretty(more).unwrap());
-						let more = more.iter().map(|i| i.as_str().unwrap()).collect::<Vec<&str>>();
-						for child in app.more_children(post_id, more_id, &more)? {
+					let more = MoreComments::from_value(item, app)?;
+					if !more.children.is_empty() {
+						debug!("Need some children {:?}", more.children);
+						let children = more.children.iter().map(|i| i.as_str()).collect::<Vec<&str>>();
+						for child in app.more_children(post_id, &more.id, &children)? {
 							listing.children.push_back(child);
 						}
 						trace!("Successfully got children");
@@ -116,3 +158,66 @@ impl Listing<Comment> {
 		}
 	}
 }
+
+/// Gets the children of the comment listing from the response to a request for the comments of a
+/// post, which is an array of the listing of the post and the listing of the comments
+pub(crate) fn comment_listing(resp: &Value, request: &str) -> Result<Value, Error> {
+	if resp[1]["kind"] == "Listing" {
+		return Ok(resp[1]["data"]["children"].clone());
+	}
+
+	if resp["reason"] == "over18" {
+		Err(Error::from(RedditError::NsfwGate { request: request.to_string() }))
+	} else {
+		Err(Error::from(RedditError::BadResponse {
+			request: request.to_string(),
+			response: json::to_string(resp).unwrap(),
+		}))
+	}
+}
+
+/// The error for an empty listing of a subreddit that can't be read, which reddit sometimes sends
+/// with a successful status for private and banned subreddits instead of an error. Uses the
+/// `reason` of the listing if it has one, and the about of the subreddit otherwise
+/// # Arguments
+/// * `listing` - The empty listing
+/// * `sub` - Name of the subreddit
+/// * `about` - Response to `/r/{sub}/about`
+/// # Returns
+/// `None` if the subreddit can be read and is just empty
+pub(crate) fn empty_listing_error(listing: &Value, sub: &str, about: Result<Value, Error>) -> Option<Error> {
+	let sub = sub.to_string();
+	match listing["reason"].as_str() {
+		Some("private") => return Some(Error::from(RedditError::SubredditPrivate { sub })),
+		Some("banned") => return Some(Error::from(RedditError::SubredditBanned { sub })),
+		_ => {}
+	}
+
+	match about.map_err(|e| e.downcast::<RedditError>()) {
+		Err(Ok(e @ RedditError::SubredditPrivate { .. })) | Err(Ok(e @ RedditError::SubredditBanned { .. })) | Err(Ok(e @ RedditError::SubredditNotFound { .. })) => Some(Error::from(e)),
+		Ok(about) => {
+			let data = &about["data"];
+			let flag = |key: &str| data[key].as_bool().unwrap_or(false);
+			if data["subreddit_type"] == "private" && !flag("user_is_contributor") && !flag("user_is_moderator") {
+				Some(Error::from(RedditError::SubredditPrivate { sub }))
+			} else {
+				None
+			}
+		}
+		// The listing isn't failed because the check itself failed
+		Err(_) => None,
+	}
+}
+
+/// Checks that a response is a json listing, so an empty listing can be told apart from a response
+/// that isn't a listing at all, like the empty object reddit sometimes sends instead of an error
+pub(crate) fn check_listing(resp: Value, request: &str) -> Result<Value, Error> {
+	if resp["kind"] == "Listing" && resp["data"]["children"].is_array() {
+		Ok(resp)
+	} else {
+		Err(Error::from(RedditError::BadResponse {
+			request: request.to_string(),
+			response: json::to_string(&resp).unwrap(),
+		}))
+	}
+}
@@ -0,0 +1,175 @@
+use chrono::{DateTime, Utc};
+use failure::Error;
+use json::{self, Value};
+
+use data::time::parse_timestamp;
+use data::{Listing, Thing};
+use errors::ParseError;
+use App;
+
+/// A struct representing an item in the inbox of the user currently authorized, which is either a
+/// private message or a notification about a comment, like a username mention
+#[derive(Debug, Clone)]
+pub struct Message {
+	/// Id of the message
+	pub id: String,
+	/// Fullname of the message, which is the fullname of the comment for comment notifications
+	pub name: String,
+	/// Author of the message, `None` if the author's account was deleted or the message is from
+	/// reddit
+	pub author: Option<String>,
+	/// Subject of the message
+	pub subject: String,
+	/// Body of the message
+	pub body: String,
+	/// Subreddit of the comment for comment notifications, or the subreddit the message was sent
+	/// from or to
+	pub subreddit: Option<String>,
+	/// Permalink of the comment for comment notifications. Empty for private messages
+	pub context: String,
+	/// Recipient of the message, `#<subreddit>` for messages to the moderators of a subreddit
+	pub dest: Option<String>,
+	/// Fullname of the first message of the conversation the message is a reply in, `None` if it
+	/// is the first message
+	pub first_message_name: Option<String>,
+	/// Whether the message is a notification about a comment
+	pub was_comment: bool,
+	/// Whether the message is unread
+	pub new: bool,
+	/// The time the message was sent
+	pub created_utc: Option<DateTime<Utc>>,
+}
+
+impl Thing for Message {
+	fn from_value(val: &Value, _app: &App) -> Result<Message, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "Message".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let val = &val["data"];
+		let id = match val["id"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let name = match val["name"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let author = match val["author"] {
+			Value::String(ref t) if t != "[deleted]" => Some(t.to_string()),
+			Value::String(_) | Value::Null => None,
+			_ => out!(val),
+		};
+		let subject = match val["subject"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let body = match val["body"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+
+		Ok(Message {
+			id,
+			name,
+			author,
+			subject,
+			body,
+			subreddit: val["subreddit"].as_str().map(|t| t.to_string()),
+			context: val["context"].as_str().unwrap_or("").to_string(),
+			dest: val["dest"].as_str().map(|t| t.to_string()),
+			first_message_name: val["first_message_name"].as_str().map(|t| t.to_string()),
+			was_comment: val["was_comment"].as_bool().unwrap_or(false),
+			new: val["new"].as_bool().unwrap_or(false),
+			created_utc: parse_timestamp(&val["created_utc"]),
+		})
+	}
+}
+
+/// A conversation of private messages, with the first message and the replies to it
+#[derive(Debug, Clone)]
+pub struct MessageThread {
+	/// The first message of the conversation
+	pub root: Message,
+	/// The replies in the conversation, oldest first
+	pub replies: Vec<Message>,
+}
+
+impl Thing for MessageThread {
+	fn from_value(val: &Value, app: &App) -> Result<MessageThread, Error> {
+		let root = &val["data"]["children"][0];
+		if root["kind"] != "t4" {
+			return Err(Error::from(ParseError {
+				thing_type: "MessageThread".to_string(),
+				json: json::to_string_pretty(val).unwrap(),
+			}));
+		}
+
+		let root_message = Message::from_value(root, app)?;
+		let mut replies = Vec::new();
+		collect_replies(&root["data"]["replies"], app, &mut replies)?;
+		// Replies can be nested, but every reply links to the first message of the conversation
+		replies.retain(|reply: &Message| reply.first_message_name.as_ref().is_none_or(|first| *first == root_message.name));
+		replies.sort_by_key(|reply| reply.created_utc);
+
+		Ok(MessageThread { root: root_message, replies })
+	}
+}
+
+/// Collects the messages of a `replies` listing and of the listings nested in it. Messages
+/// without replies have an empty string instead of a listing
+fn collect_replies(val: &Value, app: &App, replies: &mut Vec<Message>) -> Result<(), Error> {
+	for child in val["data"]["children"].as_array().into_iter().flatten() {
+		replies.push(Message::from_value(child, app)?);
+		collect_replies(&child["data"]["replies"], app, replies)?;
+	}
+	Ok(())
+}
+
+/// Whether a message was sent or an identical message had already been sent
+#[derive(Debug, Clone)]
+pub enum SendOutcome {
+	/// The message was sent
+	Sent,
+	/// A message with the same recipient and subject was found among the sent messages after a
+	/// failed attempt, so it wasn't sent again
+	AlreadySent {
+		/// The message that was found
+		existing: Box<Message>,
+	},
+}
+
+impl SendOutcome {
+	/// Whether the message was sent by the last attempt rather than found among the sent messages
+	pub fn sent(&self) -> bool {
+		match *self {
+			SendOutcome::Sent => true,
+			SendOutcome::AlreadySent { .. } => false,
+		}
+	}
+}
+
+/// Finds a message to a recipient with a subject among sent messages, sent no earlier than `since`
+/// # Arguments
+/// * `sent` - The sent messages
+/// * `to` - The recipient, as it is passed to `App::message`
+/// * `subject` - The subject of the message
+/// * `since` - The earliest time the message may have been sent
+pub(crate) fn find_sent(sent: &Listing<Message>, to: &str, subject: &str, since: DateTime<Utc>) -> Option<Message> {
+	// Messages to a subreddit have the subreddit as `#<subreddit>` as their recipient
+	let to = to.trim_start_matches('/');
+	let dest = match to.strip_prefix("r/") {
+		Some(sub) => format!("#{}", sub),
+		None => to.trim_start_matches("u/").to_string(),
+	};
+
+	sent.children
+		.iter()
+		.find(|message| message.dest.as_ref().is_some_and(|d| d.eq_ignore_ascii_case(&dest)) && message.subject == subject && message.created_utc.is_some_and(|created| created >= since))
+		.cloned()
+}
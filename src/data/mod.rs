@@ -1,13 +1,44 @@
+mod action;
+mod archive;
+mod award;
 mod comments;
+mod cooldown;
+mod flair;
 mod listing;
+mod message;
+mod modmail;
+mod pages;
+mod poll;
 mod post;
+mod preview;
+mod reports;
+mod search;
 mod sub;
+mod submit;
+mod subreddit;
 mod thing;
+mod time;
 mod user;
+mod widgets;
 
+pub use self::action::*;
+pub use self::archive::*;
+pub use self::award::*;
 pub use self::comments::*;
+pub use self::cooldown::*;
+pub use self::flair::*;
 pub use self::listing::*;
+pub use self::message::*;
+pub use self::modmail::*;
+pub use self::pages::*;
+pub use self::poll::*;
 pub use self::post::*;
+pub use self::preview::*;
+pub use self::reports::*;
+pub use self::search::*;
 pub use self::sub::*;
+pub use self::submit::*;
+pub use self::subreddit::*;
 pub use self::thing::*;
 pub use self::user::*;
+pub use self::widgets::*;
@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use failure::Error;
+use json::{self, Value};
+
+use data::Thing;
+use errors::ParseError;
+use App;
+
+/// A conversation in new modmail
+#[derive(Debug, Clone)]
+pub struct ModmailConversation {
+	/// Id of the conversation. Note that this is not a fullname
+	pub id: String,
+	/// Subject of the conversation
+	pub subject: String,
+	/// Name of the subreddit the conversation belongs to
+	pub subreddit: String,
+	/// Whether the conversation is highlighted
+	pub is_highlighted: bool,
+	/// Number of messages in the conversation
+	pub num_messages: i64,
+	/// Time of the last update to the conversation
+	pub last_updated: Option<DateTime<Utc>>,
+	/// The full json of the conversation
+	pub raw: Value,
+}
+
+impl Thing for ModmailConversation {
+	fn from_value(val: &Value, _app: &App) -> Result<ModmailConversation, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "ModmailConversation".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let id = match val["id"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let subject = match val["subject"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let subreddit = match val["owner"]["displayName"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let last_updated = val["lastUpdated"].as_str().and_then(|t| DateTime::parse_from_rfc3339(t).ok()).map(|t| t.with_timezone(&Utc));
+
+		Ok(ModmailConversation {
+			id,
+			subject,
+			subreddit,
+			is_highlighted: val["isHighlighted"].as_bool().unwrap_or(false),
+			num_messages: val["numMessages"].as_i64().unwrap_or(0),
+			last_updated,
+			raw: val.clone(),
+		})
+	}
+}
+
+/// A page of modmail conversations
+#[derive(Debug, Clone)]
+pub struct ModmailPage {
+	/// The conversations on this page, newest first
+	pub conversations: Vec<ModmailConversation>,
+	/// Cursor to pass as `after` to get the next page, or `None` if this is the last page. Can be
+	/// stored to resume later.
+	pub after: Option<String>,
+}
+
+impl ModmailPage {
+	/// Parses the response of the conversations listing. The conversations are an object keyed by
+	/// id, ordered by `conversationIds`.
+	pub(crate) fn from_response(resp: &Value, limit: usize, app: &App) -> Result<ModmailPage, Error> {
+		let mut conversations = Vec::new();
+		if let Some(ids) = resp["conversationIds"].as_array() {
+			for id in ids.iter().filter_map(|id| id.as_str()) {
+				conversations.push(ModmailConversation::from_value(&resp["conversations"][id], app)?);
+			}
+		}
+
+		let after = if conversations.len() < limit { None } else { conversations.last().map(|c| c.id.clone()) };
+
+		Ok(ModmailPage { conversations, after })
+	}
+}
@@ -0,0 +1,121 @@
+use std::collections::VecDeque;
+
+use failure::Error;
+use hyper::{Body, Request};
+use json::Value;
+use url::Url;
+
+use App;
+
+/// A struct that walks all pages of a listing, like the posts of a subreddit, yielding the json of
+/// every thing (including its `kind`) in order. The amount of things already loaded is sent as
+/// `count` with every page request, which keeps pagination consistent across deletions. Iteration
+/// stops after the last page, or after the first error.
+pub struct Pages<'a> {
+	url: String,
+	params: Vec<(String, String)>,
+	after: Option<String>,
+	count: usize,
+	dist: Option<u64>,
+	cache: VecDeque<Value>,
+	done: bool,
+	app: &'a App,
+}
+
+impl<'a> Pages<'a> {
+	/// Creates an iterator over the pages of the listing at `url`, sending `params` with every
+	/// request. Listings on `oauth.reddit.com` are requested with authorization.
+	pub(crate) fn new(app: &'a App, url: &str, params: Vec<(String, String)>) -> Pages<'a> {
+		Pages {
+			url: url.to_string(),
+			params,
+			after: None,
+			count: 0,
+			dist: Some(0),
+			cache: VecDeque::new(),
+			done: false,
+			app,
+		}
+	}
+
+	/// Continues the listing after a thing instead of from its start, e.g. to resume a walk that
+	/// was interrupted
+	/// # Arguments
+	/// * `after` - Fullname of the last thing that was already handled, `None` to start at the top
+	pub fn start_after(mut self, after: Option<&str>) -> Pages<'a> {
+		self.after = after.map(|t| t.to_string());
+		self
+	}
+
+	/// The amount of things loaded so far, which is sent as `count` with the next page request
+	pub fn loaded_count(&self) -> usize {
+		self.count
+	}
+
+	/// The fullname of the last thing loaded, which is sent as `after` with the next page request
+	pub fn after(&self) -> Option<&str> {
+		self.after.as_deref()
+	}
+
+	/// The amount of things reddit said it sent in the pages loaded so far, if it said so for
+	/// every page
+	pub fn dist(&self) -> Option<u64> {
+		self.dist
+	}
+
+	/// The url of the listing, without parameters
+	pub fn url(&self) -> &str {
+		&self.url
+	}
+
+	/// The url parameters of the next page request
+	pub(crate) fn page_params(&self) -> Vec<(String, String)> {
+		let mut params = self.params.clone();
+		params.push(("limit".to_string(), "100".to_string()));
+		params.push(("count".to_string(), self.count.to_string()));
+		if let Some(ref after) = self.after {
+			params.push(("after".to_string(), after.clone()));
+		}
+		params
+	}
+
+	/// Adds the things of a page to the cache and advances the cursor
+	pub(crate) fn record_page(&mut self, resp: &Value) {
+		let children = resp["data"]["children"].as_array().cloned().unwrap_or_default();
+		self.count += children.len();
+		self.dist = self.dist.and_then(|dist| resp["data"]["dist"].as_u64().map(|page| dist + page));
+		self.cache.extend(children);
+
+		self.after = resp["data"]["after"].as_str().map(|t| t.to_string());
+		if self.after.is_none() {
+			self.done = true;
+		}
+	}
+
+	fn refresh(&mut self) -> Result<(), Error> {
+		let url = Url::parse_with_params(&self.url, self.page_params())?;
+		let req = Request::get(url.into_string()).body(Body::empty()).unwrap();
+
+		let resp = self.app.conn.run_read_request(req)?;
+		self.record_page(&resp);
+
+		Ok(())
+	}
+}
+
+impl<'a> Iterator for Pages<'a> {
+	type Item = Result<Value, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		while self.cache.is_empty() {
+			if self.done {
+				return None;
+			}
+			if let Err(e) = self.refresh() {
+				self.done = true;
+				return Some(Err(e));
+			}
+		}
+		self.cache.pop_front().map(Ok)
+	}
+}
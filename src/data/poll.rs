@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use failure::Error;
+use json::{self, Value};
+
+use data::time::from_epoch;
+use data::Thing;
+use errors::ParseError;
+use App;
+
+/// A struct representing an option of a poll
+#[derive(Debug, Clone, PartialEq)]
+pub struct PollOption {
+	/// Id of the option
+	pub id: String,
+	/// Text of the option
+	pub text: String,
+	/// Amount of votes for the option. `None` while the poll is open and the user currently
+	/// authorized hasn't voted, since reddit hides the counts until then
+	pub vote_count: Option<i64>,
+}
+
+/// A struct representing the poll of a poll post
+#[derive(Debug, Clone, PartialEq)]
+pub struct PollData {
+	/// The options of the poll, in the order they are displayed
+	pub options: Vec<PollOption>,
+	/// Total amount of votes in the poll
+	pub total_vote_count: i64,
+	/// The time voting ends
+	pub voting_end: DateTime<Utc>,
+	/// Id of the option the user currently authorized voted for, if any
+	pub user_selection: Option<String>,
+}
+
+impl PollData {
+	/// Whether voting has ended
+	pub fn is_closed(&self) -> bool {
+		self.voting_end <= Utc::now()
+	}
+}
+
+impl Thing for PollData {
+	fn from_value(val: &Value, _app: &App) -> Result<PollData, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "PollData".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let options = match val["options"].as_array() {
+			Some(t) => {
+				let options = t.iter().map(|option| {
+					Some(PollOption {
+						id: option["id"].as_str()?.to_string(),
+						text: option["text"].as_str()?.to_string(),
+						vote_count: option["vote_count"].as_i64(),
+					})
+				});
+				match options.collect::<Option<Vec<_>>>() {
+					Some(t) => t,
+					None => out!(val),
+				}
+			}
+			None => out!(val),
+		};
+		let total_vote_count = match val["total_vote_count"].as_i64() {
+			Some(t) => t,
+			None => out!(val),
+		};
+		// In milliseconds, unlike the other timestamps
+		let voting_end = match val["voting_end_timestamp"].as_f64().and_then(|millis| from_epoch(millis / 1000.0)) {
+			Some(t) => t,
+			None => out!(val),
+		};
+		let user_selection = val["user_selection"].as_str().map(|t| t.to_string());
+
+		Ok(PollData { options, total_vote_count, voting_end, user_selection })
+	}
+}
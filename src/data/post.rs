@@ -1,4 +1,7 @@
-use data::{Comment, Listing, Thing};
+use chrono::{DateTime, Utc};
+use data::reports::{parse_mod_reports, parse_user_reports};
+use data::time::{parse_edited, parse_timestamp};
+use data::{Comment, CommentSort, Listing, PollData, Preview, Thing, Thumbnail, UserLookup};
 use errors::ParseError;
 use failure::Error;
 use json::{self, Value};
@@ -11,8 +14,8 @@ pub struct Post {
 	pub id: String,
 	/// Title of the post
 	pub title: String,
-	/// Author of the post
-	pub author: String,
+	/// Author of the post, `None` if the author's account was deleted
+	pub author: Option<String>,
 	/// Subreddit the post was made in
 	pub subreddit: String,
 	/// Number of upvotes the post has recieved
@@ -25,18 +28,126 @@ pub struct Post {
 	pub num_comments: i64,
 	/// Url of the post
 	pub url: String,
+	/// The text of the post. Empty for link posts
+	pub selftext: String,
+	/// The time the post was created. `None` if reddit didn't report it, which happens for some
+	/// deleted posts
+	pub created_utc: Option<DateTime<Utc>>,
 	/// Whether the post is stickied
 	pub stickied: bool,
 	/// Amount of times this post has been gilded
 	pub gilded: i64,
+	/// The time the post was last edited, if it has been
+	pub edited: Option<DateTime<Utc>>,
+	/// The category of the removal of this post, if it was removed. Only present when authorized
+	/// as a moderator
+	pub removed_by_category: Option<String>,
+	/// Name of the moderator that removed this post. Only present when authorized as a moderator
+	pub banned_by: Option<String>,
+	/// Name of the moderator that approved this post. Only present when authorized as a moderator
+	pub approved_by: Option<String>,
+	/// Amount of reports this post has recieved. Always 0 unless authorized as a moderator
+	pub num_reports: i64,
+	/// Reports by moderators, as pairs of the reason and the moderator. `None` unless authorized as
+	/// a moderator
+	pub mod_reports: Option<Vec<(String, String)>>,
+	/// Reports by users, as pairs of the reason and the amount of reports. `None` unless
+	/// authorized as a moderator
+	pub user_reports: Option<Vec<(String, i64)>>,
+	/// Vote of the user currently authorized on this post: `Some(true)` for an upvote,
+	/// `Some(false)` for a downvote and `None` if not voted or not authorized
+	pub likes: Option<bool>,
+	/// Whether the user currently authorized saved this post. Always false unless authorized
+	pub saved: bool,
+	/// Whether the user currently authorized hid this post. Always false unless authorized
+	pub hidden: bool,
+	/// Sort of the comments suggested by the moderators, if any
+	pub suggested_sort: Option<CommentSort>,
+	/// Id of the flair template of the post, which stays the same when the flair text is edited.
+	/// `None` if the post has no flair or a flair without a template
+	pub flair_template_id: Option<String>,
+	/// The preview image reddit generated for the post, if any and if it could be parsed
+	pub preview: Option<Preview>,
+	/// The thumbnail of the post, `None` if it has none
+	pub thumbnail: Option<Thumbnail>,
+	/// The poll of the post, if it is a poll post. `None` as well if the poll couldn't be parsed
+	pub poll_data: Option<PollData>,
+	/// Whether the post is locked, so only moderators can comment
+	pub locked: bool,
+	/// Whether the post is archived, so it can't be commented on or voted on anymore
+	pub archived: bool,
+	/// Fullname of the post this post is a crosspost of, if it is a crosspost
+	pub crosspost_parent: Option<String>,
+	/// The post this post is a crosspost of, if it is a crosspost. The comments of these posts are
+	/// not loaded, and very deeply nested crossposts are cut off
+	pub crosspost_parent_list: Vec<Post>,
 	/// The comments on this post
 	pub comments: Listing<Comment>,
 }
 
+/// The engagement of a post, which can be loaded without its comments
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostStats {
+	/// Number of comments on the post
+	pub num_comments: i64,
+	/// Total score of the post
+	pub score: i64,
+	/// Fraction of the votes on the post that are upvotes
+	pub upvote_ratio: f64,
+	/// The time the post was created, if reddit reported it
+	pub created_utc: Option<DateTime<Utc>>,
+}
+
+impl Thing for PostStats {
+	fn from_value(val: &Value, _app: &App) -> Result<PostStats, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "PostStats".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let data = &val["data"];
+		let num_comments = match data["num_comments"].as_i64() {
+			Some(t) => t,
+			None => out!(val),
+		};
+		let score = match data["score"].as_i64() {
+			Some(t) => t,
+			None => out!(val),
+		};
+		let upvote_ratio = match data["upvote_ratio"].as_f64() {
+			Some(t) => t,
+			None => out!(val),
+		};
+
+		Ok(PostStats {
+			num_comments,
+			score,
+			upvote_ratio,
+			created_utc: parse_timestamp(&data["created_utc"]),
+		})
+	}
+}
+
+/// Amount of crossposted posts parsed into `crosspost_parent_list`, since reddit allows
+/// crossposts of crossposts to be nested arbitrarily deep
+pub(crate) const MAX_CROSSPOST_DEPTH: usize = 8;
+
 impl Thing for Post {
 	fn from_value(val: &Value, app: &App) -> Result<Post, Error> {
-		let post = &val["data"]["children"][0]["data"];
+		let mut post = Post::from_data(&val["data"]["children"][0]["data"], app, MAX_CROSSPOST_DEPTH)?;
+		post.comments = app.get_comment_tree(&post.id)?;
+
+		Ok(post)
+	}
+}
 
+impl Post {
+	/// Parses the data of a post without loading its comments
+	pub(crate) fn from_data(post: &Value, app: &App, depth: usize) -> Result<Post, Error> {
 		macro_rules! out {
 			($val:ident) => {
 				return Err(Error::from(ParseError {
@@ -48,49 +159,93 @@ impl Thing for Post {
 
 		let id = match post["id"].as_str() {
 			Some(t) => t.to_string(),
-			None => out!(val),
+			None => out!(post),
 		};
 		let title = match post["title"].as_str() {
 			Some(t) => t.to_string(),
-			None => out!(val),
+			None => out!(post),
 		};
-		let author = match post["author"].as_str() {
-			Some(t) => t.to_string(),
-			None => out!(val),
+		let author = match post["author"] {
+			Value::String(ref t) if t != "[deleted]" => Some(t.to_string()),
+			Value::String(_) | Value::Null => None,
+			_ => out!(post),
 		};
 		let subreddit = match post["subreddit"].as_str() {
 			Some(t) => t.to_string(),
-			None => out!(val),
+			None => out!(post),
 		};
 		let ups = match post["ups"].as_i64() {
 			Some(t) => t,
-			None => out!(val),
+			None => out!(post),
 		};
 		let downs = match post["downs"].as_i64() {
 			Some(t) => t,
-			None => out!(val),
+			None => out!(post),
 		};
 		let score = match post["score"].as_i64() {
 			Some(t) => t,
-			None => out!(val),
+			None => out!(post),
 		};
 		let num_comments = match post["num_comments"].as_i64() {
 			Some(t) => t,
-			None => out!(val),
+			None => out!(post),
 		};
 		let url = match post["url"].as_str() {
 			Some(t) => t.to_string(),
-			None => out!(val),
+			None => out!(post),
+		};
+		let selftext = match post["selftext"] {
+			Value::String(ref t) => t.to_string(),
+			Value::Null => String::new(),
+			_ => out!(post),
 		};
 		let stickied = match post["stickied"].as_bool() {
 			Some(t) => t,
-			None => out!(val),
+			None => out!(post),
 		};
 		let gilded = match post["gilded"].as_i64() {
 			Some(t) => t,
-			None => out!(val),
+			None => out!(post),
+		};
+		let created_utc = parse_timestamp(&post["created_utc"]);
+		let edited = parse_edited(&post["edited"])?;
+		// Moderation info, only present when authorized as a moderator
+		let removed_by_category = post["removed_by_category"].as_str().map(|t| t.to_string());
+		let banned_by = post["banned_by"].as_str().map(|t| t.to_string());
+		let approved_by = post["approved_by"].as_str().map(|t| t.to_string());
+		let num_reports = post["num_reports"].as_i64().unwrap_or(0);
+		let mod_reports = parse_mod_reports(&post["mod_reports"]);
+		let user_reports = parse_user_reports(&post["user_reports"]);
+		// User state, only present when authorized
+		let likes = post["likes"].as_bool();
+		let saved = post["saved"].as_bool().unwrap_or(false);
+		let hidden = post["hidden"].as_bool().unwrap_or(false);
+		// Empty if no sort is suggested
+		let suggested_sort = post["suggested_sort"].as_str().and_then(CommentSort::from_name);
+		let flair_template_id = post["link_flair_template_id"].as_str().filter(|id| !id.is_empty()).map(|id| id.to_string());
+		// A preview reddit sent in an unexpected shape is left out instead of losing the post
+		let preview = if post["preview"].is_null() { None } else { Preview::from_value(&post["preview"], app).ok() };
+		let thumbnail = Thumbnail::from_value(&post["thumbnail"]);
+		// Like the preview, a poll in an unexpected shape is left out instead of losing the post
+		let poll_data = if post["poll_data"].is_null() { None } else { PollData::from_value(&post["poll_data"], app).ok() };
+		let locked = post["locked"].as_bool().unwrap_or(false);
+		let archived = post["archived"].as_bool().unwrap_or(false);
+		let crosspost_parent = post["crosspost_parent"].as_str().map(|t| t.to_string());
+		let crosspost_parent_list = match post["crosspost_parent_list"].as_array() {
+			// A parent that fails to parse, like a removed original missing fields, is skipped
+			// instead of losing the crosspost
+			Some(parents) if depth > 0 => parents
+				.iter()
+				.filter_map(|parent| match Post::from_data(parent, app, depth - 1) {
+					Ok(parent) => Some(parent),
+					Err(e) => {
+						warn!("Skipping a crosspost parent of {} that failed to parse: {}", id, e);
+						None
+					}
+				})
+				.collect(),
+			_ => Vec::new(),
 		};
-		let comments = app.get_comment_tree(&id)?;
 
 		Ok(Post {
 			id,
@@ -102,9 +257,78 @@ impl Thing for Post {
 			score,
 			num_comments,
 			url,
+			selftext,
+			created_utc,
 			stickied,
 			gilded,
-			comments,
+			edited,
+			removed_by_category,
+			banned_by,
+			approved_by,
+			num_reports,
+			mod_reports,
+			user_reports,
+			likes,
+			saved,
+			hidden,
+			suggested_sort,
+			flair_template_id,
+			preview,
+			thumbnail,
+			poll_data,
+			locked,
+			archived,
+			crosspost_parent,
+			crosspost_parent_list,
+			comments: Listing::new(),
 		})
 	}
+
+	/// Whether the post was removed by a moderator or by reddit
+	pub fn is_removed(&self) -> bool {
+		self.selftext == "[removed]"
+	}
+
+	/// Get the info of the author of the post, without sending a request if the account of the
+	/// author was deleted
+	pub fn author_info(&self, app: &App) -> Result<UserLookup, Error> {
+		UserLookup::lookup(self.author.as_deref(), app)
+	}
+
+	/// Whether the post (or the account of its author) was deleted by its author
+	pub fn is_deleted(&self) -> bool {
+		!self.is_removed() && (self.author.is_none() || self.selftext == "[deleted]")
+	}
+
+	/// Get the post at the root of the chain of crossposts this post is in, following
+	/// `crosspost_parent_list`. The post itself if it isn't a crosspost
+	pub fn original(&self) -> &Post {
+		let mut post = self;
+		while let Some(parent) = post.crosspost_parent_list.first() {
+			post = parent;
+		}
+		post
+	}
+}
+
+/// Orders posts by score, highest first. Posts with the same score are ordered by age, oldest
+/// first, so the order doesn't depend on how reddit happened to order them.
+pub(crate) fn rank_by_score(posts: &mut [Post]) {
+	posts.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.created_utc.cmp(&b.created_utc)));
+}
+
+/// Collects the posts of a listing sorted by new until the first post created before a time.
+/// Stickied posts are skipped instead of ending the walk, since they can be older than the posts
+/// after them
+pub(crate) fn posts_since<I: Iterator<Item = Result<Value, Error>>>(things: I, since: DateTime<Utc>, app: &App) -> Result<Vec<Post>, Error> {
+	let mut posts = Vec::new();
+	for thing in things {
+		let post = Post::from_data(&thing?["data"], app, MAX_CROSSPOST_DEPTH)?;
+		match post.created_utc {
+			Some(created) if created >= since => posts.push(post),
+			Some(_) if !post.stickied => break,
+			_ => {}
+		}
+	}
+	Ok(posts)
 }
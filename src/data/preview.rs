@@ -0,0 +1,104 @@
+use failure::Error;
+use json::{self, Value};
+
+use data::Thing;
+use errors::ParseError;
+use App;
+
+/// A struct representing one size of a preview image of a post
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewImage {
+	/// Url of the image
+	pub url: String,
+	/// Width of the image in pixels
+	pub width: u64,
+	/// Height of the image in pixels
+	pub height: u64,
+}
+
+/// A struct representing the preview image reddit generated for a post
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preview {
+	/// The image in its original size
+	pub source: PreviewImage,
+	/// Resized versions of the image, smallest first
+	pub resolutions: Vec<PreviewImage>,
+}
+
+impl Preview {
+	/// Get the smallest version of the image that is at least as wide as `width`, falling back to
+	/// the source if no resolution is wide enough
+	/// # Arguments
+	/// * `width` - Width to display the image at in pixels
+	pub fn fitting(&self, width: u64) -> &PreviewImage {
+		self.resolutions.iter().filter(|image| image.width >= width).min_by_key(|image| image.width).unwrap_or(&self.source)
+	}
+}
+
+impl Thing for Preview {
+	fn from_value(val: &Value, _app: &App) -> Result<Preview, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "Preview".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let image = &val["images"][0];
+		let source = match preview_image(&image["source"]) {
+			Some(t) => t,
+			None => out!(val),
+		};
+		let resolutions = match image["resolutions"].as_array() {
+			Some(t) => match t.iter().map(preview_image).collect::<Option<Vec<_>>>() {
+				Some(t) => t,
+				None => out!(val),
+			},
+			None => Vec::new(),
+		};
+
+		Ok(Preview { source, resolutions })
+	}
+}
+
+fn preview_image(val: &Value) -> Option<PreviewImage> {
+	Some(PreviewImage {
+		url: val["url"].as_str()?.replace("&amp;", "&"),
+		width: val["width"].as_u64()?,
+		height: val["height"].as_u64()?,
+	})
+}
+
+/// The thumbnail of a post, which is either an image or a placeholder reddit shows instead
+#[derive(Debug, Clone, PartialEq)]
+pub enum Thumbnail {
+	/// Url of the thumbnail image
+	Url(String),
+	/// Placeholder of self posts
+	SelfPost,
+	/// Placeholder of link posts without a thumbnail
+	Default,
+	/// Placeholder of posts marked over 18
+	Nsfw,
+	/// Placeholder of posts marked as spoilers
+	Spoiler,
+	/// Placeholder of image posts without a thumbnail
+	Image,
+}
+
+impl Thumbnail {
+	/// Parses the `thumbnail` field of a post. `None` if it is absent or empty
+	pub(crate) fn from_value(val: &Value) -> Option<Thumbnail> {
+		match val.as_str()? {
+			"" => None,
+			"self" => Some(Thumbnail::SelfPost),
+			"default" => Some(Thumbnail::Default),
+			"nsfw" => Some(Thumbnail::Nsfw),
+			"spoiler" => Some(Thumbnail::Spoiler),
+			"image" => Some(Thumbnail::Image),
+			url => Some(Thumbnail::Url(url.replace("&amp;", "&"))),
+		}
+	}
+}
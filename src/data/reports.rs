@@ -0,0 +1,19 @@
+use json::Value;
+
+/// Parses `mod_reports`, pairs of the reason and the moderator who reported. `None` unless
+/// authorized as a moderator
+pub(crate) fn parse_mod_reports(val: &Value) -> Option<Vec<(String, String)>> {
+	val.as_array().map(|reports| reports.iter().filter_map(|report| Some((report[0].as_str()?.to_string(), report[1].as_str()?.to_string()))).collect())
+}
+
+/// Parses `user_reports`, pairs of the reason and the amount of reports with it. `None` unless
+/// authorized as a moderator
+pub(crate) fn parse_user_reports(val: &Value) -> Option<Vec<(String, i64)>> {
+	val.as_array().map(|reports| reports.iter().filter_map(|report| Some((report[0].as_str().unwrap_or("").to_string(), report[1].as_i64()?))).collect())
+}
+
+/// Sorts the things of a mod listing, like the json children of `App::get_reports`, by their
+/// amount of reports, most reported first
+pub fn sort_by_reports(things: &mut [Value]) {
+	things.sort_by_key(|thing| std::cmp::Reverse(thing["data"]["num_reports"].as_i64().unwrap_or(0)));
+}
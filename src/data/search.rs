@@ -0,0 +1,204 @@
+use std::collections::VecDeque;
+
+use chrono::{self, DateTime, Utc};
+use failure::Error;
+use json::{self, Map, Value};
+
+use data::time::from_epoch;
+use data::{Post, MAX_CROSSPOST_DEPTH};
+use App;
+
+/// Maximum amount of results reddit returns for a single search
+pub const SEARCH_RESULT_CEILING: usize = 1000;
+
+/// The results of a search for the posts submitted in a window of time
+#[derive(Debug, Clone)]
+pub struct SearchWindow {
+	/// The json of the posts found, newest first
+	pub posts: Vec<Value>,
+	/// Start of the window the results cover. If the search was not complete this is the time of
+	/// the oldest post found, and the posts between the requested start and this time still have
+	/// to be searched
+	pub start: DateTime<Utc>,
+	/// End of the window the results cover
+	pub end: DateTime<Utc>,
+	/// Whether all posts in the requested window were found. Searches stop after
+	/// `SEARCH_RESULT_CEILING` results
+	pub complete: bool,
+}
+
+impl SearchWindow {
+	/// Creates the window covered by the results of a search of the window from `start` to `end`
+	pub(crate) fn new(posts: Vec<Value>, start: DateTime<Utc>, end: DateTime<Utc>) -> SearchWindow {
+		let complete = posts.len() < SEARCH_RESULT_CEILING;
+		let start = if complete { start } else { posts.iter().filter_map(|post| post["data"]["created_utc"].as_f64()).filter_map(from_epoch).min().unwrap_or(start) };
+
+		SearchWindow { posts, start, end, complete }
+	}
+}
+
+/// The position of a walk of the history of a subreddit, which can be saved to resume the walk
+/// later, e.g. after a crawl was interrupted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryCursor {
+	/// Time of the last post yielded, or the start of the walk if none were. The rest of the walk
+	/// starts here
+	pub start: DateTime<Utc>,
+	/// End of the walk
+	pub end: DateTime<Utc>,
+	/// Length of the next window searched, in seconds
+	window: i64,
+	/// Fullnames of the posts submitted at `start` that were already yielded, since the next
+	/// window includes them again
+	seen: Vec<String>,
+}
+
+impl HistoryCursor {
+	/// Creates the cursor of a walk from `start` to `end` that hasn't started yet
+	pub(crate) fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> HistoryCursor {
+		HistoryCursor {
+			start,
+			end,
+			window: (end - start).num_seconds().max(1),
+			seen: Vec::new(),
+		}
+	}
+
+	/// Serializes the cursor to a json document
+	pub fn to_json(&self) -> String {
+		let mut doc = Map::new();
+		doc.insert("start".to_string(), Value::from(self.start.to_rfc3339()));
+		doc.insert("end".to_string(), Value::from(self.end.to_rfc3339()));
+		doc.insert("window".to_string(), Value::from(self.window));
+		doc.insert("seen".to_string(), Value::from(self.seen.clone()));
+
+		json::to_string(&Value::Object(doc)).unwrap()
+	}
+
+	/// Loads a cursor from a json document written by `to_json`
+	pub fn from_json(cursor: &str) -> Result<HistoryCursor, Error> {
+		let doc: Value = json::from_str(cursor)?;
+		let time = |key: &str| doc[key].as_str().and_then(|t| DateTime::parse_from_rfc3339(t).ok()).map(|t| t.with_timezone(&Utc));
+
+		match (time("start"), time("end"), doc["window"].as_i64(), doc["seen"].as_array()) {
+			(Some(start), Some(end), Some(window), Some(seen)) => Ok(HistoryCursor {
+				start,
+				end,
+				window: window.max(1),
+				seen: seen.iter().filter_map(|name| name.as_str()).map(|name| name.to_string()).collect(),
+			}),
+			_ => Err(format_err!("Invalid history cursor {}", cursor)),
+		}
+	}
+
+	/// Moves the cursor past a post that is yielded
+	fn advance(&mut self, post: &Post) {
+		let created = post.created_utc.unwrap_or(self.start);
+		if created.timestamp() != self.start.timestamp() {
+			self.start = created;
+			self.seen.clear();
+		}
+		self.seen.push(format!("t3_{}", post.id));
+	}
+}
+
+/// An iterator over all posts submitted to a subreddit in a window of time, oldest first, returned
+/// by `App::walk_subreddit_history`. Listings of subreddits end after about 1000 posts, so the
+/// posts are searched for in windows of time instead. A window with more results than reddit
+/// returns for a search is split in half until its results are complete, and later windows grow
+/// again while they are complete. Iteration stops after the last post, or after the first error,
+/// and can be resumed from its `cursor`.
+pub struct SubredditHistory<'a> {
+	app: &'a App,
+	sub: String,
+	cursor: HistoryCursor,
+	/// Start of the next window searched
+	frontier: DateTime<Utc>,
+	cache: VecDeque<Post>,
+	done: bool,
+}
+
+impl<'a> SubredditHistory<'a> {
+	pub(crate) fn new(app: &'a App, sub: &str, cursor: HistoryCursor) -> SubredditHistory<'a> {
+		SubredditHistory {
+			app,
+			sub: sub.to_string(),
+			frontier: cursor.start,
+			cursor,
+			cache: VecDeque::new(),
+			done: false,
+		}
+	}
+
+	/// Continues a walk from a saved cursor instead of from its start
+	pub fn resume(self, cursor: HistoryCursor) -> SubredditHistory<'a> {
+		SubredditHistory::new(self.app, &self.sub, cursor)
+	}
+
+	/// Get the position of the walk after the posts yielded so far
+	pub fn cursor(&self) -> &HistoryCursor {
+		&self.cursor
+	}
+
+	/// Yields the next post, searching windows with `search` when the posts found so far were
+	/// all yielded
+	pub(crate) fn next_with<F: FnMut(DateTime<Utc>, DateTime<Utc>) -> Result<SearchWindow, Error>>(&mut self, mut search: F) -> Option<Result<Post, Error>> {
+		while self.cache.is_empty() {
+			if self.done {
+				return None;
+			}
+			if let Err(e) = self.search_window(&mut search) {
+				self.done = true;
+				return Some(Err(e));
+			}
+		}
+
+		let post = self.cache.pop_front()?;
+		self.cursor.advance(&post);
+		Some(Ok(post))
+	}
+
+	fn search_window<F: FnMut(DateTime<Utc>, DateTime<Utc>) -> Result<SearchWindow, Error>>(&mut self, search: &mut F) -> Result<(), Error> {
+		let (end, found) = loop {
+			let end = (self.frontier + chrono::Duration::seconds(self.cursor.window)).min(self.cursor.end);
+			let found = search(self.frontier, end)?;
+			if found.complete || self.cursor.window <= 1 {
+				if !found.complete {
+					warn!("More posts than a search returns were submitted to {} in a second, some are missing", self.sub);
+				}
+				break (end, found);
+			}
+			self.cursor.window = (self.cursor.window / 2).max(1);
+		};
+
+		let mut posts = Vec::new();
+		for post in &found.posts {
+			let post = Post::from_data(&post["data"], self.app, MAX_CROSSPOST_DEPTH)?;
+			// Windows overlap by their boundary, which can repeat posts that were yielded
+			if !self.cursor.seen.contains(&format!("t3_{}", post.id)) {
+				posts.push(post);
+			}
+		}
+		posts.sort_by(|a, b| a.created_utc.cmp(&b.created_utc).then_with(|| a.id.cmp(&b.id)));
+		self.cache.extend(posts);
+
+		if found.complete {
+			self.cursor.window = self.cursor.window.saturating_mul(2);
+		}
+		if end >= self.cursor.end {
+			self.done = true;
+		}
+		self.frontier = end;
+		Ok(())
+	}
+}
+
+impl<'a> Iterator for SubredditHistory<'a> {
+	type Item = Result<Post, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let app = self.app;
+		let sub = self.sub.clone();
+		self.next_with(|start, end| app.search_by_time(sub.as_str(), start, end))
+	}
+}
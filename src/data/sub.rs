@@ -1,15 +1,66 @@
-use std::collections::VecDeque;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
+use failure::Error;
+use json::Value;
+
+use app::pacing::Backoff;
 use data::Comment;
-use App;
+use {App, RedditError};
+
+/// How often a sleeping stream checks whether it was stopped
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A handle that stops a stream from any thread. Once stopped, the stream yields the items it
+/// already retrieved and then ends, instead of polling again.
+#[derive(Debug, Clone)]
+pub struct StreamHandle {
+	stopped: Arc<AtomicBool>,
+}
+
+impl StreamHandle {
+	pub(crate) fn new() -> StreamHandle {
+		StreamHandle { stopped: Arc::new(AtomicBool::new(false)) }
+	}
+
+	/// Stops the stream
+	pub fn stop(&self) {
+		self.stopped.store(true, Ordering::SeqCst);
+	}
+
+	/// Whether the stream was stopped
+	pub fn is_stopped(&self) -> bool {
+		self.stopped.load(Ordering::SeqCst)
+	}
+
+	/// Sleeps for `duration`, waking up early if the stream is stopped meanwhile
+	/// # Returns
+	/// Whether the stream was stopped
+	pub(crate) fn sleep(&self, duration: Duration) -> bool {
+		let start = Instant::now();
+		while !self.is_stopped() {
+			match duration.checked_sub(start.elapsed()) {
+				Some(left) if left > Duration::from_secs(0) => thread::sleep(left.min(STOP_CHECK_INTERVAL)),
+				_ => return false,
+			}
+		}
+		true
+	}
+}
 
 /// A struct that represents a stream of comments from a subreddit as they are posted. To use it
 /// simply create a `for` loop with this is the source. It will automatically retrieve comments
 /// as needed. The subreddit can be `all` to create a stream of comments from all of reddit.
 pub struct Comments<'a> {
-	sub: String,
+	target: Target,
 	cache: VecDeque<Comment>,
 	last: Option<String>,
+	handle: StreamHandle,
 	app: &'a App,
 }
 
@@ -17,24 +68,55 @@ impl<'a> Comments<'a> {
 	/// Creates a stream of comments from a subreddit
 	/// # Arguments
 	/// * `app` - A reference to a Reddit `App` instance
-	/// * `sub` - The subreddit to load comments from. Can be "all" to stream comments from all
+	/// * `target` - The subreddit to load comments from. Can be "all" to stream comments from all
 	/// of reddit.
-	pub fn new(app: &'a App, sub: &str) -> Comments<'a> {
+	pub fn new<T: Into<Target>>(app: &'a App, target: T) -> Comments<'a> {
 		let cache: VecDeque<Comment> = VecDeque::new();
 		let last = None;
 
-		Comments { sub: sub.to_string(), cache, last, app }
+		Comments {
+			target: target.into(),
+			cache,
+			last,
+			handle: StreamHandle::new(),
+			app,
+		}
+	}
+
+	/// Get a handle that stops the stream, which can be sent to other threads
+	pub fn handle(&self) -> StreamHandle {
+		self.handle.clone()
+	}
+
+	/// Turns the stream into one that also yields comments again when they are edited, with the
+	/// version from before the edit. The stream keeps the same handle.
+	pub fn include_edits(self) -> CommentEdits<'a> {
+		CommentEdits::new(self.app, self.target, self.handle)
+	}
+
+	/// Splits the stream into receivers that each yield every comment of the stream, so several
+	/// consumers can share one polling loop. Comments are polled when a receiver runs out of
+	/// buffered comments. The stream is stopped once all receivers are dropped.
+	/// # Arguments
+	/// * `n` - Amount of receivers to create
+	pub fn split(self, n: usize) -> Vec<CommentReceiver<'a>> {
+		let handle = self.handle.clone();
+		split_comments(self, handle, n)
 	}
 
 	fn refresh(&mut self, app: &App) {
-		let mut fails = 0;
+		let mut backoff = Backoff::new();
 		let resp = loop {
-			match app.get_recent_comments(&self.sub, Some(500), self.last.as_ref().map(|s| s.as_str())) {
+			match app.get_recent_comments(self.target.clone(), Some(500), self.last.as_ref().map(|s| s.as_str())) {
 				Ok(x) => break x,
 				Err(e) => {
+					if self.handle.is_stopped() {
+						return;
+					}
 					log::warn!("Error from get_recent_comments, retrying: {}\n", e);
-					std::thread::sleep(std::time::Duration::from_millis(fails * 1000 + rand::random::<u64>() % 1000));
-					fails = (fails + 1).min(10);
+					if self.handle.sleep(backoff.next_delay()) {
+						return;
+					}
 					continue;
 				}
 			}
@@ -54,15 +136,551 @@ impl<'a> Iterator for Comments<'a> {
 	type Item = Comment;
 
 	fn next(&mut self) -> Option<Self::Item> {
+		// Buffered items are still yielded after the stream is stopped
+		while self.cache.is_empty() {
+			if self.handle.is_stopped() {
+				return None;
+			}
+			self.refresh(self.app);
+		}
+		self.cache.pop_front()
+	}
+}
+
+/// Default amount of comments a `CommentReceiver` buffers before dropping the oldest ones
+const RECEIVER_CAPACITY: usize = 1000;
+
+struct ReceiverBuffer {
+	comments: VecDeque<Comment>,
+	capacity: usize,
+	dropped: u64,
+}
+
+/// The stream shared by the receivers of `Comments::split`, with the buffers of the receivers
+/// that weren't dropped yet
+struct CommentBus<'a> {
+	source: Box<dyn Iterator<Item = Comment> + 'a>,
+	handle: StreamHandle,
+	buffers: Vec<Option<ReceiverBuffer>>,
+}
+
+impl<'a> CommentBus<'a> {
+	/// Takes the next comment of the stream and passes it to every receiver, returning whether
+	/// the stream had a comment
+	fn pump(&mut self) -> bool {
+		let comment = match self.source.next() {
+			Some(comment) => comment,
+			None => return false,
+		};
+		for buffer in self.buffers.iter_mut().filter_map(Option::as_mut) {
+			if buffer.comments.len() >= buffer.capacity {
+				buffer.comments.pop_front();
+				buffer.dropped += 1;
+			}
+			buffer.comments.push_back(comment.clone());
+		}
+		true
+	}
+}
+
+/// One of the receivers of a stream split with `Comments::split`. It yields every comment of the
+/// stream, independently of the other receivers. A receiver that falls behind the others by more
+/// than its capacity drops its oldest comments without affecting the other receivers.
+pub struct CommentReceiver<'a> {
+	bus: Rc<RefCell<CommentBus<'a>>>,
+	index: usize,
+}
+
+impl<'a> CommentReceiver<'a> {
+	/// Sets the amount of comments the receiver buffers while other receivers poll. Defaults to
+	/// 1000.
+	pub fn set_capacity(&mut self, capacity: usize) {
+		if let Some(ref mut buffer) = self.bus.borrow_mut().buffers[self.index] {
+			buffer.capacity = capacity.max(1);
+		}
+	}
+
+	/// Amount of comments the receiver buffered that it didn't yield yet
+	pub fn lag(&self) -> usize {
+		self.bus.borrow().buffers[self.index].as_ref().map_or(0, |buffer| buffer.comments.len())
+	}
+
+	/// Amount of comments the receiver dropped because it fell behind
+	pub fn dropped(&self) -> u64 {
+		self.bus.borrow().buffers[self.index].as_ref().map_or(0, |buffer| buffer.dropped)
+	}
+
+	/// Get a handle that stops the stream of all receivers, which can be sent to other threads
+	pub fn handle(&self) -> StreamHandle {
+		self.bus.borrow().handle.clone()
+	}
+}
+
+impl<'a> Iterator for CommentReceiver<'a> {
+	type Item = Comment;
+
+	fn next(&mut self) -> Option<Comment> {
+		let mut bus = self.bus.borrow_mut();
+		loop {
+			if let Some(comment) = bus.buffers[self.index].as_mut().and_then(|buffer| buffer.comments.pop_front()) {
+				return Some(comment);
+			}
+			if !bus.pump() {
+				return None;
+			}
+		}
+	}
+}
+
+impl<'a> Drop for CommentReceiver<'a> {
+	fn drop(&mut self) {
+		let mut bus = self.bus.borrow_mut();
+		bus.buffers[self.index] = None;
+		if bus.buffers.iter().all(Option::is_none) {
+			bus.handle.stop();
+		}
+	}
+}
+
+/// Creates receivers that share a stream of comments, which is stopped with `handle` once all
+/// receivers are dropped
+pub(crate) fn split_comments<'a, I: Iterator<Item = Comment> + 'a>(source: I, handle: StreamHandle, n: usize) -> Vec<CommentReceiver<'a>> {
+	let buffers = (0..n)
+		.map(|_| {
+			Some(ReceiverBuffer {
+				comments: VecDeque::new(),
+				capacity: RECEIVER_CAPACITY,
+				dropped: 0,
+			})
+		})
+		.collect();
+	let bus = Rc::new(RefCell::new(CommentBus { source: Box::new(source), handle, buffers }));
+
+	(0..n).map(|index| CommentReceiver { bus: bus.clone(), index }).collect()
+}
+
+/// Amount of fullnames a polling stream remembers to skip items it already yielded
+const SEEN_CAPACITY: usize = 5000;
+
+/// The fullnames of the most recent items a polling stream yielded
+pub(crate) struct SeenNames {
+	names: HashSet<String>,
+	order: VecDeque<String>,
+}
+
+impl SeenNames {
+	pub(crate) fn new() -> SeenNames {
+		SeenNames { names: HashSet::new(), order: VecDeque::new() }
+	}
+
+	/// Remembers a fullname, returning whether it was new. Forgets the oldest fullnames past
+	/// `SEEN_CAPACITY`
+	pub(crate) fn insert(&mut self, name: String) -> bool {
+		if !self.names.insert(name.clone()) {
+			return false;
+		}
+		self.order.push_back(name);
+		while self.order.len() > SEEN_CAPACITY {
+			if let Some(old) = self.order.pop_front() {
+				self.names.remove(&old);
+			}
+		}
+		true
+	}
+}
+
+/// A struct that represents a stream of posts and comments as they arrive in the modqueue of a
+/// subreddit. Like `Comments` it can be used as the source of a `for` loop and retrieves new items
+/// as needed, yielding every item once even if it stays in the queue. Items are the json of the
+/// thing, including its `kind`.
+pub struct ModqueueStream<'a> {
+	sub: String,
+	cache: VecDeque<Value>,
+	seen: SeenNames,
+	interval: Duration,
+	polled: bool,
+	handle: StreamHandle,
+	app: &'a App,
+}
+
+impl<'a> ModqueueStream<'a> {
+	/// Creates a stream of the modqueue of a subreddit
+	/// # Arguments
+	/// * `app` - A reference to a Reddit `App` instance authorized as a moderator of the subreddit
+	/// * `sub` - The subreddit of the modqueue. Can be "mod" to stream the modqueues of all
+	/// subreddits the user moderates.
+	pub fn new(app: &'a App, sub: &str) -> ModqueueStream<'a> {
+		ModqueueStream {
+			sub: sub.to_string(),
+			cache: VecDeque::new(),
+			seen: SeenNames::new(),
+			interval: Duration::from_secs(10),
+			polled: false,
+			handle: StreamHandle::new(),
+			app,
+		}
+	}
+
+	/// Sets the time waited between polls of the modqueue that found nothing new. Defaults to 10
+	/// seconds. Requests are ratelimited by the connection in addition to this.
+	pub fn set_interval(&mut self, interval: Duration) {
+		self.interval = interval;
+	}
+
+	/// Get a handle that stops the stream, which can be sent to other threads
+	pub fn handle(&self) -> StreamHandle {
+		self.handle.clone()
+	}
+
+	fn refresh(&mut self, app: &App) {
+		if self.polled && self.handle.sleep(self.interval) {
+			return;
+		}
+		self.polled = true;
+
+		let mut backoff = Backoff::new();
+		let resp = loop {
+			match app.get_modqueue(&self.sub, Some(100)) {
+				Ok(x) => break x,
+				Err(e) => {
+					if self.handle.is_stopped() {
+						return;
+					}
+					log::warn!("Error from get_modqueue, retrying: {}\n", e);
+					if self.handle.sleep(backoff.next_delay()) {
+						return;
+					}
+					continue;
+				}
+			}
+		};
+
+		if let Some(children) = resp["data"]["children"].as_array() {
+			// The queue is newest first, so yield the items in reverse order
+			for child in children.iter().rev() {
+				let name = match child["data"]["name"].as_str() {
+					Some(name) => name.to_string(),
+					None => continue,
+				};
+				if self.seen.insert(name) {
+					self.cache.push_back(child.clone());
+				}
+			}
+		}
+	}
+}
+
+impl<'a> Iterator for ModqueueStream<'a> {
+	type Item = Value;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		// Buffered items are still yielded after the stream is stopped
 		while self.cache.is_empty() {
+			if self.handle.is_stopped() {
+				return None;
+			}
 			self.refresh(self.app);
 		}
 		self.cache.pop_front()
 	}
 }
 
+/// A new or edited comment from a `CommentEdits` stream
+#[derive(Debug, Clone)]
+pub enum CommentEvent {
+	/// A comment that was posted
+	New(Comment),
+	/// A comment that was edited since it was last seen
+	Edited {
+		/// The comment as it was last seen
+		before: Box<Comment>,
+		/// The comment after the edit
+		after: Comment,
+	},
+}
+
+/// A stream of comments from a subreddit that yields comments when they are posted and again
+/// when their `edited` time changes, created with `Comments::include_edits`. Edits are found by
+/// polling the newest comments, so a comment is only checked for edits while it is among the 100
+/// newest comments of the subreddit, and at most `SEEN_CAPACITY` comments are remembered.
+pub struct CommentEdits<'a> {
+	target: Target,
+	cache: VecDeque<CommentEvent>,
+	seen: HashMap<String, Comment>,
+	seen_order: VecDeque<String>,
+	interval: Duration,
+	polled: bool,
+	handle: StreamHandle,
+	app: &'a App,
+}
+
+impl<'a> CommentEdits<'a> {
+	fn new(app: &'a App, target: Target, handle: StreamHandle) -> CommentEdits<'a> {
+		CommentEdits {
+			target,
+			cache: VecDeque::new(),
+			seen: HashMap::new(),
+			seen_order: VecDeque::new(),
+			interval: Duration::from_secs(10),
+			polled: false,
+			handle,
+			app,
+		}
+	}
+
+	/// Sets the time waited between polls of the newest comments. Defaults to 10 seconds.
+	/// Requests are ratelimited by the connection in addition to this.
+	pub fn set_interval(&mut self, interval: Duration) {
+		self.interval = interval;
+	}
+
+	/// Get a handle that stops the stream, which can be sent to other threads
+	pub fn handle(&self) -> StreamHandle {
+		self.handle.clone()
+	}
+
+	/// Compares polled comments, oldest first, with the versions last seen
+	pub(crate) fn record(&mut self, comments: Vec<Comment>) {
+		for comment in comments {
+			match self.seen.get(&comment.name).cloned() {
+				None => {
+					self.seen_order.push_back(comment.name.clone());
+					self.cache.push_back(CommentEvent::New(comment.clone()));
+				}
+				Some(before) => {
+					if before.edited != comment.edited {
+						self.cache.push_back(CommentEvent::Edited { before: Box::new(before), after: comment.clone() });
+					}
+				}
+			}
+			self.seen.insert(comment.name.clone(), comment);
+		}
+
+		while self.seen_order.len() > SEEN_CAPACITY {
+			if let Some(old) = self.seen_order.pop_front() {
+				self.seen.remove(&old);
+			}
+		}
+	}
+
+	fn refresh(&mut self, app: &App) {
+		if self.polled && self.handle.sleep(self.interval) {
+			return;
+		}
+		self.polled = true;
+
+		let mut backoff = Backoff::new();
+		let resp = loop {
+			match app.get_recent_comments(self.target.clone(), Some(100), None) {
+				Ok(x) => break x,
+				Err(e) => {
+					if self.handle.is_stopped() {
+						return;
+					}
+					log::warn!("Error from get_recent_comments, retrying: {}\n", e);
+					if self.handle.sleep(backoff.next_delay()) {
+						return;
+					}
+					continue;
+				}
+			}
+		};
+
+		// The result is in reverse-chronological order
+		self.record(resp.children.into_iter().rev().collect());
+	}
+}
+
+impl<'a> Iterator for CommentEdits<'a> {
+	type Item = CommentEvent;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		// Buffered items are still yielded after the stream is stopped
+		while self.cache.is_empty() {
+			if self.handle.is_stopped() {
+				return None;
+			}
+			self.refresh(self.app);
+		}
+		self.cache.pop_front()
+	}
+}
+
+/// A struct that represents a stream of the comments of a single post as they are posted, for
+/// instance to follow an AMA or a live thread. Like `Comments` it can be used as the source of a
+/// `for` loop and retrieves new comments as needed. Replies anywhere in the tree are yielded once,
+/// oldest first.
+pub struct PostComments<'a> {
+	post: String,
+	cache: VecDeque<Comment>,
+	seen: SeenNames,
+	interval: Duration,
+	polled: bool,
+	handle: StreamHandle,
+	app: &'a App,
+}
+
+impl<'a> PostComments<'a> {
+	/// Creates a stream of the comments of a post
+	/// # Arguments
+	/// * `app` - A reference to a Reddit `App` instance
+	/// * `post` - The id of the post
+	pub fn new(app: &'a App, post: &str) -> PostComments<'a> {
+		PostComments {
+			post: post.to_string(),
+			cache: VecDeque::new(),
+			seen: SeenNames::new(),
+			interval: Duration::from_secs(10),
+			polled: false,
+			handle: StreamHandle::new(),
+			app,
+		}
+	}
+
+	/// Sets the time waited between polls of the post that found no new comments. Defaults to 10
+	/// seconds. Requests are ratelimited by the connection in addition to this.
+	pub fn set_interval(&mut self, interval: Duration) {
+		self.interval = interval;
+	}
+
+	/// Get a handle that stops the stream, which can be sent to other threads
+	pub fn handle(&self) -> StreamHandle {
+		self.handle.clone()
+	}
+
+	fn refresh(&mut self, app: &App) {
+		if self.polled && self.handle.sleep(self.interval) {
+			return;
+		}
+		self.polled = true;
+
+		let mut backoff = Backoff::new();
+		let resp = loop {
+			match app.get_newest_comments(&self.post, Some(100)) {
+				Ok(x) => break x,
+				Err(e) => {
+					if self.handle.is_stopped() {
+						return;
+					}
+					log::warn!("Error from get_newest_comments, retrying: {}\n", e);
+					if self.handle.sleep(backoff.next_delay()) {
+						return;
+					}
+					continue;
+				}
+			}
+		};
+
+		let mut comments: Vec<Comment> = resp.traverse().into_iter().filter(|comment| self.seen.insert(comment.name.clone())).collect();
+		comments.sort_by_key(|comment| comment.created_utc);
+		self.cache.extend(comments);
+	}
+}
+
+impl<'a> Iterator for PostComments<'a> {
+	type Item = Comment;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		// Buffered items are still yielded after the stream is stopped
+		while self.cache.is_empty() {
+			if self.handle.is_stopped() {
+				return None;
+			}
+			self.refresh(self.app);
+		}
+		self.cache.pop_front()
+	}
+}
+
+/// The regions reddit accepts as the `geo_filter` of `r/popular` and `r/all`: `GLOBAL`, ISO codes
+/// of countries and `US_` followed by the code of a state of the United States
+pub const GEO_FILTERS: &[&str] = &[
+	"GLOBAL", "AR", "AU", "BG", "CA", "CL", "CO", "CZ", "DE", "ES", "FI", "FR", "GB", "GR", "HR", "HU", "IE", "IN", "IS", "IT", "JP", "MX", "MY", "NL", "NZ", "PH", "PL", "PR", "PT", "RO", "RS", "SE", "SG", "TH", "TR", "TW", "US", "US_AK", "US_AL", "US_AR", "US_AZ", "US_CA", "US_CO", "US_CT",
+	"US_DC", "US_DE", "US_FL", "US_GA", "US_HI", "US_IA", "US_ID", "US_IL", "US_IN", "US_KS", "US_KY", "US_LA", "US_MA", "US_MD", "US_ME", "US_MI", "US_MN", "US_MO", "US_MS", "US_MT", "US_NC", "US_ND", "US_NE", "US_NH", "US_NJ", "US_NM", "US_NV", "US_NY", "US_OH", "US_OK", "US_OR", "US_PA",
+	"US_RI", "US_SC", "US_SD", "US_TN", "US_TX", "US_UT", "US_VA", "US_VT", "US_WA", "US_WI", "US_WV", "US_WY",
+];
+
+/// A listing that posts and comments can be retrieved from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+	/// A subreddit
+	Subreddit(String),
+	/// All of reddit, except quarantined subreddits and the excluded subreddits
+	All {
+		/// Names of subreddits to leave out
+		exclude: Vec<String>,
+	},
+	/// The popular subreddits
+	Popular,
+	/// The front page, which is the subscriptions of the user currently authorized or the default
+	/// subreddits otherwise
+	Frontpage,
+}
+
+impl Target {
+	/// Fails with `RedditError::BadRequest` for regions reddit doesn't know, and for targets other
+	/// than `r/popular` and `r/all`, for which reddit ignores the region
+	pub(crate) fn check_geo_filter(&self, geo_filter: &str) -> Result<(), Error> {
+		let request = match *self {
+			Target::Popular | Target::All { .. } if GEO_FILTERS.contains(&geo_filter) => return Ok(()),
+			Target::Popular | Target::All { .. } => format!("Unknown geo filter {}", geo_filter),
+			_ => "A geo filter is only available for r/popular and r/all".to_string(),
+		};
+
+		Err(Error::from(RedditError::BadRequest {
+			request,
+			response: "not sent".to_string(),
+			status: None,
+		}))
+	}
+
+	/// The path of the listing, e.g. `/r/all-pics-funny`. Empty for the front page
+	pub fn path(&self) -> String {
+		match *self {
+			Target::Subreddit(ref sub) => format!("/r/{}", sub),
+			Target::All { ref exclude } if exclude.is_empty() => "/r/all".to_string(),
+			Target::All { ref exclude } => format!("/r/all-{}", exclude.join("-")),
+			Target::Popular => "/r/popular".to_string(),
+			Target::Frontpage => String::new(),
+		}
+	}
+}
+
+impl<'a> From<&'a str> for Target {
+	/// Parses the name of a subreddit, understanding `all` (with `all-sub-sub` exclusions),
+	/// `popular` and an empty name for the front page
+	fn from(name: &'a str) -> Target {
+		let name = name.trim_start_matches('/').trim_start_matches("r/");
+		if name.eq_ignore_ascii_case("all") {
+			Target::All { exclude: Vec::new() }
+		} else if name.len() > 4 && name.get(..4).is_some_and(|prefix| prefix.eq_ignore_ascii_case("all-")) {
+			// Slicing by bytes only works at the end of a character, so names that aren't ascii
+			// are sliced with `get`
+			Target::All {
+				exclude: name.get(4..).unwrap_or_default().split('-').filter(|sub| !sub.is_empty()).map(|sub| sub.to_string()).collect(),
+			}
+		} else if name.eq_ignore_ascii_case("popular") {
+			Target::Popular
+		} else if name.is_empty() {
+			Target::Frontpage
+		} else {
+			Target::Subreddit(name.to_string())
+		}
+	}
+}
+
+impl From<String> for Target {
+	fn from(name: String) -> Target {
+		Target::from(name.as_str())
+	}
+}
+
 /// Sort type of a subreddit
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Sort {
+	/// Personalized sort of the front page of the user currently authorized. Only available for
+	/// the front page, not subreddits
+	Best,
 	/// Hot
 	Hot,
 	/// New
@@ -80,6 +698,7 @@ impl Sort {
 	pub fn param<'a>(self) -> Vec<(&'a str, &'a str)> {
 		use self::Sort::*;
 		match self {
+			Best => vec![("sort", "best")],
 			Hot => vec![("sort", "hot")],
 			New => vec![("sort", "new")],
 			Rising => vec![("sort", "rising")],
@@ -87,9 +706,23 @@ impl Sort {
 			Controversial(sort) => vec![("sort", "controversial"), sort.param()],
 		}
 	}
+
+	/// The path segment of a listing sorted this way
+	pub fn path(&self) -> &'static str {
+		use self::Sort::*;
+		match *self {
+			Best => "best",
+			Hot => "hot",
+			New => "new",
+			Rising => "rising",
+			Top(_) => "top",
+			Controversial(_) => "controversial",
+		}
+	}
 }
 
 /// Time parameter of a subreddit sort
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SortTime {
 	/// Hour
 	Hour,
@@ -122,3 +755,60 @@ impl SortTime {
 		)
 	}
 }
+
+/// Sort type of the comments of a post
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommentSort {
+	/// Best, called `confidence` by the api
+	Confidence,
+	/// Top
+	Top,
+	/// New
+	New,
+	/// Controversial
+	Controversial,
+	/// Old
+	Old,
+	/// Random
+	Random,
+	/// Q&A
+	Qa,
+	/// Live
+	Live,
+}
+
+impl CommentSort {
+	/// Convert the comment sort to a tuple to be used in url parameters
+	pub fn param<'a>(self) -> (&'a str, &'a str) {
+		use self::CommentSort::*;
+		(
+			"sort",
+			match self {
+				Confidence => "confidence",
+				Top => "top",
+				New => "new",
+				Controversial => "controversial",
+				Old => "old",
+				Random => "random",
+				Qa => "qa",
+				Live => "live",
+			},
+		)
+	}
+
+	/// Parses a comment sort from its name in the api, returning `None` for empty or unknown names
+	pub fn from_name(name: &str) -> Option<CommentSort> {
+		use self::CommentSort::*;
+		match name {
+			"confidence" => Some(Confidence),
+			"top" => Some(Top),
+			"new" => Some(New),
+			"controversial" => Some(Controversial),
+			"old" => Some(Old),
+			"random" => Some(Random),
+			"qa" => Some(Qa),
+			"live" => Some(Live),
+			_ => None,
+		}
+	}
+}
@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use failure::Error;
+use json::{self, Value};
+
+use data::time::parse_timestamp;
+use data::{Post, Thing, MAX_CROSSPOST_DEPTH};
+use errors::ParseError;
+use App;
+
+/// Kind of a post to submit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubmitKind {
+	/// A text post
+	SelfPost,
+	/// A link post
+	Link,
+}
+
+/// The post created by a submission
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubmitResult {
+	/// Fullname of the new post
+	pub fullname: String,
+	/// Url of the new post
+	pub url: String,
+	/// Whether replies to the post are sent to the inbox of the submitter, as reddit applied it.
+	/// Subreddits can override the requested setting. `None` if reddit didn't include it
+	pub send_replies: Option<bool>,
+}
+
+impl Thing for SubmitResult {
+	fn from_value(val: &Value, _app: &App) -> Result<SubmitResult, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "SubmitResult".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let data = &val["json"]["data"];
+		let fullname = match data["name"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let url = match data["url"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+
+		Ok(SubmitResult {
+			fullname,
+			url,
+			send_replies: data["send_replies"].as_bool().or_else(|| data["sendreplies"].as_bool()),
+		})
+	}
+}
+
+/// Settings of the check for duplicate submissions done before submitting, see
+/// `App::set_duplicate_guard`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DuplicateGuard {
+	/// How long ago an earlier submission with the same title can have been made to count as a
+	/// duplicate
+	pub lookback: Duration,
+	/// Amount of the newest submissions of the account that are checked, at most 100
+	pub limit: usize,
+}
+
+impl Default for DuplicateGuard {
+	fn default() -> DuplicateGuard {
+		DuplicateGuard { lookback: Duration::from_secs(60 * 60 * 24), limit: 25 }
+	}
+}
+
+/// Finds a submission with the same title to the same subreddit made since a point in time, if
+/// given, among the listing of the submissions of an account
+pub(crate) fn find_duplicate(submitted: &Value, sub: &str, title: &str, since: Option<DateTime<Utc>>, app: &App) -> Result<Option<Post>, Error> {
+	let sub = sub.trim_start_matches('/').trim_start_matches("r/");
+	let children = match submitted["data"]["children"].as_array() {
+		Some(children) => children,
+		None => return Ok(None),
+	};
+
+	for child in children {
+		let data = &child["data"];
+		let same_sub = data["subreddit"].as_str().is_some_and(|other| other.eq_ignore_ascii_case(sub));
+		let recent = match since {
+			Some(since) => parse_timestamp(&data["created_utc"]).is_some_and(|created| created >= since),
+			None => true,
+		};
+		if same_sub && recent && data["title"] == title {
+			return Post::from_data(data, app, MAX_CROSSPOST_DEPTH).map(Some);
+		}
+	}
+
+	Ok(None)
+}
+
+/// Whether a submission to a subreddit is expected to be accepted
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubmitCheck {
+	/// Nothing known prevents the submission
+	Allowed,
+	/// The submission would be rejected
+	Denied {
+		/// Why the submission would be rejected
+		reason: String,
+	},
+}
+
+impl SubmitCheck {
+	/// Predicts whether a submission would be accepted from the subreddit's about json, the json
+	/// of the account submitting and the subreddit's post requirements
+	pub(crate) fn evaluate(sub: &str, kind: SubmitKind, about: &Value, account: &Value, requirements: &PostRequirements) -> SubmitCheck {
+		macro_rules! deny {
+			($($arg:tt)*) => {
+				return SubmitCheck::Denied { reason: format!($($arg)*) }
+			};
+		}
+
+		let about = &about["data"];
+		let flag = |key: &str| about[key].as_bool().unwrap_or(false);
+
+		if account["is_suspended"].as_bool().unwrap_or(false) {
+			deny!("The account is suspended");
+		}
+		if flag("user_is_banned") {
+			deny!("The account is banned from r/{}", sub);
+		}
+
+		let approved = flag("user_is_contributor") || flag("user_is_moderator");
+		match about["subreddit_type"].as_str() {
+			Some("archived") => deny!("r/{} is archived", sub),
+			Some("restricted") | Some("private") | Some("employees_only") | Some("gold_restricted") if !approved => deny!("r/{} only accepts submissions from approved users", sub),
+			_ => {}
+		}
+		if flag("restrict_posting") && !approved {
+			deny!("r/{} only accepts submissions from approved users", sub);
+		}
+
+		match (about["submission_type"].as_str(), kind) {
+			(Some("self"), SubmitKind::Link) => deny!("r/{} only accepts self posts", sub),
+			(Some("link"), SubmitKind::SelfPost) => deny!("r/{} only accepts link posts", sub),
+			_ => {}
+		}
+
+		if kind == SubmitKind::Link && requirements.body_restriction_policy == "required" {
+			deny!("r/{} requires a text body, which link posts can't have", sub);
+		}
+
+		SubmitCheck::Allowed
+	}
+
+	/// Whether the submission is expected to be accepted
+	pub fn allowed(&self) -> bool {
+		match *self {
+			SubmitCheck::Allowed => true,
+			SubmitCheck::Denied { .. } => false,
+		}
+	}
+}
+
+/// The requirements a subreddit places on new posts
+#[derive(Debug, Clone)]
+pub struct PostRequirements {
+	/// Whether posts need a text body: `required`, `notAllowed` or `none`
+	pub body_restriction_policy: String,
+	/// Minimum length of titles
+	pub title_text_min_length: Option<i64>,
+	/// Maximum length of titles
+	pub title_text_max_length: Option<i64>,
+	/// Domains links may not point to
+	pub domain_blacklist: Vec<String>,
+	/// Domains links must point to, if not empty
+	pub domain_whitelist: Vec<String>,
+	/// Whether posts need a flair
+	pub is_flair_required: bool,
+}
+
+impl Thing for PostRequirements {
+	fn from_value(val: &Value, _app: &App) -> Result<PostRequirements, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "PostRequirements".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let strings = |key: &str| -> Vec<String> { val[key].as_array().map(|a| a.iter().filter_map(|d| d.as_str()).map(|d| d.to_string()).collect()).unwrap_or_default() };
+
+		let body_restriction_policy = match val["body_restriction_policy"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+
+		Ok(PostRequirements {
+			body_restriction_policy,
+			title_text_min_length: val["title_text_min_length"].as_i64(),
+			title_text_max_length: val["title_text_max_length"].as_i64(),
+			domain_blacklist: strings("domain_blacklist"),
+			domain_whitelist: strings("domain_whitelist"),
+			is_flair_required: val["is_flair_required"].as_bool().unwrap_or(false),
+		})
+	}
+}
@@ -0,0 +1,521 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use failure::Error;
+use json::{self, Value};
+use url::Url;
+
+use data::Thing;
+use errors::ParseError;
+use App;
+
+/// The settings of a subreddit as they can be edited by its moderators
+#[derive(Debug, Clone)]
+pub struct SubredditSettings {
+	/// Title of the subreddit
+	pub title: String,
+	/// Short description of the subreddit shown in search results and the sidebar
+	pub public_description: String,
+	/// The sidebar text of the subreddit
+	pub description: String,
+	/// Text shown on the submission page
+	pub submit_text: String,
+	/// Who can view and submit to the subreddit (`public`, `restricted`, `private`, ...)
+	pub subreddit_type: String,
+	/// Kind of posts allowed (`any`, `link` or `self`)
+	pub content_options: String,
+	/// Strength of the spam filter for link posts (`low`, `high` or `all`)
+	pub spam_links: String,
+	/// Strength of the spam filter for self posts (`low`, `high` or `all`)
+	pub spam_selfposts: String,
+	/// Strength of the spam filter for comments (`low`, `high` or `all`)
+	pub spam_comments: String,
+	/// Whether the subreddit is marked NSFW
+	pub over_18: bool,
+	/// Language of the subreddit
+	pub language: String,
+	/// All of the settings returned by reddit, including the ones not parsed into this struct
+	pub raw: Value,
+}
+
+impl Thing for SubredditSettings {
+	fn from_value(val: &Value, _app: &App) -> Result<SubredditSettings, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "SubredditSettings".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let data = &val["data"];
+		let title = match data["title"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let subreddit_type = match data["subreddit_type"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let content_options = match data["content_options"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let over_18 = match data["over_18"].as_bool() {
+			Some(t) => t,
+			None => out!(val),
+		};
+		// Text fields are null when they were never set
+		let text = |key: &str| data[key].as_str().unwrap_or("").to_string();
+
+		Ok(SubredditSettings {
+			title,
+			public_description: text("public_description"),
+			description: text("description"),
+			submit_text: text("submit_text"),
+			subreddit_type,
+			content_options,
+			spam_links: text("spam_links"),
+			spam_selfposts: text("spam_selfposts"),
+			spam_comments: text("spam_comments"),
+			over_18,
+			language: text("language"),
+			raw: data.clone(),
+		})
+	}
+}
+
+/// A setting that differs between two `SubredditSettings`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingChange {
+	/// Name of the setting as it appears in the settings json, e.g. `title`
+	pub field: String,
+	/// The value the setting has now
+	pub old: Value,
+	/// The value the setting is changed to
+	pub new: Value,
+}
+
+impl SubredditSettings {
+	/// Get the settings that differ between these settings and others. Only the settings parsed
+	/// into fields are compared, not the ones only present in `raw`
+	/// # Arguments
+	/// * `other` - The settings to compare to, usually the desired settings
+	pub fn diff(&self, other: &SubredditSettings) -> Vec<SettingChange> {
+		self.fields()
+			.into_iter()
+			.zip(other.fields())
+			.filter(|&((_, ref old), (_, ref new))| old != new)
+			.map(|((field, old), (_, new))| SettingChange { field: field.to_string(), old, new })
+			.collect()
+	}
+
+	fn fields(&self) -> Vec<(&'static str, Value)> {
+		vec![
+			("title", Value::from(self.title.as_str())),
+			("public_description", Value::from(self.public_description.as_str())),
+			("description", Value::from(self.description.as_str())),
+			("submit_text", Value::from(self.submit_text.as_str())),
+			("subreddit_type", Value::from(self.subreddit_type.as_str())),
+			("content_options", Value::from(self.content_options.as_str())),
+			("spam_links", Value::from(self.spam_links.as_str())),
+			("spam_selfposts", Value::from(self.spam_selfposts.as_str())),
+			("spam_comments", Value::from(self.spam_comments.as_str())),
+			("over_18", Value::from(self.over_18)),
+			("language", Value::from(self.language.as_str())),
+		]
+	}
+
+	/// The form for `/api/site_admin` that applies changes to these settings. `site_admin` resets
+	/// every setting missing from the form, so all of the settings in `raw` are sent along with
+	/// the changes.
+	pub(crate) fn site_admin_form(&self, changes: &[SettingChange]) -> Vec<(String, String)> {
+		let mut form = BTreeMap::new();
+		if let Some(raw) = self.raw.as_object() {
+			for (key, val) in raw {
+				if let Some(val) = form_value(val) {
+					form.insert(site_admin_param(key).to_string(), val);
+				}
+			}
+		}
+		for change in changes {
+			if let Some(val) = form_value(&change.new) {
+				form.insert(site_admin_param(&change.field).to_string(), val);
+			}
+		}
+		form.insert("api_type".to_string(), "json".to_string());
+
+		form.into_iter().collect()
+	}
+}
+
+/// The name `site_admin` expects for a key of the settings json. Settings sent under another name
+/// are ignored and reset like missing ones
+fn site_admin_param(key: &str) -> &str {
+	match key {
+		"subreddit_id" => "sr",
+		"subreddit_type" => "type",
+		"content_options" => "link_type",
+		"language" => "lang",
+		"default_set" => "allow_top",
+		"header_hover_text" => "header-title",
+		key => key,
+	}
+}
+
+/// A setting as a form value. Settings that were never set and nested settings are left out
+fn form_value(val: &Value) -> Option<String> {
+	match *val {
+		Value::String(ref val) => Some(val.clone()),
+		Value::Bool(val) => Some(val.to_string()),
+		Value::Number(ref val) => Some(val.to_string()),
+		_ => None,
+	}
+}
+
+/// A subreddit as it appears in listings of subreddits. The profile of a user is a subreddit too,
+/// named after the user with a `u_` prefix.
+#[derive(Debug, Clone)]
+pub struct Subreddit {
+	/// Name of the subreddit, e.g. `rust` or `u_IntrepidPig`
+	pub display_name: String,
+	/// Fullname of the subreddit
+	pub name: String,
+	/// Title of the subreddit
+	pub title: String,
+	/// Who can view and submit to the subreddit (`public`, `restricted`, `private`, `user`, ...)
+	pub subreddit_type: String,
+	/// Short description of the subreddit
+	pub public_description: String,
+	/// Amount of subscribers, if reddit included it
+	pub subscribers: Option<i64>,
+	/// Amount of users currently viewing the subreddit, if reddit included it. Reddit fuzzes the
+	/// count of small subreddits, so it changes randomly between requests and isn't exact.
+	pub accounts_active: Option<i64>,
+	/// Whether the subreddit is marked NSFW
+	pub over_18: bool,
+}
+
+impl Subreddit {
+	/// Whether the subreddit is the profile of a user
+	pub fn is_user_profile(&self) -> bool {
+		self.subreddit_type == "user"
+	}
+
+	/// Get the name of the user whose profile the subreddit is, if it is a profile
+	pub fn profile_user(&self) -> Option<&str> {
+		if self.is_user_profile() {
+			Some(self.display_name.trim_start_matches("u_"))
+		} else {
+			None
+		}
+	}
+}
+
+impl Thing for Subreddit {
+	fn from_value(val: &Value, _app: &App) -> Result<Subreddit, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "Subreddit".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		if val["kind"].as_str() != Some("t5") {
+			out!(val);
+		}
+		let data = &val["data"];
+		let display_name = match data["display_name"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let name = match data["name"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let subreddit_type = match data["subreddit_type"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+
+		Ok(Subreddit {
+			display_name,
+			name,
+			title: data["title"].as_str().unwrap_or("").to_string(),
+			subreddit_type,
+			public_description: data["public_description"].as_str().unwrap_or("").to_string(),
+			subscribers: data["subscribers"].as_i64(),
+			accounts_active: data["accounts_active"].as_i64().or_else(|| data["active_user_count"].as_i64()),
+			// Profiles have `over_18` where subreddits have `over18`
+			over_18: data["over18"].as_bool().or_else(|| data["over_18"].as_bool()).unwrap_or(false),
+		})
+	}
+}
+
+/// What an image of the style of a subreddit is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+	/// The icon of the subreddit on old reddit
+	Icon,
+	/// The icon of the subreddit on new reddit
+	CommunityIcon,
+	/// The banner of the subreddit
+	Banner,
+	/// The background image of the banner
+	BannerBackground,
+	/// The banner of the subreddit on mobile
+	MobileBanner,
+	/// The header image of old reddit
+	Header,
+	/// An image uploaded for the stylesheet of old reddit
+	StylesheetImage,
+}
+
+/// The fields of the about page of a subreddit that contain images
+const ABOUT_ASSETS: [(&str, AssetKind, &str); 6] = [
+	("icon_img", AssetKind::Icon, "icon"),
+	("community_icon", AssetKind::CommunityIcon, "community_icon"),
+	("banner_img", AssetKind::Banner, "banner"),
+	("banner_background_image", AssetKind::BannerBackground, "banner_background"),
+	("mobile_banner_image", AssetKind::MobileBanner, "mobile_banner"),
+	("header_img", AssetKind::Header, "header"),
+];
+
+/// An image of the style of a subreddit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubredditAsset {
+	/// What the image is used for
+	pub kind: AssetKind,
+	/// Url of the image
+	pub url: String,
+	/// Path the image is saved at, relative to the directory of the backup, e.g. `icon.png` or
+	/// `stylesheet/snoo.png`
+	pub file_name: String,
+}
+
+/// The result of downloading the style of a subreddit. Assets that failed to download don't stop
+/// the others from being saved.
+#[derive(Debug)]
+pub struct AssetManifest {
+	/// The assets that were saved, with the path they were saved at
+	pub saved: Vec<(SubredditAsset, PathBuf)>,
+	/// The assets that couldn't be downloaded or saved, with the reason
+	pub failed: Vec<(SubredditAsset, Error)>,
+	/// Why the images of the stylesheet couldn't be listed, e.g. because the subreddit has no
+	/// stylesheet. The other assets are still saved
+	pub stylesheet_error: Option<Error>,
+}
+
+/// Whether the user currently authorized can crosspost to a subreddit, judging from the data of
+/// the subreddit in a listing of subreddits
+pub(crate) fn can_crosspost_to(data: &Value) -> bool {
+	let flag = |key: &str| data[key].as_bool().unwrap_or(false);
+	// Submitting to restricted subreddits needs approval, which moderators have implicitly
+	let may_submit = match data["subreddit_type"].as_str() {
+		Some("public") | Some("user") => true,
+		Some("restricted") | Some("private") => flag("user_is_contributor") || flag("user_is_moderator"),
+		_ => false,
+	};
+	data["is_crosspostable_subreddit"].as_bool().unwrap_or(true) && data["submission_type"].as_str() != Some("self") && !flag("user_is_banned") && may_submit
+}
+
+/// Picks the subreddits with the given names from responses to `/api/info?sr_name=...`, in the
+/// order of the names. Names reddit found no subreddit for are left out
+pub(crate) fn subreddits_by_name(resps: &[Value], names: &[&str], app: &App) -> Result<Vec<Subreddit>, Error> {
+	let mut found = Vec::new();
+	for resp in resps {
+		if let Some(children) = resp["data"]["children"].as_array() {
+			for child in children {
+				found.push(Subreddit::from_value(child, app)?);
+			}
+		}
+	}
+
+	// Reddit matches the names case-insensitively and doesn't keep their order
+	let mut subs = Vec::with_capacity(found.len());
+	for name in names {
+		if let Some(i) = found.iter().position(|sub| sub.display_name.eq_ignore_ascii_case(name)) {
+			subs.push(found.swap_remove(i));
+		}
+	}
+	Ok(subs)
+}
+
+/// Lists the images of the style of a subreddit
+/// # Arguments
+/// * `about` - Response to `/r/{sub}/about`
+/// * `stylesheet` - Response to `/r/{sub}/about/stylesheet`
+pub(crate) fn subreddit_assets(about: &Value, stylesheet: &Value) -> Vec<SubredditAsset> {
+	let mut assets = Vec::new();
+	for &(field, kind, name) in &ABOUT_ASSETS {
+		if let Some(url) = asset_url(&about["data"][field]) {
+			let file_name = format!("{}.{}", name, asset_extension(&url));
+			assets.push(SubredditAsset { kind, url, file_name });
+		}
+	}
+
+	if let Some(images) = stylesheet["data"]["images"].as_array() {
+		for image in images {
+			if let (Some(url), Some(name)) = (asset_url(&image["url"]), image["name"].as_str()) {
+				let name: String = name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect();
+				let ext = asset_extension(&url);
+				// Different names can be the same once sanitized, so later ones are numbered
+				let mut file_name = format!("stylesheet/{}.{}", name, ext);
+				let mut n = 1;
+				while assets.iter().any(|asset: &SubredditAsset| asset.file_name.eq_ignore_ascii_case(&file_name)) {
+					n += 1;
+					file_name = format!("stylesheet/{}_{}.{}", name, n, ext);
+				}
+				assets.push(SubredditAsset { kind: AssetKind::StylesheetImage, url, file_name });
+			}
+		}
+	}
+
+	assets
+}
+
+/// The url of an image, which reddit leaves empty for images that aren't set and sometimes gives
+/// without a scheme
+fn asset_url(val: &Value) -> Option<String> {
+	let url = val.as_str().filter(|url| !url.is_empty())?.replace("&amp;", "&");
+	if url.starts_with("//") {
+		Some(format!("https:{}", url))
+	} else {
+		Some(url)
+	}
+}
+
+/// The extension of the file an image url points to. Defaults to `png`
+fn asset_extension(url: &str) -> String {
+	Url::parse(url)
+		.ok()
+		.and_then(|url| url.path_segments().and_then(|mut segments| segments.next_back().map(|last| last.to_string())))
+		.and_then(|last| last.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase()))
+		.filter(|ext| !ext.is_empty() && ext.len() <= 5 && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+		.unwrap_or_else(|| "png".to_string())
+}
+
+/// An entry of a subreddit autocompletion, which is either a subreddit or a user profile
+#[derive(Debug, Clone)]
+pub struct AutocompleteEntry {
+	/// Name of the subreddit or user
+	pub name: String,
+	/// Whether this entry is a user profile rather than a subreddit
+	pub is_profile: bool,
+	/// Amount of subscribers, if reddit included it
+	pub subscribers: Option<i64>,
+	/// Url of the icon of the subreddit or user, if it has one
+	pub icon: Option<String>,
+	/// Whether the subreddit or profile is marked NSFW
+	pub over_18: bool,
+}
+
+impl Thing for AutocompleteEntry {
+	fn from_value(val: &Value, _app: &App) -> Result<AutocompleteEntry, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "AutocompleteEntry".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let data = &val["data"];
+		let icon = |icon: &Value| icon.as_str().filter(|icon| !icon.is_empty()).map(|icon| icon.replace("&amp;", "&"));
+		match val["kind"].as_str() {
+			Some("t5") => {
+				let name = match data["display_name"].as_str() {
+					Some(t) => t,
+					None => out!(val),
+				};
+				// Profile subreddits are named after their user with a `u_` prefix
+				let is_profile = data["subreddit_type"].as_str() == Some("user");
+				Ok(AutocompleteEntry {
+					name: if is_profile { name.trim_start_matches("u_") } else { name }.to_string(),
+					is_profile,
+					subscribers: data["subscribers"].as_i64(),
+					icon: icon(&data["community_icon"]).or_else(|| icon(&data["icon_img"])),
+					over_18: data["over18"].as_bool().unwrap_or(false),
+				})
+			}
+			Some("t2") => {
+				let name = match data["name"].as_str() {
+					Some(t) => t.to_string(),
+					None => out!(val),
+				};
+				// The profile subreddit of the user holds the subscriber and nsfw info
+				let profile = &data["subreddit"];
+				Ok(AutocompleteEntry {
+					name,
+					is_profile: true,
+					subscribers: profile["subscribers"].as_i64(),
+					icon: icon(&data["icon_img"]),
+					over_18: profile["over_18"].as_bool().unwrap_or(false),
+				})
+			}
+			_ => out!(val),
+		}
+	}
+}
+
+/// A permission a moderator can have in a subreddit
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModPermission {
+	/// Full permissions, which include all others
+	All,
+	/// Managing approved users and bans
+	Access,
+	/// Editing the settings, sidebar, rules and so on
+	Config,
+	/// Managing flair
+	Flair,
+	/// Reading and sending modmail
+	Mail,
+	/// Approving, removing and otherwise moderating posts and comments
+	Posts,
+	/// Managing the wiki
+	Wiki,
+	/// Configuring chat
+	ChatConfig,
+	/// Moderating chat
+	ChatOperator,
+	/// A permission unknown to orca
+	Other(String),
+}
+
+impl ModPermission {
+	/// Parses a permission from its name in the api
+	pub fn from_name(name: &str) -> ModPermission {
+		use self::ModPermission::*;
+		match name {
+			"all" => All,
+			"access" => Access,
+			"config" => Config,
+			"flair" => Flair,
+			"mail" => Mail,
+			"posts" => Posts,
+			"wiki" => Wiki,
+			"chat_config" => ChatConfig,
+			"chat_operator" => ChatOperator,
+			other => Other(other.to_string()),
+		}
+	}
+
+	/// Whether a set of permissions includes this permission, either directly or through `All`
+	pub fn granted_by(&self, permissions: &[ModPermission]) -> bool {
+		permissions.iter().any(|permission| permission == self || *permission == ModPermission::All)
+	}
+}
+
+/// Gets the permissions of a user from a listing of moderators
+pub(crate) fn mod_permissions_of(resp: &Value, user: &str) -> Vec<ModPermission> {
+	let moderators = resp["data"]["children"].as_array().cloned().unwrap_or_default();
+	match moderators.iter().find(|moderator| moderator["name"].as_str().is_some_and(|name| name.eq_ignore_ascii_case(user))) {
+		Some(moderator) => moderator["mod_permissions"].as_array().map(|perms| perms.iter().filter_map(|perm| perm.as_str()).map(ModPermission::from_name).collect()).unwrap_or_default(),
+		None => Vec::new(),
+	}
+}
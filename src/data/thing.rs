@@ -1,5 +1,7 @@
 use failure::Error;
 use json;
+
+use data::{Comment, Post, MAX_CROSSPOST_DEPTH};
 use App;
 
 /// A trait representing a reddit Thing that can be deserialized from JSON
@@ -13,3 +15,16 @@ pub trait Thing {
 	where
 		Self: Sized;
 }
+
+/// Whether the thing in a response to a request to `/api/info` is still publicly visible, which
+/// it isn't if it is missing from the response or was removed or deleted. Things other than posts
+/// and comments are visible if they are present
+pub(crate) fn info_available(resp: &json::Value, app: &App) -> Result<bool, Error> {
+	let thing = &resp["data"]["children"][0];
+	match thing["kind"].as_str() {
+		None => Ok(false),
+		Some("t1") => Comment::from_value(thing, app).map(|comment| !comment.is_removed() && !comment.is_deleted()),
+		Some("t3") => Post::from_data(&thing["data"], app, MAX_CROSSPOST_DEPTH).map(|post| !post.is_removed() && !post.is_deleted()),
+		Some(_) => Ok(true),
+	}
+}
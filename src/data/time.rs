@@ -0,0 +1,29 @@
+use chrono::{DateTime, TimeZone, Utc};
+use failure::Error;
+use json::Value;
+
+/// Converts reddit's floating point epoch seconds to a `DateTime<Utc>`
+pub(crate) fn from_epoch(secs: f64) -> Option<DateTime<Utc>> {
+	if !secs.is_finite() {
+		return None;
+	}
+	let whole = secs.trunc();
+	let nanos = ((secs - whole) * 1_000_000_000.0).round() as u32;
+	Utc.timestamp_opt(whole as i64, nanos.min(999_999_999)).single()
+}
+
+/// Parses a timestamp field like `created_utc`, which reddit leaves out or sets to 0 for some
+/// deleted content
+pub(crate) fn parse_timestamp(val: &Value) -> Option<DateTime<Utc>> {
+	val.as_f64().filter(|secs| *secs != 0.0).and_then(from_epoch)
+}
+
+/// Parses the `edited` field of a thing, which is `false` when the thing was never edited and
+/// the epoch time of the last edit otherwise
+pub(crate) fn parse_edited(val: &Value) -> Result<Option<DateTime<Utc>>, Error> {
+	match *val {
+		Value::Bool(_) | Value::Null => Ok(None),
+		Value::Number(ref num) => Ok(num.as_f64().and_then(from_epoch)),
+		_ => Err(format_err!("Unexpected value for \"edited\": {}", val)),
+	}
+}
@@ -1,3 +1,12 @@
+use chrono::{DateTime, Utc};
+use failure::Error;
+use json::{self, Value};
+
+use data::time::parse_timestamp;
+use data::Thing;
+use errors::ParseError;
+use App;
+
 /// Struct that represent's a user that could either be authorized or not
 pub enum User {
 	/// An authorized user
@@ -13,13 +22,14 @@ pub struct AuthUserData {
 }
 
 /// Data structure that represents a user's info
+#[derive(Debug, Clone)]
 pub struct UserData {
 	/// Comment karma of the user
 	pub comment_karma: i64,
-	/// The time the user was created in seconds
-	pub created: f64,
-	/// I DON't KNoowW
-	pub created_utc: f64,
+	/// The time the user was created, in the timezone of reddit's servers
+	pub created: DateTime<Utc>,
+	/// The time the user was created
+	pub created_utc: DateTime<Utc>,
 	/// also don't know
 	pub has_subscribed: bool,
 	/// Whether the user has verified their email
@@ -41,3 +51,260 @@ pub struct UserData {
 	/// The user's username
 	pub name: String,
 }
+
+impl Thing for UserData {
+	fn from_value(val: &Value, _app: &App) -> Result<UserData, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "UserData".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let data = &val["data"];
+		let comment_karma = match data["comment_karma"].as_i64() {
+			Some(t) => t,
+			None => out!(val),
+		};
+		let created = match parse_timestamp(&data["created"]) {
+			Some(t) => t,
+			None => out!(val),
+		};
+		let created_utc = match parse_timestamp(&data["created_utc"]) {
+			Some(t) => t,
+			None => out!(val),
+		};
+		let id = match data["id"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let link_karma = match data["link_karma"].as_i64() {
+			Some(t) => t,
+			None => out!(val),
+		};
+		let name = match data["name"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		// These are not present in every context (e.g. is_friend when not authorized)
+		let has_subscribed = data["has_subscribed"].as_bool().unwrap_or(false);
+		let has_verified_email = data["has_verified_email"].as_bool().unwrap_or(false);
+		let hide_from_robots = data["hide_from_robots"].as_bool().unwrap_or(false);
+		let is_employee = data["is_employee"].as_bool().unwrap_or(false);
+		let is_friend = data["is_friend"].as_bool().unwrap_or(false);
+		let is_gold = data["is_gold"].as_bool().unwrap_or(false);
+		let is_mod = data["is_mod"].as_bool().unwrap_or(false);
+
+		Ok(UserData {
+			comment_karma,
+			created,
+			created_utc,
+			has_subscribed,
+			has_verified_email,
+			hide_from_robots,
+			id,
+			is_employee,
+			is_friend,
+			is_gold,
+			is_mod,
+			link_karma,
+			name,
+		})
+	}
+}
+
+/// The result of looking up the author of a post or comment
+#[derive(Debug, Clone)]
+pub enum UserLookup {
+	/// The info of the author
+	Found(UserData),
+	/// The account of the author was deleted, so there is nothing to look up
+	NotAvailable,
+}
+
+impl UserLookup {
+	/// Looks up the info of an author, without sending a request if there is no author
+	pub(crate) fn lookup(author: Option<&str>, app: &App) -> Result<UserLookup, Error> {
+		match author {
+			Some(author) => Ok(UserLookup::Found(UserData::from_value(&app.get_user(author)?, app)?)),
+			None => Ok(UserLookup::NotAvailable),
+		}
+	}
+}
+
+/// Kind of things to retrieve from a user's saved things
+pub enum SavedKind {
+	/// Only saved posts
+	Links,
+	/// Only saved comments
+	Comments,
+}
+
+impl SavedKind {
+	/// Convert the kind to a tuple to be used in url parameters
+	pub fn param<'a>(self) -> (&'a str, &'a str) {
+		use self::SavedKind::*;
+		(
+			"type",
+			match self {
+				Links => "links",
+				Comments => "comments",
+			},
+		)
+	}
+}
+
+/// The karma of the user currently authorized in a single subreddit
+#[derive(Debug, Clone, PartialEq)]
+pub struct KarmaEntry {
+	/// Name of the subreddit
+	pub sub: String,
+	/// Karma from comments in the subreddit
+	pub comment_karma: i64,
+	/// Karma from posts in the subreddit
+	pub link_karma: i64,
+}
+
+impl KarmaEntry {
+	/// The sum of comment and link karma in the subreddit
+	pub fn total(&self) -> i64 {
+		self.comment_karma + self.link_karma
+	}
+}
+
+/// The karma of the user currently authorized, broken down by subreddit
+#[derive(Debug, Clone)]
+pub struct KarmaBreakdown {
+	/// Karma in each subreddit, sorted by total karma descending
+	pub entries: Vec<KarmaEntry>,
+	/// Sum of the comment karma of all entries
+	pub total_comment: i64,
+	/// Sum of the link karma of all entries
+	pub total_link: i64,
+}
+
+impl Thing for KarmaBreakdown {
+	fn from_value(val: &Value, _app: &App) -> Result<KarmaBreakdown, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "KarmaBreakdown".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let list = match val["data"].as_array() {
+			Some(t) => t,
+			None => out!(val),
+		};
+
+		let mut entries = Vec::new();
+		for entry in list {
+			let sub = match entry["sr"].as_str() {
+				Some(t) => t.to_string(),
+				None => out!(val),
+			};
+			let comment_karma = match entry["comment_karma"].as_i64() {
+				Some(t) => t,
+				None => out!(val),
+			};
+			let link_karma = match entry["link_karma"].as_i64() {
+				Some(t) => t,
+				None => out!(val),
+			};
+			entries.push(KarmaEntry { sub, comment_karma, link_karma });
+		}
+		entries.sort_by_key(|entry| std::cmp::Reverse(entry.total()));
+
+		let total_comment = entries.iter().map(|entry| entry.comment_karma).sum();
+		let total_link = entries.iter().map(|entry| entry.link_karma).sum();
+
+		Ok(KarmaBreakdown { entries, total_comment, total_link })
+	}
+}
+
+/// The unread counts of the user currently authorized, as shown in badges
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NotificationCounts {
+	/// Amount of unread messages in the inbox
+	pub inbox: i64,
+	/// Amount of unread new modmail conversations, which are new, in progress, from moderators or
+	/// notifications
+	pub modmail: i64,
+}
+
+impl NotificationCounts {
+	/// Creates the counts from the json of the user currently authorized and the unread counts of
+	/// modmail, if they were requested
+	pub(crate) fn from_values(me: &Value, modmail: Option<&Value>) -> NotificationCounts {
+		let inbox = me["inbox_count"].as_i64().unwrap_or(0);
+		let modmail = modmail.map(|counts| ["new", "inprogress", "mod", "notifications"].iter().filter_map(|state| counts[*state].as_i64()).sum()).unwrap_or(0);
+
+		NotificationCounts { inbox, modmail }
+	}
+}
+
+/// A user banned or muted in a subreddit
+#[derive(Debug, Clone)]
+pub struct BannedUser {
+	/// Name of the user
+	pub name: String,
+	/// Fullname of the user
+	pub id: String,
+	/// Note of the moderator who banned the user. Empty for mutes
+	pub note: String,
+	/// Days until the ban ends, or `None` if it is permanent or a mute
+	pub days_left: Option<i64>,
+	/// Time the user was banned or muted
+	pub date: Option<DateTime<Utc>>,
+}
+
+impl Thing for BannedUser {
+	fn from_value(val: &Value, _app: &App) -> Result<BannedUser, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "BannedUser".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let name = match val["name"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let id = match val["id"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+
+		Ok(BannedUser {
+			name,
+			id,
+			note: val["note"].as_str().unwrap_or("").to_string(),
+			days_left: val["days_left"].as_i64(),
+			date: parse_timestamp(&val["date"]),
+		})
+	}
+}
+
+/// Gets the names of the users in a `UserList`, the response to requests for lists like the
+/// blocked users of the user currently authorized, which is not shaped like a normal listing
+pub(crate) fn user_list_names(resp: &Value) -> Result<Vec<String>, Error> {
+	let names = if resp["kind"] == "UserList" {
+		resp["data"]["children"].as_array().and_then(|users| users.iter().map(|user| user["name"].as_str().map(|name| name.to_string())).collect())
+	} else {
+		None
+	};
+
+	names.ok_or_else(|| {
+		Error::from(ParseError {
+			thing_type: "UserList".to_string(),
+			json: json::to_string_pretty(resp).unwrap(),
+		})
+	})
+}
@@ -0,0 +1,150 @@
+use failure::Error;
+use json::{self, Value};
+
+use data::Thing;
+use errors::ParseError;
+use App;
+
+/// The widgets of a subreddit, which new reddit shows in the sidebar instead of the description
+#[derive(Debug, Clone)]
+pub struct Widgets {
+	/// The widgets of the sidebar, in order
+	pub sidebar: Vec<Widget>,
+	/// The widget with the name, subscribers and description of the subreddit
+	pub id_card: Option<Widget>,
+	/// The widget listing the moderators of the subreddit
+	pub moderators: Option<Widget>,
+}
+
+/// A widget of a subreddit
+#[derive(Debug, Clone)]
+pub struct Widget {
+	/// Id of the widget
+	pub id: String,
+	/// Title of the widget
+	pub short_name: String,
+	/// Kind of the widget, with the data specific to it
+	pub kind: WidgetKind,
+}
+
+/// A rule shown in a rules widget
+#[derive(Debug, Clone, PartialEq)]
+pub struct WidgetRule {
+	/// Short name of the rule
+	pub short_name: String,
+	/// Description of the rule
+	pub description: String,
+}
+
+/// Kind of a widget, with the data specific to it
+#[derive(Debug, Clone)]
+pub enum WidgetKind {
+	/// The rules of the subreddit
+	Rules(Vec<WidgetRule>),
+	/// A list of related subreddits
+	CommunityList(Vec<String>),
+	/// Markdown text
+	TextArea(String),
+	/// Custom markdown with its own styling
+	Custom(String),
+	/// Links shown as buttons, with their texts and urls
+	Button(Vec<(String, String)>),
+	/// The name, subscribers and description of the subreddit
+	IdCard {
+		/// Amount of subscribers
+		subscribers: i64,
+		/// Amount of users currently viewing the subreddit
+		currently_viewing: i64,
+		/// Short description of the subreddit
+		description: String,
+	},
+	/// The moderators of the subreddit
+	Moderators(Vec<String>),
+	/// A kind of widget unknown to orca, with its kind and json
+	Other(String, Value),
+}
+
+impl Thing for Widget {
+	fn from_value(val: &Value, _app: &App) -> Result<Widget, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "Widget".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let id = match val["id"].as_str() {
+			Some(t) => t.to_string(),
+			None => out!(val),
+		};
+		let kind_name = match val["kind"].as_str() {
+			Some(t) => t,
+			None => out!(val),
+		};
+		let short_name = val["shortName"].as_str().unwrap_or("").to_string();
+
+		let text = |key: &str| val[key].as_str().unwrap_or("").to_string();
+		let each = |key: &str| val[key].as_array().cloned().unwrap_or_default();
+		let kind = match kind_name {
+			"subreddit-rules" => WidgetKind::Rules(
+				each("data")
+					.iter()
+					.map(|rule| WidgetRule {
+						short_name: rule["shortName"].as_str().unwrap_or("").to_string(),
+						description: rule["description"].as_str().unwrap_or("").to_string(),
+					})
+					.collect(),
+			),
+			"community-list" => WidgetKind::CommunityList(each("data").iter().filter_map(|sub| sub["name"].as_str()).map(|sub| sub.to_string()).collect()),
+			"textarea" => WidgetKind::TextArea(text("text")),
+			"custom" => WidgetKind::Custom(text("text")),
+			"button" => WidgetKind::Button(each("buttons").iter().map(|button| (button["text"].as_str().unwrap_or("").to_string(), button["url"].as_str().unwrap_or("").to_string())).collect()),
+			"id-card" => WidgetKind::IdCard {
+				subscribers: val["subscribersCount"].as_i64().unwrap_or(0),
+				currently_viewing: val["currentlyViewingCount"].as_i64().unwrap_or(0),
+				description: text("description"),
+			},
+			"moderators" => WidgetKind::Moderators(each("mods").iter().filter_map(|user| user["name"].as_str()).map(|user| user.to_string()).collect()),
+			other => WidgetKind::Other(other.to_string(), val.clone()),
+		};
+
+		Ok(Widget { id, short_name, kind })
+	}
+}
+
+impl Thing for Widgets {
+	fn from_value(val: &Value, app: &App) -> Result<Widgets, Error> {
+		macro_rules! out {
+			($val:ident) => {
+				return Err(Error::from(ParseError {
+					thing_type: "Widgets".to_string(),
+					json: json::to_string_pretty($val).unwrap(),
+				}));
+			};
+		}
+
+		let items = &val["items"];
+		if !items.is_object() {
+			out!(val);
+		}
+		let widget = |id: &Value| -> Result<Option<Widget>, Error> {
+			match id.as_str() {
+				Some(id) if items[id].is_object() => Ok(Some(Widget::from_value(&items[id], app)?)),
+				_ => Ok(None),
+			}
+		};
+
+		let mut sidebar = Vec::new();
+		for id in val["layout"]["sidebar"]["order"].as_array().cloned().unwrap_or_default() {
+			if let Some(widget) = widget(&id)? {
+				sidebar.push(widget);
+			}
+		}
+		let id_card = widget(&val["layout"]["idCardWidget"])?;
+		let moderators = widget(&val["layout"]["moderatorWidget"])?;
+
+		Ok(Widgets { sidebar, id_card, moderators })
+	}
+}
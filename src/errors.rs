@@ -1,3 +1,10 @@
+use std::fmt;
+use std::time::Duration;
+
+use failure::Error;
+
+use data::Post;
+
 /// An enum containing possible errors from a request to reddit
 #[derive(Debug, Fail)]
 pub enum RedditError {
@@ -13,6 +20,102 @@ pub enum RedditError {
 		/// The requested resource
 		request: String,
 	},
+	/// The requested subreddit is private
+	#[fail(display = "Subreddit {} is private", sub)]
+	SubredditPrivate {
+		/// Name of the subreddit
+		sub: String,
+	},
+	/// The requested subreddit has been banned
+	#[fail(display = "Subreddit {} is banned", sub)]
+	SubredditBanned {
+		/// Name of the subreddit
+		sub: String,
+	},
+	/// The requested subreddit does not exist
+	#[fail(display = "Subreddit {} does not exist", sub)]
+	SubredditNotFound {
+		/// Name of the subreddit
+		sub: String,
+	},
+	/// The account currently authorized is suspended site-wide
+	#[fail(display = "The account is suspended")]
+	AccountSuspended,
+	/// The account currently authorized is banned from a subreddit or otherwise not allowed to
+	/// post there
+	#[fail(display = "The account is banned from subreddit {:?}", sub)]
+	BannedFromSubreddit {
+		/// Name of the subreddit, if the request named it in its path
+		sub: Option<String>,
+		/// `ApiErrorCode::SubredditNotAllowed` if reddit refused the request with that code, which
+		/// it also sends for restricted and approved-only subreddits. `None` for other bans
+		code: Option<ApiErrorCode>,
+	},
+	/// Reddit refused an action because the account did it too often, like commenting during the
+	/// comment cooldown
+	#[fail(display = "Ratelimited by reddit, try again in {:?}", wait)]
+	RateLimited {
+		/// How long to wait before trying again
+		wait: Duration,
+	},
+	/// A submission was not sent because the account already made the same submission, see
+	/// `App::set_duplicate_guard`
+	#[fail(display = "The same submission was already made")]
+	AlreadySubmitted {
+		/// The earlier submission. Its comments are not loaded
+		existing: Box<Post>,
+	},
+	/// The requested resource is marked over 18 and reddit responded with a confirmation gate
+	/// instead of the content
+	#[fail(display = "Requested resource {} is behind an over 18 gate", request)]
+	NsfwGate {
+		/// The requested resource
+		request: String,
+	},
+	/// Reddit redirected the request instead of responding to it
+	#[fail(display = "Request {} was redirected to {}", request, location)]
+	Redirected {
+		/// The request that was sent
+		request: String,
+		/// Where reddit redirected the request to
+		location: String,
+	},
+	/// Reddit redirected the request to its login page, so the endpoint needs the connection to
+	/// be authorized
+	#[fail(display = "Request {} needs authorization", request)]
+	NotAuthenticated {
+		/// The request that was sent
+		request: String,
+	},
+	/// The request was blocked by cloudflare before reaching reddit, which usually happens under heavy load
+	#[fail(display = "Request was blocked by cloudflare (ray id {:?})", cf_ray)]
+	Blocked {
+		/// The cloudflare ray id of the blocked request, if it was present
+		cf_ray: Option<String>,
+	},
+	/// The request timed out before reddit responded, which is usually worth retrying
+	#[fail(display = "Request {} timed out", request)]
+	Timeout {
+		/// The request that was sent
+		request: String,
+	},
+	/// No connection to reddit could be made, or it broke before the response was recieved
+	#[fail(display = "Connection failed for request {}: {}", request, error)]
+	ConnectionFailed {
+		/// The request that was sent
+		request: String,
+		/// What went wrong with the connection
+		error: String,
+	},
+	/// The host name of the request couldn't be resolved, which usually means there is no network
+	/// or the dns configuration is wrong
+	#[fail(display = "Could not resolve the host of request {}: {}", request, error)]
+	Dns {
+		/// The request that was sent
+		request: String,
+		/// The error of the lookup
+		error: String,
+	},
 	/// Recieved a response that was unexpected
 	#[fail(display = "\nSent request {}, got unexpected reponse {}\n", request, response)]
 	BadResponse {
@@ -28,10 +131,111 @@ pub enum RedditError {
 		request: String,
 		/// The response that was recieved
 		response: String,
+		/// The status of the response, if the request was rejected with an unsuccessful status
+		status: Option<u16>,
+	},
+	/// Reddit refused an action with an error code that calls for a specific reaction, like a
+	/// comment on an archived post. Other codes are reported as `BadRequest`
+	#[fail(display = "Request {} was refused with {}: {}", request, code, message)]
+	ApiError {
+		/// The request that was sent
+		request: String,
+		/// The code reddit refused the action with
+		code: ApiErrorCode,
+		/// The explanation reddit sent with the code
+		message: String,
 	},
 	/// Authorization failed
 	#[fail(display = "Failed to authorize")]
 	AuthError,
+	/// A step of an action that takes multiple requests failed, so the following steps were not sent
+	#[fail(display = "Step \"{}\" failed: {}", step, error)]
+	StepFailed {
+		/// The step that failed
+		step: String,
+		/// The error the step failed with
+		error: Error,
+	},
+}
+
+impl RedditError {
+	/// The http status of the response the error originated from, for instance to use as a
+	/// metrics label. `None` if the error didn't come from an unsuccessful response or the status
+	/// isn't known.
+	pub fn status_code(&self) -> Option<u16> {
+		use self::RedditError::*;
+		match *self {
+			NotFound { .. } | SubredditBanned { .. } | SubredditNotFound { .. } => Some(404),
+			Forbidden { .. } | SubredditPrivate { .. } | AccountSuspended | BannedFromSubreddit { .. } => Some(403),
+			RateLimited { .. } => Some(429),
+			BadRequest { status, .. } => status,
+			AlreadySubmitted { .. } | ApiError { .. } | NsfwGate { .. } | Redirected { .. } | NotAuthenticated { .. } | Blocked { .. } | Timeout { .. } | ConnectionFailed { .. } | Dns { .. } | BadResponse { .. } | AuthError | StepFailed { .. } => None,
+		}
+	}
+}
+
+/// The error codes of reddit's api that `RedditError::ApiError` tells apart.
+/// `SUBREDDIT_NOTALLOWED` is reported as `RedditError::BannedFromSubreddit` carrying the code
+/// instead, since reddit also sends it to banned accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiErrorCode {
+	/// `SUBREDDIT_NOTALLOWED`: The account may not submit to the subreddit, because it is banned
+	/// or the subreddit is restricted or only accepts approved accounts
+	SubredditNotAllowed,
+	/// `NOT_WHITELISTED`: The recipient or subreddit only accepts approved accounts
+	NotWhitelisted,
+	/// `TOO_OLD`: The post or comment is archived and can't be commented on or voted on anymore
+	TooOld,
+	/// `THREAD_LOCKED`: The post or comment is locked by the moderators
+	ThreadLocked,
+	/// `DELETED_LINK`: The post was deleted
+	DeletedLink,
+	/// `SOMETHING_IS_BROKEN`: Reddit failed to handle the request, which is usually worth retrying
+	SomethingIsBroken,
+}
+
+impl ApiErrorCode {
+	/// Get the code for an error code sent by reddit, if it is one of the codes told apart
+	pub fn from_code(code: &str) -> Option<ApiErrorCode> {
+		match code {
+			"SUBREDDIT_NOTALLOWED" => Some(ApiErrorCode::SubredditNotAllowed),
+			"NOT_WHITELISTED" => Some(ApiErrorCode::NotWhitelisted),
+			"TOO_OLD" => Some(ApiErrorCode::TooOld),
+			"THREAD_LOCKED" => Some(ApiErrorCode::ThreadLocked),
+			"DELETED_LINK" => Some(ApiErrorCode::DeletedLink),
+			"SOMETHING_IS_BROKEN" => Some(ApiErrorCode::SomethingIsBroken),
+			_ => None,
+		}
+	}
+
+	/// Get the error code as reddit sends it
+	pub fn as_str(self) -> &'static str {
+		match self {
+			ApiErrorCode::SubredditNotAllowed => "SUBREDDIT_NOTALLOWED",
+			ApiErrorCode::NotWhitelisted => "NOT_WHITELISTED",
+			ApiErrorCode::TooOld => "TOO_OLD",
+			ApiErrorCode::ThreadLocked => "THREAD_LOCKED",
+			ApiErrorCode::DeletedLink => "DELETED_LINK",
+			ApiErrorCode::SomethingIsBroken => "SOMETHING_IS_BROKEN",
+		}
+	}
+}
+
+impl fmt::Display for ApiErrorCode {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+/// An error representing a listing with less or more children than reddit said it contains,
+/// which happens when children were skipped while parsing it
+#[derive(Debug, Fail, Clone, PartialEq, Eq)]
+#[fail(display = "Listing has {} children, but reddit sent {}", parsed, dist)]
+pub struct DistMismatch {
+	/// Amount of children reddit said the listing contains
+	pub dist: u64,
+	/// Amount of children the listing contains
+	pub parsed: usize,
 }
 
 /// An error representing a json value that could not be parsed as a certain struct
@@ -0,0 +1,179 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use failure::Error;
+use json;
+use serde::Serialize;
+
+/// Writes items as newline-delimited json (one json object per line) to a file, e.g. to export
+/// the posts of a subreddit for analysis in other tools. The file can be rotated once it reaches
+/// a size, which continues the export in `name.1.jsonl`, `name.2.jsonl` and so on.
+///
+/// The writer remembers the fullname of the last item written as a resume token in a file next
+/// to the export (`name.jsonl.resume`), so an export that is interrupted can be continued by
+/// creating a writer for the same path. Files are appended to, and only truncated to remove a
+/// last line that was cut off by a crash while it was written. Buffered items are flushed when
+/// the writer is dropped.
+pub struct JsonlWriter<T: Serialize> {
+	path: PathBuf,
+	max_bytes: Option<u64>,
+	part: usize,
+	file: BufWriter<File>,
+	written: u64,
+	resume: Option<String>,
+	items: PhantomData<T>,
+}
+
+impl<T: Serialize> JsonlWriter<T> {
+	/// Opens an export, continuing in the last part of it and reading its resume token if it
+	/// exists
+	/// # Arguments
+	/// * `path` - Path of the first file of the export, e.g. `posts.jsonl`
+	pub fn create<P: AsRef<Path>>(path: P) -> Result<JsonlWriter<T>, Error> {
+		let path = path.as_ref().to_path_buf();
+		let mut part = 0;
+		while part_path(&path, part + 1).exists() {
+			part += 1;
+		}
+		let (file, written) = open_part(&path, part)?;
+		let resume = fs::read_to_string(resume_path(&path)).ok().map(|token| token.trim().to_string()).filter(|token| !token.is_empty());
+
+		Ok(JsonlWriter {
+			path,
+			max_bytes: None,
+			part,
+			file,
+			written,
+			resume,
+			items: PhantomData,
+		})
+	}
+
+	/// Starts a new file once the current file would grow past a size. Files are never rotated by
+	/// default
+	/// # Arguments
+	/// * `max_bytes` - Maximum size of a file. A single item larger than this gets a file of its own
+	pub fn rotate_at(&mut self, max_bytes: u64) {
+		self.max_bytes = Some(max_bytes);
+	}
+
+	/// Get the fullname of the last item written, which an interrupted export continues after
+	pub fn resume_token(&self) -> Option<&str> {
+		self.resume.as_deref()
+	}
+
+	/// Get the paths of all files of the export written so far, in order
+	pub fn paths(&self) -> Vec<PathBuf> {
+		(0..=self.part).map(|part| part_path(&self.path, part)).collect()
+	}
+
+	/// Writes an item as a line of json
+	pub fn write(&mut self, item: &T) -> Result<(), Error> {
+		let mut line = json::to_vec(item)?;
+		line.push(b'\n');
+
+		if let Some(max_bytes) = self.max_bytes {
+			if self.written > 0 && self.written + line.len() as u64 > max_bytes {
+				self.file.flush()?;
+				self.part += 1;
+				let (file, written) = open_part(&self.path, self.part)?;
+				self.file = file;
+				self.written = written;
+			}
+		}
+
+		self.file.write_all(&line)?;
+		self.written += line.len() as u64;
+		Ok(())
+	}
+
+	/// Writes an item and records its fullname as the resume token
+	/// # Arguments
+	/// * `fullname` - Fullname of the item, e.g. `t3_7le01h`
+	/// * `item` - The item to write
+	pub fn write_named(&mut self, fullname: &str, item: &T) -> Result<(), Error> {
+		self.write(item)?;
+		self.resume = Some(fullname.to_string());
+		Ok(())
+	}
+
+	/// Writes the buffered items to the file, then saves the resume token. The token is only saved
+	/// after the items before it are written, so a resumed export never skips items.
+	pub fn flush(&mut self) -> Result<(), Error> {
+		self.file.flush()?;
+		if let Some(ref resume) = self.resume {
+			fs::write(resume_path(&self.path), resume)?;
+		}
+		Ok(())
+	}
+}
+
+impl<T: Serialize> Drop for JsonlWriter<T> {
+	fn drop(&mut self) {
+		if let Err(e) = self.flush() {
+			warn!("Failed to flush export to {}: {}", self.path.display(), e);
+		}
+	}
+}
+
+/// The path of a part of an export. The first part is the path itself, later parts number the
+/// file before its extension
+fn part_path(path: &Path, part: usize) -> PathBuf {
+	if part == 0 {
+		return path.to_path_buf();
+	}
+	let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+	let name = match path.extension() {
+		Some(ext) => format!("{}.{}.{}", stem, part, ext.to_string_lossy()),
+		None => format!("{}.{}", stem, part),
+	};
+	path.with_file_name(name)
+}
+
+fn resume_path(path: &Path) -> PathBuf {
+	let mut name = path.file_name().map(|name| name.to_os_string()).unwrap_or_default();
+	name.push(".resume");
+	path.with_file_name(name)
+}
+
+fn open_part(path: &Path, part: usize) -> Result<(BufWriter<File>, u64), Error> {
+	let path = part_path(path, part);
+	truncate_partial_line(&path)?;
+	let file = OpenOptions::new().create(true).append(true).open(path)?;
+	let written = file.metadata()?.len();
+	Ok((BufWriter::new(file), written))
+}
+
+/// Cuts a file back to the end of its last complete line, so an export that crashed while a line
+/// was written continues on a line of its own
+fn truncate_partial_line(path: &Path) -> Result<(), Error> {
+	let mut file = match OpenOptions::new().read(true).write(true).open(path) {
+		Ok(file) => file,
+		Err(ref e) if e.kind() == ::std::io::ErrorKind::NotFound => return Ok(()),
+		Err(e) => return Err(Error::from(e)),
+	};
+
+	// Search backwards for the last newline, a chunk at a time
+	let len = file.metadata()?.len();
+	let mut end = len;
+	let mut chunk = vec![0; 8192];
+	while end > 0 {
+		let start = end.saturating_sub(chunk.len() as u64);
+		let size = (end - start) as usize;
+		file.seek(SeekFrom::Start(start))?;
+		file.read_exact(&mut chunk[..size])?;
+		if let Some(i) = chunk[..size].iter().rposition(|&b| b == b'\n') {
+			end = start + i as u64 + 1;
+			break;
+		}
+		end = start;
+	}
+
+	if end < len {
+		warn!("Removing the last line of {}, which was cut off", path.display());
+		file.set_len(end)?;
+	}
+	Ok(())
+}
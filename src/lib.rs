@@ -27,7 +27,7 @@
 //! ```rust
 //! # use orca::App;
 //! # let (name, version, author) = ("a", "b", "c");
-//! let mut reddit = App::new(name, version, author).unwrap();
+//! let reddit = App::new(name, version, author).unwrap();
 //! ```
 //!
 //! where `name`, `version`, and `author` are all `&str`s.
@@ -37,11 +37,11 @@
 //! done multiple ways. The most common way for clients to authorize is as scripts, which can be
 //! done by just providing a username and password as well as the id and secret of the app that can
 //! be registered on the desktop site. It looks like this in code (assuming you already have a
-//! mutable reddit instance):
+//! reddit instance):
 //!
 //! ```rust,no_run
 //! # use orca::App;
-//! # let mut reddit = App::new("a", "b", "c").unwrap();
+//! # let reddit = App::new("a", "b", "c").unwrap();
 //! # let (id, secret, username, password) = ("a", "b", "c", "d");
 //! reddit.authorize_script(id, secret, username, password).unwrap();
 //! ```
@@ -52,7 +52,7 @@
 //!
 //! ```rust,no_run
 //! # use orca::App;
-//! # let mut reddit = App::new("a", "b", "c").unwrap();
+//! # let reddit = App::new("a", "b", "c").unwrap();
 //! reddit.get_self();
 //! ```
 //!
@@ -70,6 +70,8 @@ extern crate hyper_tls;
 #[macro_use]
 extern crate log;
 extern crate base64;
+#[cfg(feature = "dangerous")]
+extern crate native_tls;
 extern crate open;
 extern crate rand;
 extern crate serde;
@@ -92,8 +94,14 @@ pub mod errors;
 /// Main entry point
 pub mod app;
 
+/// Main loop for bots reacting to new comments, posts and mentions
+pub mod bot;
+
+/// Exporting listings and streams as newline-delimited json
+pub mod export;
+
 pub use app::App;
-pub use data::{Sort, SortTime};
-pub use errors::RedditError;
-pub use net::auth::{self, InstalledAppError, ResponseGenFn, Scopes};
-pub use net::{Connection, LimitMethod};
+pub use data::{SavedKind, Sort, SortTime, Target};
+pub use errors::{ApiErrorCode, RedditError};
+pub use net::auth::{self, Delegate, InstalledAppError, ResponseGenFn, ScopeInfo, Scopes};
+pub use net::{Connection, EndpointClass, LatencyStats, LimitMethod, RequestStats, ResponseMeta, SelfTestReport, SelfTestStep, UserAgent};
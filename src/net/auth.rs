@@ -54,9 +54,10 @@
 //! click allow, the page should redirect to a simple display of the words `Authorization successful`.
 //! Hopefully this too will be customizable one day.
 //!
-//! Installed apps, unlike scripts, require periodic reauthorization, or will expire without the
-//! possibility of refreshing if a permanent duration wasn't requested. This should be done
-//! automatically by the `net::Connection` instance.
+//! Tokens of both app types expire after some time. Scripts simply log in again with their
+//! credentials, while installed apps use their refresh token, or expire without the possibility of
+//! refreshing if a permanent duration wasn't requested. This is done automatically by the
+//! `net::Connection` instance.
 
 use rand::{self, Rng};
 use std;
@@ -75,17 +76,26 @@ use hyper::header::{self, HeaderValue};
 use hyper::server::Server;
 use hyper::service::{MakeService, Service};
 use hyper::{Body, Error as HyperError, Method, Request, Response};
+use json::{self, Value};
 use open;
 use url::{form_urlencoded, Url};
 
+use data::{CommentCooldown, DuplicateGuard};
 use errors::RedditError;
-use net::Connection;
+use net::{json_url, Connection, RatelimitState};
 
 /// Function type that is passed into OAuthApp::InstalledApp to generate response from code retrieval.
 pub type ResponseGenFn = (Fn(&Result<String, InstalledAppError>) -> Response<Body>) + Send + Sync;
 
 type CodeSender = Arc<Mutex<Option<Sender<Result<String, InstalledAppError>>>>>;
 
+/// Get the url of the endpoint access tokens are requested from. Refreshed tokens of installed
+/// apps are requested from www.reddit.com, all others from ssl.reddit.com
+pub(crate) fn token_url(refresh: bool) -> String {
+	let host = if refresh { "https://www.reddit.com" } else { "https://ssl.reddit.com" };
+	json_url(&format!("{}/api/v1/access_token", host))
+}
+
 /// Enum representing OAuth information that has been aquired from authorization. This should only be
 /// used internally within orca.
 #[derive(Debug, Clone)]
@@ -101,7 +111,9 @@ pub enum OAuth {
 		/// Password of the script user
 		password: String,
 		/// Token retrieved from script authorization
-		token: String,
+		token: RefCell<String>,
+		/// Instant when the current token expires
+		expire_instant: Cell<Option<Instant>>,
 	},
 	/// Installed app type
 	InstalledApp {
@@ -120,10 +132,27 @@ pub enum OAuth {
 }
 
 impl OAuth {
-	/// Refreshes the token (only necessary for installed app types)
+	/// Refreshes the token. Scripts log in again with their credentials, installed apps use their
+	/// refresh token.
 	pub fn refresh(&self, conn: &Connection) -> Result<(), Error> {
 		match *self {
-			OAuth::Script { .. } => Ok(()),
+			OAuth::Script {
+				ref id,
+				ref secret,
+				ref username,
+				ref password,
+				ref token,
+				ref expire_instant,
+			} => {
+				if let OAuth::Script {
+					token: new_token, expire_instant: new_expire_instant, ..
+				} = OAuth::create_script(conn, id, secret, username, password)?
+				{
+					*token.borrow_mut() = new_token.into_inner();
+					expire_instant.set(new_expire_instant.get());
+				}
+				Ok(())
+			}
 			OAuth::InstalledApp {
 				ref id,
 				redirect: ref _redirect,
@@ -136,7 +165,7 @@ impl OAuth {
 				let body = form_urlencoded::Serializer::new(String::new()).append_pair("grant_type", "refresh_token").append_pair("refresh_token", &old_refresh_token).finish();
 
 				// Request for the access token
-				let mut tokenreq = Request::builder().method(Method::POST).uri("https://www.reddit.com/api/v1/access_token/.json").body(body.into()).unwrap();
+				let mut tokenreq = Request::builder().method(Method::POST).uri(token_url(true)).body(body.into()).unwrap();
 				// httpS is important
 				tokenreq.headers_mut().insert(header::AUTHORIZATION, HeaderValue::from_str(&format!("Basic {}", { base64::encode(&format!("{}:", id)) })).unwrap());
 
@@ -148,7 +177,7 @@ impl OAuth {
 					let new_token = new_token.as_str().unwrap();
 					let _scope = scope.as_str().unwrap();
 					*token.borrow_mut() = new_token.to_string();
-					expire_instant.set(Some(Instant::now() + Duration::new(expires_in.to_string().parse::<u64>().unwrap(), 0)));
+					expire_instant.set(Some(Instant::now() + Duration::from_secs(expires_in)));
 
 					Ok(())
 				} else {
@@ -170,7 +199,7 @@ impl OAuth {
 		let body = form_urlencoded::Serializer::new(String::new()).append_pair("grant_type", "password").append_pair("username", username).append_pair("password", password).finish();
 
 		// Request for the bearer token
-		let mut tokenreq = Request::builder().method(Method::POST).uri("https://ssl.reddit.com/api/v1/access_token/.json").body(body.into()).unwrap();
+		let mut tokenreq = Request::builder().method(Method::POST).uri(token_url(false)).body(body.into()).unwrap();
 		// httpS is important
 		tokenreq.headers_mut().insert(header::AUTHORIZATION, HeaderValue::from_str(&format!("Basic {}", { base64::encode(&format!("{}:{}", id, secret)) })).unwrap());
 
@@ -179,12 +208,14 @@ impl OAuth {
 
 		if let Some(token) = response.get("access_token") {
 			let token = token.as_str().unwrap().to_string();
+			let expire_instant = response.get("expires_in").and_then(|expires_in| expires_in.as_u64()).map(|expires_in| Instant::now() + Duration::from_secs(expires_in));
 			Ok(OAuth::Script {
 				id: id.to_string(),
 				secret: secret.to_string(),
 				username: username.to_string(),
 				password: password.to_string(),
-				token,
+				token: RefCell::new(token),
+				expire_instant: Cell::new(expire_instant),
 			})
 		} else {
 			Err(RedditError::AuthError.into())
@@ -288,7 +319,7 @@ impl OAuth {
 		let body = form_urlencoded::Serializer::new(String::new()).append_pair("grant_type", "authorization_code").append_pair("code", &code).append_pair("redirect_uri", redirect).finish();
 
 		// Request for the access token
-		let mut tokenreq = Request::builder().method(Method::POST).uri("https://ssl.reddit.com/api/v1/access_token/.json").body(body.into()).unwrap();
+		let mut tokenreq = Request::builder().method(Method::POST).uri(token_url(false)).body(body.into()).unwrap();
 		// httpS is important
 		tokenreq.headers_mut().insert(header::AUTHORIZATION, HeaderValue::from_str(&format!("Basic {}", base64::encode(&format!("{}:", id)))).unwrap());
 
@@ -305,7 +336,7 @@ impl OAuth {
 				redirect: redirect.to_string(),
 				token: RefCell::new(token.to_string()),
 				refresh_token: RefCell::new(Some(refresh_token.to_string())),
-				expire_instant: Cell::new(Some(Instant::now() + Duration::new(expires_in.to_string().parse::<u64>().unwrap(), 0))),
+				expire_instant: Cell::new(Some(Instant::now() + Duration::from_secs(expires_in))),
 			})
 		} else {
 			Err(Error::from(RedditError::AuthError))
@@ -413,6 +444,33 @@ impl Scopes {
 		}
 	}
 
+	/// Get the names reddit knows the requested scopes by
+	pub fn names(&self) -> Vec<&'static str> {
+		let scopes = [
+			(self.identity, "identity"),
+			(self.edit, "edit"),
+			(self.flair, "flair"),
+			(self.history, "history"),
+			(self.modconfig, "modconfig"),
+			(self.modflair, "modflair"),
+			(self.modlog, "modlog"),
+			(self.modposts, "modposts"),
+			(self.modwiki, "modwiki"),
+			(self.mysubreddits, "mysubreddits"),
+			(self.privatemessages, "privatemessages"),
+			(self.read, "read"),
+			(self.report, "report"),
+			(self.save, "save"),
+			(self.submit, "submit"),
+			(self.subscribe, "subscribe"),
+			(self.vote, "vote"),
+			(self.wikiedit, "wikiedit"),
+			(self.wikiread, "wikiread"),
+			(self.account, "account"),
+		];
+		scopes.iter().filter(|(requested, _)| *requested).map(|(_, name)| *name).collect()
+	}
+
 	/// Convert the struct to a string representation to be sent to Reddit
 	fn to_string(&self) -> String {
 		let mut string = String::new();
@@ -481,6 +539,97 @@ impl Scopes {
 	}
 }
 
+/// The name and description of an OAuth scope as reddit presents it to users
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeInfo {
+	/// Name of the scope
+	pub name: String,
+	/// Description of what the scope allows
+	pub description: String,
+}
+
+/// Parses the response to a request for the scopes reddit supports, which maps the id of each
+/// scope to its info
+pub(crate) fn scope_infos(resp: &Value) -> Result<HashMap<String, ScopeInfo>, Error> {
+	let scopes = resp.as_object().and_then(|scopes| {
+		scopes
+			.iter()
+			.map(|(id, scope)| {
+				let info = ScopeInfo {
+					name: scope["name"].as_str()?.to_string(),
+					description: scope["description"].as_str()?.to_string(),
+				};
+				Some((id.to_string(), info))
+			})
+			.collect()
+	});
+
+	scopes.ok_or_else(|| {
+		Error::from(RedditError::BadResponse {
+			request: "scopes".to_string(),
+			response: json::to_string(resp).unwrap(),
+		})
+	})
+}
+
+/// The authorization of another account that requests can be sent as with `App::on_behalf_of`,
+/// e.g. a moderator who authorized a service with the code flow. The delegate keeps the ratelimit
+/// state and the observed comment cooldowns of its account, since reddit limits every account
+/// separately, as well as its own duplicate guard and whether it waits for comment cooldowns.
+#[derive(Debug, Clone)]
+pub struct Delegate {
+	pub(crate) auth: Option<OAuth>,
+	pub(crate) limits: RatelimitState,
+	pub(crate) comment_cooldowns: HashMap<String, CommentCooldown>,
+	pub(crate) duplicate_guard: Option<DuplicateGuard>,
+	pub(crate) wait_for_comment_cooldown: bool,
+}
+
+impl Delegate {
+	/// Creates a delegate from the authorization of an account, without a duplicate guard and
+	/// without waiting for comment cooldowns
+	pub fn new(auth: OAuth) -> Delegate {
+		Delegate {
+			auth: Some(auth),
+			limits: RatelimitState::new(),
+			comment_cooldowns: HashMap::new(),
+			duplicate_guard: None,
+			wait_for_comment_cooldown: false,
+		}
+	}
+
+	/// Sets the duplicate guard of submissions sent as the delegate, like
+	/// `App::set_duplicate_guard` does for the app
+	pub fn set_duplicate_guard(&mut self, guard: Option<DuplicateGuard>) {
+		self.duplicate_guard = guard;
+	}
+
+	/// Sets whether comments sent as the delegate wait out its comment cooldown, like
+	/// `App::set_wait_for_comment_cooldown` does for the app
+	pub fn set_wait_for_comment_cooldown(&mut self, wait: bool) {
+		self.wait_for_comment_cooldown = wait;
+	}
+
+	/// Get what orca observed of the comment cooldown of the account in a subreddit
+	/// # Arguments
+	/// * `sub` - Name of the subreddit
+	pub fn comment_cooldown(&self, sub: &str) -> Option<CommentCooldown> {
+		self.comment_cooldowns.get(&sub.to_lowercase()).cloned()
+	}
+
+	/// Get the authorization of the delegate, with the token it was last refreshed to. `None` if
+	/// the authorization was removed while requests were sent as the delegate.
+	pub fn auth(&self) -> Option<&OAuth> {
+		self.auth.as_ref()
+	}
+
+	/// Get the amount of requests reddit reported as remaining for the account in the current
+	/// ratelimit period, if any were sent
+	pub fn ratelimit_remaining(&self) -> Option<i32> {
+		self.limits.remaining
+	}
+}
+
 /// Enum that contains possible errors from a request for the OAuth Installed App type.
 #[derive(Debug, Fail, Clone)]
 pub enum InstalledAppError {
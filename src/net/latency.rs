@@ -0,0 +1,90 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use hyper::Method;
+
+/// The classes of requests whose latency `Connection::latency_stats` reports separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointClass {
+	/// Requests reading from reddit, like listings and the about of things
+	Listing,
+	/// Requests changing something, like submitting, voting and moderating
+	Mutation,
+	/// Requests for access tokens
+	Auth,
+}
+
+impl EndpointClass {
+	/// Get the class of a request from its method and path
+	pub(crate) fn of(method: &Method, path: &str) -> EndpointClass {
+		if path.starts_with("/api/v1/access_token") {
+			EndpointClass::Auth
+		} else if *method == Method::GET {
+			EndpointClass::Listing
+		} else {
+			EndpointClass::Mutation
+		}
+	}
+}
+
+/// The latency of the most recent requests of a class, returned by `Connection::latency_stats`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyStats {
+	/// Amount of requests the stats are about
+	pub count: usize,
+	/// Median duration of the requests
+	pub p50: Duration,
+	/// Duration 95 percent of the requests were faster than or as fast as
+	pub p95: Duration,
+	/// Duration of the slowest request
+	pub max: Duration,
+}
+
+/// The durations of the most recent requests of each class
+pub(crate) struct LatencyTracker {
+	window: usize,
+	durations: HashMap<EndpointClass, VecDeque<Duration>>,
+}
+
+impl LatencyTracker {
+	/// Creates a tracker keeping the durations of the last `window` requests of each class
+	pub(crate) fn new(window: usize) -> LatencyTracker {
+		LatencyTracker { window: window.max(1), durations: HashMap::new() }
+	}
+
+	/// Records the duration of a request, forgetting the oldest request of its class once the
+	/// window is full
+	pub(crate) fn record(&mut self, class: EndpointClass, duration: Duration) {
+		let durations = self.durations.entry(class).or_default();
+		if durations.len() == self.window {
+			durations.pop_front();
+		}
+		durations.push_back(duration);
+	}
+
+	/// Forgets all durations recorded
+	pub(crate) fn reset(&mut self) {
+		self.durations.clear();
+	}
+
+	/// Get the stats of each class with requests recorded
+	pub(crate) fn stats(&self) -> HashMap<EndpointClass, LatencyStats> {
+		self.durations
+			.iter()
+			.filter(|(_, durations)| !durations.is_empty())
+			.map(|(&class, durations)| {
+				let mut sorted: Vec<Duration> = durations.iter().cloned().collect();
+				sorted.sort();
+				// Nearest-rank percentiles, so they are always durations that were recorded
+				let percentile = |p: usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+				let stats = LatencyStats {
+					count: sorted.len(),
+					p50: percentile(50),
+					p95: percentile(95),
+					max: sorted[sorted.len() - 1],
+				};
+				(class, stats)
+			})
+			.collect()
+	}
+}
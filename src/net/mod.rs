@@ -6,27 +6,54 @@
 
 /// Contains all functionality for OAuth and logins
 pub mod auth;
+mod latency;
+mod useragent;
 
 use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt;
 use std::hash::BuildHasher;
+use std::io;
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::thread;
 use std::time::{Duration, Instant};
 
 use futures::Stream;
 use hyper::client::{Client, HttpConnector};
 use hyper::header::{self, HeaderValue};
-use hyper::{Body, Request, Response, Uri};
+use hyper::{Body, Method, Request, StatusCode, Uri};
 use hyper_tls::HttpsConnector;
 use json;
 use json::Value;
+use serde::Serialize;
 use tokio_core::reactor::Core;
+use url::Url;
 
-use self::auth::OAuth;
-use errors::RedditError;
+use self::auth::{Delegate, OAuth};
+pub(crate) use self::latency::LatencyTracker;
+pub use self::latency::{EndpointClass, LatencyStats};
+pub use self::useragent::UserAgent;
+use errors::{ApiErrorCode, RedditError};
 
 use failure::Error;
 
+/// The ratelimit state of an account as reddit reported it
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RatelimitState {
+	pub(crate) reqs: i32,
+	pub(crate) remaining: Option<i32>,
+	pub(crate) reset_time: Instant,
+}
+
+impl RatelimitState {
+	pub(crate) fn new() -> RatelimitState {
+		RatelimitState { reqs: 0, remaining: None, reset_time: Instant::now() }
+	}
+}
+
 /// How to ratelimit
 #[derive(Copy, Clone)]
 pub enum LimitMethod {
@@ -37,15 +64,133 @@ pub enum LimitMethod {
 	Burst,
 }
 
+/// Metadata about a response recieved from reddit. Passed to the callback set with
+/// `Connection::on_response` for every request sent.
+#[derive(Debug, Clone)]
+pub struct ResponseMeta {
+	/// Method of the request that was sent
+	pub method: Method,
+	/// Uri of the request that was sent
+	pub uri: Uri,
+	/// Status of the response
+	pub status: StatusCode,
+	/// The content type, location, cloudflare ray id and all `x-` headers (ratelimiting, tracking,
+	/// etc.) of the response, with lowercase header names
+	pub headers: HashMap<String, String>,
+	/// Size of the response body in bytes
+	pub size: usize,
+	/// Time between sending the request and recieving the whole response body
+	pub duration: Duration,
+}
+
+/// Counts of the requests sent by a connection, returned by `Connection::request_stats`
+#[derive(Debug, Clone, Default)]
+pub struct RequestStats {
+	/// Amount of requests sent, including retries and authorization
+	pub total: usize,
+	/// Amount of requests sent to each endpoint, keyed by method and path (e.g.
+	/// `GET /api/v1/me/.json`)
+	pub endpoints: HashMap<String, usize>,
+	/// Amount of requests sent to each host (e.g. `oauth.reddit.com`)
+	pub hosts: HashMap<String, usize>,
+}
+
+/// The result of a step of `App::self_test`
+#[derive(Debug, Clone)]
+pub struct SelfTestStep {
+	/// Name of the step, e.g. `authorized_read`
+	pub name: &'static str,
+	/// Why the step failed, `None` if it passed
+	pub error: Option<String>,
+	/// How long the step took, including its requests
+	pub latency: Duration,
+}
+
+impl SelfTestStep {
+	/// Runs a step, timing it
+	pub(crate) fn run<F: FnOnce() -> Result<(), Error>>(name: &'static str, step: F) -> SelfTestStep {
+		let start = Instant::now();
+		let error = step().err().map(|e| e.to_string());
+		SelfTestStep { name, error, latency: start.elapsed() }
+	}
+
+	/// Whether the step passed
+	pub fn passed(&self) -> bool {
+		self.error.is_none()
+	}
+}
+
+/// Report of `App::self_test`, which can be logged as a single line
+#[derive(Debug, Clone)]
+pub struct SelfTestReport {
+	/// The steps of the test, in the order they ran
+	pub steps: Vec<SelfTestStep>,
+	/// Requests used in the current ratelimit period, as reddit reported it
+	pub ratelimit_used: i32,
+	/// Requests remaining in the current ratelimit period, `None` if reddit didn't report it
+	pub ratelimit_remaining: Option<i32>,
+	/// Time until the ratelimit period resets, `None` if reddit didn't report it
+	pub ratelimit_reset: Option<Duration>,
+}
+
+impl SelfTestReport {
+	/// Whether every step passed
+	pub fn passed(&self) -> bool {
+		self.steps.iter().all(|step| step.passed())
+	}
+
+	/// Get the steps that failed
+	pub fn failures(&self) -> Vec<&SelfTestStep> {
+		self.steps.iter().filter(|step| !step.passed()).collect()
+	}
+}
+
+impl fmt::Display for SelfTestReport {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for step in &self.steps {
+			match step.error {
+				None => write!(f, "{} ok ({} ms), ", step.name, step.latency.as_millis())?,
+				Some(ref error) => write!(f, "{} FAILED ({} ms): {}, ", step.name, step.latency.as_millis(), error)?,
+			}
+		}
+		match (self.ratelimit_remaining, self.ratelimit_reset) {
+			(Some(remaining), Some(reset)) => write!(f, "ratelimit {} used, {} remaining, resets in {} s", self.ratelimit_used, remaining, reset.as_secs()),
+			_ => write!(f, "ratelimit unknown"),
+		}
+	}
+}
+
+/// Checks the ratelimit reddit reported for sanity. Reddit resets the ratelimit every 10 minutes
+/// and reports it with every response to `oauth.reddit.com`
+pub(crate) fn check_ratelimit(remaining: Option<i32>, reset: Option<Duration>) -> Result<(), Error> {
+	match (remaining, reset) {
+		(None, _) | (_, None) => Err(format_err!("Reddit sent no ratelimit headers")),
+		(Some(_), Some(reset)) if reset > Duration::from_secs(600) => Err(format_err!("Ratelimit resets in {} s, more than a ratelimit period", reset.as_secs())),
+		(Some(remaining), Some(_)) if remaining <= 0 => Err(format_err!("No requests remaining in the ratelimit period")),
+		_ => Ok(()),
+	}
+}
+
+type ResponseCallback = Box<dyn Fn(&ResponseMeta)>;
+
 /// A connection holder to reddit. Holds authorization info if provided, and is in charge
 /// of ratelimiting.
 pub struct Connection {
-	/// Authorization info (optional, but required for sending authorized requests)
-	pub auth: Option<auth::OAuth>,
+	/// Authorization info (optional, but required for sending authorized requests). Kept in a
+	/// `RefCell` so that it can be set and refreshed through a shared reference. The connection
+	/// is bound to the thread it was created on (the tokio core is), so a refresh can never race
+	/// with another request.
+	pub auth: RefCell<Option<auth::OAuth>>,
 	/// User agent for the client
 	pub useragent: HeaderValue,
 	/// HTTP client
 	pub client: Client<HttpsConnector<HttpConnector>, Body>,
+	/// Maximum amount of idle connections kept alive per host
+	pool_max_idle_per_host: usize,
+	/// How long idle connections are kept alive in the pool
+	pool_idle_timeout: Option<Duration>,
+	/// Whether invalid TLS certificates are accepted
+	accept_invalid_certs: bool,
 	/// Tokio core
 	core: RefCell<Core>,
 	/// How to ratelimit (burst or steady)
@@ -56,6 +201,22 @@ pub struct Connection {
 	remaining: Cell<Option<i32>>,
 	/// Time when request amount will reset
 	reset_time: Cell<Instant>,
+	/// Callback recieving the metadata of every response
+	response_callback: RefCell<Option<ResponseCallback>>,
+	/// Amount of requests sent
+	total_requests: AtomicUsize,
+	/// Amount of requests sent to each endpoint
+	endpoint_requests: Mutex<HashMap<String, usize>>,
+	/// Amount of requests sent to each host
+	host_requests: Mutex<HashMap<String, usize>>,
+	/// Whether read requests are sent without authorization even if the connection is authorized
+	anonymous_reads: Cell<bool>,
+	/// Minimum time between downloads of files that aren't part of the api
+	download_interval: Cell<Duration>,
+	/// Time the last download was sent
+	last_download: Cell<Option<Instant>>,
+	/// Durations of the most recent requests, if tracking them was enabled
+	latency: RefCell<Option<LatencyTracker>>,
 }
 
 impl Connection {
@@ -65,21 +226,100 @@ impl Connection {
 	/// * `appversion` - The version of the app
 	/// * `appauthor` - The author of the app (should be in reddit form as /u/<username>)
 	pub fn new(appname: &str, appversion: &str, appauthor: &str) -> Result<Connection, Error> {
-		let useragent = HeaderValue::from_str(&format!("linux:{}:{} (by {})", appname, appversion, appauthor)).unwrap();
+		Connection::with_user_agent(&UserAgent::new(appname, appversion).author(appauthor))
+	}
+
+	/// Creates a new connection instance to reddit with a specific user agent. Logs a warning for
+	/// each way the user agent doesn't follow reddit's guidelines.
+	/// # Arguments
+	/// * `useragent` - The user agent to identify the app with
+	pub fn with_user_agent(useragent: &UserAgent) -> Result<Connection, Error> {
+		for warning in useragent.warnings() {
+			warn!("User agent \"{}\" might be throttled by reddit: {}", useragent, warning);
+		}
+		let useragent = HeaderValue::from_str(&useragent.to_string())?;
 		let core = Core::new()?;
-		let client = Client::builder().build(HttpsConnector::new(1)?);
+		let pool_max_idle_per_host = usize::MAX;
+		let pool_idle_timeout = Some(Duration::from_secs(90));
+		let client = Connection::build_client(pool_max_idle_per_host, pool_idle_timeout, false)?;
 		Ok(Connection {
-			auth: None,
+			auth: RefCell::new(None),
 			useragent,
 			client,
+			pool_max_idle_per_host,
+			pool_idle_timeout,
+			accept_invalid_certs: false,
 			core: RefCell::new(core),
 			limit: Cell::new(LimitMethod::Steady),
 			reqs: Cell::new(0),
 			remaining: Cell::new(None),
 			reset_time: Cell::new(Instant::now()),
+			response_callback: RefCell::new(None),
+			total_requests: AtomicUsize::new(0),
+			endpoint_requests: Mutex::new(HashMap::new()),
+			host_requests: Mutex::new(HashMap::new()),
+			anonymous_reads: Cell::new(false),
+			download_interval: Cell::new(Duration::from_millis(250)),
+			last_download: Cell::new(None),
+			latency: RefCell::new(None),
 		})
 	}
 
+	/// Get the user agent sent with every request
+	pub fn user_agent(&self) -> &str {
+		self.useragent.to_str().unwrap_or("")
+	}
+
+	fn build_client(max_idle: usize, idle_timeout: Option<Duration>, accept_invalid_certs: bool) -> Result<Client<HttpsConnector<HttpConnector>, Body>, Error> {
+		Ok(Client::builder().max_idle_per_host(max_idle).keep_alive_timeout(idle_timeout).build(Connection::https_connector(accept_invalid_certs)?))
+	}
+
+	#[cfg(not(feature = "dangerous"))]
+	fn https_connector(_accept_invalid_certs: bool) -> Result<HttpsConnector<HttpConnector>, Error> {
+		Ok(HttpsConnector::new(1)?)
+	}
+
+	#[cfg(feature = "dangerous")]
+	fn https_connector(accept_invalid_certs: bool) -> Result<HttpsConnector<HttpConnector>, Error> {
+		let tls = native_tls::TlsConnector::builder().danger_accept_invalid_certs(accept_invalid_certs).build()?;
+		let mut http = HttpConnector::new(1);
+		http.enforce_http(false);
+		Ok(HttpsConnector::from((http, tls)))
+	}
+
+	/// Sets the maximum amount of idle connections to keep alive per host. Almost every request
+	/// goes to the same couple of reddit hosts, so a high-throughput bot loses nothing by keeping
+	/// this small (2-4 is plenty); set it to 0 to disable connection reuse entirely. Defaults to
+	/// no limit.
+	pub fn pool_max_idle_per_host(&mut self, max: usize) -> Result<(), Error> {
+		self.pool_max_idle_per_host = max;
+		self.client = Connection::build_client(self.pool_max_idle_per_host, self.pool_idle_timeout, self.accept_invalid_certs)?;
+		Ok(())
+	}
+
+	/// Sets how long an idle connection is kept alive in the pool before being closed. A bot that
+	/// sends requests steadily should keep this above its average interval between requests so
+	/// connections can be reused; 60-90 seconds works well. Defaults to 90 seconds.
+	pub fn pool_idle_timeout(&mut self, timeout: Duration) -> Result<(), Error> {
+		self.pool_idle_timeout = Some(timeout);
+		self.client = Connection::build_client(self.pool_max_idle_per_host, self.pool_idle_timeout, self.accept_invalid_certs)?;
+		Ok(())
+	}
+
+	/// Sets whether TLS certificates that are invalid, expired or for another host are accepted.
+	/// This is dangerous: anyone between the app and reddit can read and change the traffic,
+	/// including passwords and tokens. Only use it for testing, e.g. against a local proxy or a
+	/// mock server with a self-signed certificate. Requires the `dangerous` feature.
+	#[cfg(feature = "dangerous")]
+	pub fn danger_accept_invalid_certs(&mut self, accept: bool) -> Result<(), Error> {
+		if accept {
+			warn!("Accepting invalid TLS certificates, connections to reddit are not secure");
+		}
+		self.accept_invalid_certs = accept;
+		self.client = Connection::build_client(self.pool_max_idle_per_host, self.pool_idle_timeout, self.accept_invalid_certs)?;
+		Ok(())
+	}
+
 	/// Send a request to reddit. This is where ratelimiting happens, as well as setting the
 	/// user agent.
 	pub fn run_request(&self, mut req: Request<Body>) -> Result<Value, Error> {
@@ -117,9 +357,16 @@ impl Connection {
 		// Log the request
 		trace!("Sending request {:?}", req);
 
+		let method = req.method().clone();
+		let uri = req.uri().clone();
+		let path = uri.path().to_string();
+		let authorized = req.headers().contains_key(header::AUTHORIZATION);
+		let start = Instant::now();
+		self.record_request(&method, &uri);
+
 		// Execute the request!
 		let response = self.client.request(req);
-		let response = self.core.borrow_mut().run(response)?;
+		let response = self.core.borrow_mut().run(response).map_err(|e| network_error(&req_str, e))?;
 
 		// Update values from response ratelimiting headers
 		if let Some(reqs_used) = response.headers().get("x-ratelimit-used") {
@@ -140,21 +387,37 @@ impl Connection {
 		trace!("Ratelimiting:\n\tRequests used: {:?}\n\tRequests remaining: {:?}\n\tReset time: {:?}\n\tNow: {:?}", self.reqs.get(), self.remaining.get(), self.reset_time.get(), Instant::now());
 
 		let response_str = format!("{:?}", response);
-		let get_body = |response: Response<Body>| -> Result<String, Error> {
-			let body = self.core.borrow_mut().run(response.into_body().concat2())?;
-			let body: String = String::from_utf8_lossy(&body).into();
-			Ok(body)
-		};
+		let status = response.status();
+		let headers = response_headers(response.headers());
+		let body = self.core.borrow_mut().run(response.into_body().concat2()).map_err(|e| network_error(&req_str, e))?;
+		let duration = Instant::now() - start;
 
-		if !response.status().is_success() {
+		if let Some(ref mut latency) = *self.latency.borrow_mut() {
+			latency.record(EndpointClass::of(&method, &path), duration);
+		}
+		if let Some(ref callback) = *self.response_callback.borrow() {
+			callback(&ResponseMeta {
+				method,
+				uri,
+				status,
+				headers: headers.clone(),
+				size: body.len(),
+				duration,
+			});
+		}
+
+		let body: String = String::from_utf8_lossy(&body).into();
+
+		if !status.is_success() {
 			error!("Got error response: {}", response_str);
-			return Err(Error::from(RedditError::BadRequest {
-				request: req_str,
-				response: format!("Reponse: {}\nResponse body: {:?}", response_str, get_body(response)?),
-			}));
+			return Err(Error::from(error_from_response(&req_str, &path, authorized, status, &headers, &response_str, &body)));
 		}
 
-		let body = get_body(response)?;
+		// Some endpoints (mostly PUT and DELETE ones) respond with no content at all
+		if body.trim().is_empty() {
+			trace!("Got successful empty response: {:?}", response_str);
+			return Ok(Value::Null);
+		}
 
 		match json::from_str(&body) {
 			Ok(r) => {
@@ -165,56 +428,231 @@ impl Connection {
 		}
 	}
 
-	/// Send a request to reddit with authorization headers
+	/// Download a file that isn't part of the api, like an image on reddit's CDN. Downloads are
+	/// not ratelimited like requests to the api, since they don't count against its ratelimit, but
+	/// are paced by the interval set with `set_download_interval`.
+	/// # Arguments
+	/// * `url` - Url of the file
+	/// # Returns
+	/// The content of the file
+	pub fn download(&self, url: &str) -> Result<Vec<u8>, Error> {
+		if let Some(last) = self.last_download.get() {
+			if let Some(wait) = self.download_interval.get().checked_sub(last.elapsed()) {
+				thread::sleep(wait);
+			}
+		}
+
+		let mut req = Request::get(url).body(Body::empty())?;
+		req.headers_mut().insert(header::USER_AGENT, self.useragent.clone());
+		let req_str = format!("{:?}", req);
+		trace!("Sending download {:?}", req);
+
+		let method = req.method().clone();
+		let uri = req.uri().clone();
+		let path = uri.path().to_string();
+		let start = Instant::now();
+		self.record_request(&method, &uri);
+		self.last_download.set(Some(start));
+
+		let response = self.client.request(req);
+		let response = self.core.borrow_mut().run(response).map_err(|e| network_error(&req_str, e))?;
+		let response_str = format!("{:?}", response);
+		let status = response.status();
+		let headers = response_headers(response.headers());
+		let body = self.core.borrow_mut().run(response.into_body().concat2()).map_err(|e| network_error(&req_str, e))?;
+
+		if let Some(ref callback) = *self.response_callback.borrow() {
+			callback(&ResponseMeta {
+				method,
+				uri,
+				status,
+				headers: headers.clone(),
+				size: body.len(),
+				duration: Instant::now() - start,
+			});
+		}
+
+		if !status.is_success() {
+			error!("Got error response to download: {}", response_str);
+			return Err(Error::from(error_from_response(&req_str, &path, false, status, &headers, &response_str, &String::from_utf8_lossy(&body))));
+		}
+
+		Ok(body.to_vec())
+	}
+
+	/// Sets the minimum time between downloads sent with `download`. Defaults to 250 milliseconds
+	pub fn set_download_interval(&self, interval: Duration) {
+		self.download_interval.set(interval);
+	}
+
+	/// Get the amount of requests reddit reported as remaining in the current ratelimit period,
+	/// `None` before the first response
+	pub fn ratelimit_remaining(&self) -> Option<i32> {
+		self.remaining.get()
+	}
+
+	/// The ratelimit state reddit reported last
+	pub(crate) fn ratelimit_state(&self) -> RatelimitState {
+		RatelimitState {
+			reqs: self.reqs.get(),
+			remaining: self.remaining.get(),
+			reset_time: self.reset_time.get(),
+		}
+	}
+
+	/// Exchanges the authorization and ratelimit state of the connection with the ones of a
+	/// delegate. Exchanging them again restores both.
+	pub(crate) fn swap_delegate(&self, delegate: &mut Delegate) {
+		mem::swap(&mut *self.auth.borrow_mut(), &mut delegate.auth);
+		let limits = self.ratelimit_state();
+		self.reqs.set(delegate.limits.reqs);
+		self.remaining.set(delegate.limits.remaining);
+		self.reset_time.set(delegate.limits.reset_time);
+		delegate.limits = limits;
+	}
+
+	/// Sets whether requests that only read, like loading posts, are sent to `www.reddit.com`
+	/// without authorization even when the connection is authorized. By default they are sent to
+	/// `oauth.reddit.com` once the connection is authorized, which has higher ratelimits and
+	/// includes the state of the user, like `likes` and `saved`.
+	pub fn set_anonymous_reads(&self, anonymous: bool) {
+		self.anonymous_reads.set(anonymous);
+	}
+
+	/// The host read requests are sent to, which is `oauth.reddit.com` if the connection is
+	/// authorized and `www.reddit.com` otherwise
+	pub(crate) fn read_host(&self) -> &'static str {
+		if self.auth.borrow().is_some() && !self.anonymous_reads.get() {
+			"https://oauth.reddit.com"
+		} else {
+			"https://www.reddit.com"
+		}
+	}
+
+	/// Send a read request, with authorization if it goes to `oauth.reddit.com`
+	pub(crate) fn run_read_request(&self, req: Request<Body>) -> Result<Value, Error> {
+		if req.uri().host() == Some("oauth.reddit.com") {
+			self.run_auth_request(req)
+		} else {
+			self.run_request(req)
+		}
+	}
+
+	/// Send a request to reddit with authorization headers. If the token has expired it is
+	/// refreshed first, so only the first request after an expiration refreshes it.
 	pub fn run_auth_request(&self, mut req: Request<Body>) -> Result<Value, Error> {
-		if let Some(ref auth) = self.auth {
+		let token = if let Some(ref auth) = *self.auth.borrow() {
 			let req_str = format!("{:?}", req);
-			req.headers_mut().insert(
-				header::AUTHORIZATION,
-				HeaderValue::from_str(&format!(
-					"Bearer {}",
-					match *auth {
-						OAuth::Script {
-							id: ref _id,
-							secret: ref _secret,
-							username: ref _username,
-							password: ref _password,
-							ref token,
-						} => token.to_string(),
-						OAuth::InstalledApp {
-							id: ref _id,
-							redirect: ref _redirect,
-							ref token,
-							ref refresh_token,
-							ref expire_instant,
-						} => {
-							// If the token can expire and we are able to refresh it
-							if let (Some(_refresh_token), Some(expire_instant)) = (refresh_token.borrow().clone(), expire_instant.get()) {
-								// If the token's expired, refresh it
-								if Instant::now() > expire_instant {
-									auth.refresh(self)?;
-								}
-								token.borrow().to_string()
-							} else if let Some(expire_instant) = expire_instant.get() {
-								if Instant::now() > expire_instant {
-									return Err(Error::from(RedditError::Forbidden { request: format!("{:?}", req_str) }));
-								} else {
-									token.borrow().to_string()
-								}
-							} else {
-								token.borrow().to_string()
-							}
+			match *auth {
+				OAuth::Script { ref token, ref expire_instant, .. } => {
+					// Scripts can always log in again once the token's expired
+					if let Some(expire_instant) = expire_instant.get() {
+						if Instant::now() > expire_instant {
+							auth.refresh(self)?;
 						}
 					}
-				))
-				.unwrap(),
-			);
-			self.run_request(req)
+					token.borrow().to_string()
+				}
+				OAuth::InstalledApp { ref token, ref refresh_token, ref expire_instant, .. } => {
+					// If the token can expire and we are able to refresh it
+					if let (Some(_refresh_token), Some(expire_instant)) = (refresh_token.borrow().clone(), expire_instant.get()) {
+						// If the token's expired, refresh it
+						if Instant::now() > expire_instant {
+							auth.refresh(self)?;
+						}
+						token.borrow().to_string()
+					} else if let Some(expire_instant) = expire_instant.get() {
+						if Instant::now() > expire_instant {
+							return Err(Error::from(RedditError::Forbidden { request: format!("{:?}", req_str) }));
+						} else {
+							token.borrow().to_string()
+						}
+					} else {
+						token.borrow().to_string()
+					}
+				}
+			}
 		} else {
-			Err(Error::from(RedditError::Forbidden { request: format!("{:?}", req) }))
+			return Err(Error::from(RedditError::Forbidden { request: format!("{:?}", req) }));
+		};
+
+		req.headers_mut().insert(header::AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap());
+		self.run_request(req)
+	}
+
+	/// Send a request with any method and an optional json body to reddit with authorization
+	/// headers. Use `run_auth_request` for endpoints that take form encoded bodies.
+	/// # Arguments
+	/// * `method` - The method of the request (e.g. `PUT`, `PATCH` or `DELETE`)
+	/// * `url` - The url to send the request to
+	/// * `body` - Optional body that is serialized as json
+	/// # Returns
+	/// The json response, or `Value::Null` if reddit responded with no content
+	pub fn run_auth_request_with<B: Serialize>(&self, method: Method, url: Url, body: Option<&B>) -> Result<Value, Error> {
+		let mut req = Request::builder();
+		req.method(method).uri(url.as_str());
+		let req = match body {
+			Some(body) => req.header(header::CONTENT_TYPE, "application/json").body(Body::from(json::to_string(body)?)),
+			None => req.body(Body::empty()),
+		}?;
+
+		self.run_auth_request(req)
+	}
+
+	/// Get the amount of requests sent by this connection, in total, to each endpoint and to each
+	/// host
+	pub fn request_stats(&self) -> RequestStats {
+		RequestStats {
+			total: self.total_requests.load(Ordering::Relaxed),
+			endpoints: self.endpoint_requests.lock().map(|endpoints| endpoints.clone()).unwrap_or_default(),
+			hosts: self.host_requests.lock().map(|hosts| hosts.clone()).unwrap_or_default(),
+		}
+	}
+
+	fn record_request(&self, method: &Method, uri: &Uri) {
+		self.total_requests.fetch_add(1, Ordering::Relaxed);
+		if let Ok(mut endpoints) = self.endpoint_requests.lock() {
+			*endpoints.entry(format!("{} {}", method, uri.path())).or_insert(0) += 1;
+		}
+		if let Ok(mut hosts) = self.host_requests.lock() {
+			*hosts.entry(uri.host().unwrap_or_default().to_string()).or_insert(0) += 1;
 		}
 	}
 
+	/// Starts tracking the duration of requests, e.g. to compare the health of connections from
+	/// different regions. Tracking is off by default. Starting it again forgets the durations
+	/// tracked so far
+	/// # Arguments
+	/// * `window` - Amount of the most recent requests of each class the stats are about
+	pub fn track_latency(&self, window: usize) {
+		*self.latency.borrow_mut() = Some(LatencyTracker::new(window));
+	}
+
+	/// Get the latency of the most recent requests of each class. Classes without requests since
+	/// tracking started or was reset are left out
+	pub fn latency_stats(&self) -> HashMap<EndpointClass, LatencyStats> {
+		self.latency.borrow().as_ref().map(LatencyTracker::stats).unwrap_or_default()
+	}
+
+	/// Forgets the durations tracked so far, without stopping tracking
+	pub fn reset_latency_stats(&self) {
+		if let Some(ref mut latency) = *self.latency.borrow_mut() {
+			latency.reset();
+		}
+	}
+
+	/// Sets a callback that recieves the metadata (status, headers, size and duration) of every
+	/// response recieved by this connection, replacing any previously set callback. Useful for
+	/// exporting metrics. The callback must not set a new callback itself.
+	pub fn on_response<F: Fn(&ResponseMeta) + 'static>(&self, callback: F) {
+		*self.response_callback.borrow_mut() = Some(Box::new(callback));
+	}
+
+	/// Removes the callback set with `on_response`
+	pub fn clear_response_callback(&self) {
+		*self.response_callback.borrow_mut() = None;
+	}
+
 	/// Set's the ratelimiting method
 	pub fn set_limit(&self, limit: LimitMethod) {
 		self.limit.set(limit);
@@ -226,9 +664,249 @@ impl Connection {
 	}
 }
 
+/// The headers of a response that are kept for errors and the response callback
+fn response_headers(headers: &header::HeaderMap) -> HashMap<String, String> {
+	headers
+		.iter()
+		.filter(|(name, _)| name.as_str().starts_with("x-") || name.as_str() == "cf-ray" || *name == header::CONTENT_TYPE || *name == header::LOCATION)
+		.filter_map(|(name, value)| value.to_str().ok().map(|value| (name.as_str().to_string(), value.to_string())))
+		.collect()
+}
+
 /// Creates a url with encoded parameters from hashmap. Right now it's kinda hacky
 pub fn uri_params_from_map<S: BuildHasher>(url: &str, map: &HashMap<&str, &str, S>) -> Result<Uri, Error> {
-	use url::Url;
-
 	Ok(Url::parse_with_params(url, map)?.to_string().parse()?)
 }
+
+/// Adds the `.json` suffix to the url of an endpoint, giving the same url whether or not the url
+/// already ends in a slash or the suffix, e.g. `/r/rust.json` for both `/r/rust` and `/r/rust/`.
+/// The url of the front page, which has an empty path, becomes `/.json`. Every endpoint takes the
+/// suffix, including the ones that only respond with json, like modmail and widgets, since reddit
+/// strips it before routing the request
+pub(crate) fn json_url(url: &str) -> String {
+	let base = url.trim_end_matches(".json").trim_end_matches('/');
+	let has_path = base.split_once("://").is_some_and(|(_, rest)| rest.contains('/'));
+	if has_path {
+		format!("{}.json", base)
+	} else {
+		format!("{}/.json", base)
+	}
+}
+
+/// Returns the name of the subreddit if the path is of a request about a whole subreddit (its
+/// listings or info) rather than a single post in it
+fn subreddit_from_path(path: &str) -> Option<&str> {
+	if !path.starts_with("/r/") || path.contains("/comments/") {
+		return None;
+	}
+	path[3..].split('/').next().map(|sub| sub.trim_end_matches(".json")).filter(|sub| !sub.is_empty())
+}
+
+/// Gets the subreddit a redirect leads to from the location of the redirect, which can be relative
+pub(crate) fn subreddit_from_location(location: &str) -> Option<String> {
+	let url = Url::parse("https://www.reddit.com").ok()?.join(location).ok()?;
+	subreddit_from_path(url.path()).map(|sub| sub.to_string())
+}
+
+/// Whether a redirect leads to the login page, which reddit redirects to for some endpoints that
+/// need authorization instead of responding with an error
+pub(crate) fn is_login_location(location: &str) -> bool {
+	let url = match Url::parse("https://www.reddit.com").ok().and_then(|base| base.join(location).ok()) {
+		Some(url) => url,
+		None => return false,
+	};
+	let path = url.path().trim_end_matches('/');
+	path == "/login" || path == "/account/login" || url.host_str() == Some("accounts.reddit.com")
+}
+
+/// The reasons reddit's api gives for refusing requests of suspended accounts
+const SUSPENDED_REASONS: &[&str] = &["USER_SUSPENDED", "ACCOUNT_SUSPENDED", "suspended"];
+
+/// Creates the error for an unsuccessful response, telling apart the special cases reddit reports
+/// in the status and body of the response
+/// # Arguments
+/// * `authorized` - Whether the request was sent with authorization, since only those can fail
+/// because the account is suspended
+pub(crate) fn error_from_response(request: &str, path: &str, authorized: bool, status: StatusCode, headers: &HashMap<String, String>, response: &str, body: &str) -> RedditError {
+	let reason = json::from_str::<Value>(body).ok().and_then(|body| body["reason"].as_str().map(|reason| reason.to_string()));
+
+	if status == StatusCode::FORBIDDEN {
+		// Only the reason is checked, since the body of a 403 can be content that mentions suspensions
+		if authorized && reason.as_deref().is_some_and(|reason| SUSPENDED_REASONS.contains(&reason)) {
+			return RedditError::AccountSuspended;
+		}
+		if let Ok(json) = json::from_str::<Value>(body) {
+			if let Some(error) = ban_from_codes(&api_error_codes(&json), subreddit_from_path(path)) {
+				return error;
+			}
+		}
+	}
+
+	if let Some(sub) = subreddit_from_path(path) {
+		let sub = sub.to_string();
+		// Reddit redirects requests for subreddits that don't exist to a search for the name
+		let to_search = headers.get("location").is_some_and(|location| location.contains("/subreddits/search"));
+		match (status.as_u16(), reason.as_deref()) {
+			(403, Some("private")) => return RedditError::SubredditPrivate { sub },
+			(404, Some("banned")) => return RedditError::SubredditBanned { sub },
+			(404, None) => return RedditError::SubredditNotFound { sub },
+			(302, _) if to_search => return RedditError::SubredditNotFound { sub },
+			_ => {}
+		}
+	}
+
+	// Anonymous requests for nsfw content are redirected to a confirmation page
+	let to_gate = headers.get("location").is_some_and(|location| location.contains("/over18"));
+	if (status.is_redirection() && to_gate) || reason.as_deref() == Some("over18") {
+		return RedditError::NsfwGate { request: path.to_string() };
+	}
+
+	if status.is_redirection() {
+		if let Some(location) = headers.get("location") {
+			if is_login_location(location) {
+				return RedditError::NotAuthenticated { request: request.to_string() };
+			}
+			return RedditError::Redirected {
+				request: request.to_string(),
+				location: location.clone(),
+			};
+		}
+	}
+
+	// Errors from reddit itself are json, while cloudflare responds with html pages when blocking
+	// requests. Other html pages, like the ones of reddit's own servers, aren't blocks.
+	let is_json = headers.get("content-type").is_none_or(|content_type| content_type.contains("json"));
+	let from_cloudflare = headers.contains_key("cf-ray") || body.to_lowercase().contains("cloudflare");
+	if status.as_u16() >= 400 && !is_json && from_cloudflare {
+		return RedditError::Blocked { cf_ray: headers.get("cf-ray").cloned() };
+	}
+
+	if status == StatusCode::FORBIDDEN {
+		return RedditError::Forbidden { request: request.to_string() };
+	}
+
+	RedditError::BadRequest {
+		request: request.to_string(),
+		response: format!("Reponse: {}\nResponse body: {:?}", response, body),
+		status: Some(status.as_u16()),
+	}
+}
+
+/// Turns an error of sending a request or recieving its response into a `RedditError` telling
+/// timeouts, failed connections and failed host name lookups apart. Other errors are kept as
+/// they are
+fn network_error(request: &str, error: hyper::Error) -> Error {
+	let mut io_error = None;
+	let mut source = error.source();
+	while let Some(cause) = source {
+		if let Some(cause) = cause.downcast_ref::<io::Error>() {
+			io_error = Some(cause);
+			break;
+		}
+		source = cause.source();
+	}
+
+	match classify_network_error(request, error.is_connect(), io_error) {
+		Some(e) => Error::from(e),
+		None => Error::from(error),
+	}
+}
+
+/// Classifies a network error from whether it happened while connecting and the io error that
+/// caused it
+pub(crate) fn classify_network_error(request: &str, is_connect: bool, io_error: Option<&io::Error>) -> Option<RedditError> {
+	let request = request.to_string();
+	let error = io_error.map(|e| e.to_string()).unwrap_or_else(|| "connection error".to_string());
+	match io_error.map(io::Error::kind) {
+		Some(io::ErrorKind::TimedOut) => Some(RedditError::Timeout { request }),
+		// The resolver reports failed lookups with the message of getaddrinfo
+		_ if is_connect && (error.contains("lookup address") || error.contains("resolve") || error.contains("not known")) => Some(RedditError::Dns { request, error }),
+		Some(io::ErrorKind::ConnectionRefused) | Some(io::ErrorKind::ConnectionReset) | Some(io::ErrorKind::ConnectionAborted) | Some(io::ErrorKind::NotConnected) | Some(io::ErrorKind::BrokenPipe) => Some(RedditError::ConnectionFailed { request, error }),
+		_ if is_connect => Some(RedditError::ConnectionFailed { request, error }),
+		_ => None,
+	}
+}
+
+/// Checks a response from an endpoint using `api_type=json` for errors reported inside of the
+/// json envelope (`{"json": {"errors": [...]}}`), which reddit sends with a successful status
+pub(crate) fn check_json_errors(request: &str, resp: &Value) -> Result<(), Error> {
+	if let Some(error) = ban_from_codes(&api_error_codes(resp), None) {
+		return Err(Error::from(error));
+	}
+	if let Some(wait) = ratelimit_wait(resp) {
+		return Err(Error::from(RedditError::RateLimited { wait }));
+	}
+	if let Some(errors) = resp["json"]["errors"].as_array() {
+		let known = errors.iter().find_map(|error| error[0].as_str().and_then(ApiErrorCode::from_code).map(|code| (code, error[1].as_str().unwrap_or(""))));
+		if let Some((code, message)) = known {
+			return Err(Error::from(RedditError::ApiError {
+				request: request.to_string(),
+				code,
+				message: message.to_string(),
+			}));
+		}
+	}
+
+	match resp["json"]["errors"].as_array() {
+		Some(errors) if !errors.is_empty() => Err(Error::from(RedditError::BadRequest {
+			request: request.to_string(),
+			response: json::to_string(&resp["json"]["errors"]).unwrap(),
+			status: None,
+		})),
+		_ => Ok(()),
+	}
+}
+
+/// Collects the error codes of a response, which the json api sends as `reason` or `error` and
+/// the form api sends as the first element of each entry of `json.errors`
+fn api_error_codes(resp: &Value) -> Vec<&str> {
+	let mut codes: Vec<&str> = resp["reason"].as_str().into_iter().chain(resp["error"].as_str()).collect();
+	if let Some(errors) = resp["json"]["errors"].as_array() {
+		codes.extend(errors.iter().filter_map(|error| error[0].as_str()));
+	}
+	codes
+}
+
+/// Creates the error for codes reporting that the account may not post in a subreddit.
+/// `USER_BLOCKED` is only treated as a ban when the request was for a subreddit, since it is also
+/// sent when messaging users that blocked the account.
+fn ban_from_codes(codes: &[&str], sub: Option<&str>) -> Option<RedditError> {
+	let not_allowed = codes.contains(&"SUBREDDIT_NOTALLOWED");
+	if not_allowed || (sub.is_some() && codes.contains(&"USER_BLOCKED")) {
+		Some(RedditError::BannedFromSubreddit {
+			sub: sub.map(|sub| sub.to_string()),
+			code: if not_allowed { Some(ApiErrorCode::SubredditNotAllowed) } else { None },
+		})
+	} else {
+		None
+	}
+}
+
+/// Gets how long to wait from a `RATELIMIT` error, using the `ratelimit` seconds if reddit sent
+/// them and the message ("try again in 5 minutes.") otherwise
+fn ratelimit_wait(resp: &Value) -> Option<Duration> {
+	let errors = resp["json"]["errors"].as_array()?;
+	let error = errors.iter().find(|error| error[0] == "RATELIMIT")?;
+	if let Some(secs) = resp["json"]["ratelimit"].as_f64() {
+		return Some(Duration::from_millis((secs * 1000.0).ceil() as u64));
+	}
+
+	let message = error[1].as_str().unwrap_or("");
+	let words: Vec<&str> = message.trim_end_matches('.').split_whitespace().collect();
+	let secs = words.windows(2).find_map(|pair| {
+		let amount = pair[0].parse::<u64>().ok()?;
+		if pair[1].starts_with("millisecond") {
+			Some(amount / 1000 + 1)
+		} else if pair[1].starts_with("second") {
+			Some(amount)
+		} else if pair[1].starts_with("minute") {
+			Some(amount * 60)
+		} else if pair[1].starts_with("hour") {
+			Some(amount * 60 * 60)
+		} else {
+			None
+		}
+	});
+	// Reddit's cooldowns are rarely longer than ten minutes
+	Some(Duration::from_secs(secs.unwrap_or(10 * 60)))
+}
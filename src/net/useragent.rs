@@ -0,0 +1,101 @@
+use std::env;
+use std::fmt;
+
+/// App names that don't identify an app, which reddit throttles heavily
+const GENERIC_NAMES: &[&str] = &["bot", "app", "client", "script", "test", "reddit", "redditbot", "mybot", "myapp", "python", "rust", "curl"];
+
+/// Builder for a user agent in the form reddit requires,
+/// `<platform>:<app id>:<version> (by /u/<username>)`.
+///
+/// Reddit throttles requests with generic or undescriptive user agents, so `warnings` reports
+/// user agents that look like that. A `Connection` logs these warnings when it is created.
+#[derive(Debug, Clone)]
+pub struct UserAgent {
+	platform: String,
+	app_id: String,
+	version: String,
+	author: Option<String>,
+	custom: Option<String>,
+}
+
+impl UserAgent {
+	/// Creates a user agent for an app. The platform defaults to the operating system this is
+	/// compiled for.
+	/// # Arguments
+	/// * `app_id` - Unique name of the app
+	/// * `version` - Version of the app
+	pub fn new(app_id: &str, version: &str) -> UserAgent {
+		UserAgent {
+			platform: env::consts::OS.to_string(),
+			app_id: app_id.to_string(),
+			version: version.to_string(),
+			author: None,
+			custom: None,
+		}
+	}
+
+	/// Uses a user agent exactly as given instead of building one. This is not validated.
+	pub fn custom(useragent: &str) -> UserAgent {
+		UserAgent {
+			platform: String::new(),
+			app_id: String::new(),
+			version: String::new(),
+			author: None,
+			custom: Some(useragent.to_string()),
+		}
+	}
+
+	/// Sets the platform the app runs on
+	pub fn platform(mut self, platform: &str) -> UserAgent {
+		self.platform = platform.to_string();
+		self
+	}
+
+	/// Sets the reddit user to contact about the app. Can be given as `name`, `u/name` or `/u/name`.
+	pub fn author(mut self, username: &str) -> UserAgent {
+		let name = username.trim().trim_start_matches('/').trim_start_matches("u/");
+		self.author = Some(format!("/u/{}", name));
+		self
+	}
+
+	/// Reports the ways this user agent doesn't follow reddit's guidelines. Custom user agents are
+	/// never reported.
+	pub fn warnings(&self) -> Vec<String> {
+		let mut warnings = Vec::new();
+		if self.custom.is_some() {
+			return warnings;
+		}
+
+		let app_id = self.app_id.trim().to_lowercase();
+		if app_id.is_empty() {
+			warnings.push("the app id is empty".to_string());
+		} else if GENERIC_NAMES.contains(&app_id.as_str()) {
+			warnings.push(format!("the app id \"{}\" is too generic to identify the app", self.app_id));
+		}
+		if self.version.trim().is_empty() {
+			warnings.push("the version is empty".to_string());
+		}
+		if self.platform.trim().is_empty() {
+			warnings.push("the platform is empty".to_string());
+		}
+		if self.author.as_ref().is_none_or(|author| author == "/u/") {
+			warnings.push("there is no user to contact about the app".to_string());
+		}
+
+		warnings
+	}
+}
+
+impl fmt::Display for UserAgent {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if let Some(ref custom) = self.custom {
+			return write!(f, "{}", custom);
+		}
+
+		write!(f, "{}:{}:{}", self.platform, self.app_id, self.version)?;
+		if let Some(ref author) = self.author {
+			write!(f, " (by {})", author)?;
+		}
+		Ok(())
+	}
+}
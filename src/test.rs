@@ -2,7 +2,7 @@ extern crate env_logger;
 
 use std::sync::{Arc, Once, ONCE_INIT};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use hyper::{Body, Response};
 use log;
@@ -44,7 +44,7 @@ fn source_env() -> Result<(String, String, String, String, String, String), ()>
 
 fn init_reddit() -> App {
 	init_logging();
-	let mut reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
 	let (username, password, script_id, secret, installed_id, redirect) = source_env().unwrap();
 	reddit.authorize_script(&script_id, &secret, &username, &password).unwrap();
 
@@ -56,12 +56,33 @@ fn get_posts() {
 	init_reddit().get_posts("unixporn", Sort::Top(SortTime::All)).unwrap();
 }
 
+#[test(response_meta)]
+fn response_meta() {
+	use std::cell::RefCell;
+	use std::rc::Rc;
+
+	let reddit = init_reddit();
+	let metas: Rc<RefCell<Vec<ResponseMeta>>> = Rc::new(RefCell::new(Vec::new()));
+	let metas_clone = Rc::clone(&metas);
+	reddit.conn().on_response(move |meta| metas_clone.borrow_mut().push(meta.clone()));
+
+	reddit.get_self().unwrap();
+	let metas = metas.borrow();
+	assert_eq!(metas.len(), 1);
+	let stats = reddit.conn().request_stats();
+	assert_eq!(stats.endpoints.get("GET /api/v1/me.json"), Some(&1));
+	assert!(stats.total >= 2);
+	assert!(metas[0].status.is_success());
+	assert!(metas[0].headers.contains_key("x-ratelimit-remaining"));
+	assert!(metas[0].size > 0);
+}
+
 // Conflicts with the force_refresh test
 //#[test(installed_auth)]
 fn installed_app_auth() {
 	init_logging();
 	let (username, password, script_id, secret, installed_id, redirect) = source_env().unwrap();
-	let mut reddit = App::new("Orca Test Installed App", "v0.3.0", "/u/IntrepidPig").unwrap();
+	let reddit = App::new("Orca Test Installed App", "v0.3.0", "/u/IntrepidPig").unwrap();
 	use net::auth::InstalledAppError;
 	let response_gen: Arc<ResponseGenFn> = Arc::new(|res: &Result<String, InstalledAppError>| -> Response<Body> {
 		match res {
@@ -83,11 +104,47 @@ fn post_sort() {
 	assert_eq!(Sort::Top(SortTime::All).param(), &[("sort", "top"), ("t", "all")])
 }
 
+#[test(comment_sort)]
+fn comment_sort() {
+	assert_eq!(CommentSort::from_name("qa"), Some(CommentSort::Qa));
+	assert_eq!(CommentSort::from_name(""), None);
+	assert_eq!(CommentSort::Confidence.param(), ("sort", "confidence"));
+}
+
+#[test(best_sort)]
+fn best_sort() {
+	init_logging();
+	assert_eq!(Sort::Best.path(), "best");
+	assert_eq!(Sort::Best.param(), &[("sort", "best")]);
+
+	// Rejected without sending a request
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	match reddit.get_posts("unixporn", Sort::Best).unwrap_err().downcast::<RedditError>() {
+		Ok(RedditError::BadRequest { .. }) => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+}
+
 #[test(auth)]
 fn test_auth() {
 	init_reddit().get_self().unwrap();
 }
 
+#[test(json_methods)]
+fn json_methods() {
+	use hyper::Method;
+	use std::collections::HashMap;
+	use url::Url;
+
+	let reddit = init_reddit();
+	let url = Url::parse("https://oauth.reddit.com/api/v1/me/friends/IntrepidPig").unwrap();
+	let mut body = HashMap::new();
+	body.insert("name", "IntrepidPig");
+
+	reddit.conn().run_auth_request_with(Method::PUT, url.clone(), Some(&body)).unwrap();
+	reddit.conn().run_auth_request_with::<()>(Method::DELETE, url, None).unwrap();
+}
+
 #[test(selfuser)]
 fn self_info() {
 	let reddit = init_reddit();
@@ -96,6 +153,17 @@ fn self_info() {
 	info!("Me:\n{}", json::to_string_pretty(&user).unwrap());
 }
 
+#[test(saved)]
+fn saved() {
+	let reddit = init_reddit();
+	let (username, _, _, _, _, _) = source_env().unwrap();
+
+	let saved = reddit.get_saved(&username, Some(SavedKind::Comments)).unwrap();
+	for thing in saved["data"]["children"].as_array().unwrap() {
+		assert_eq!(thing["kind"], "t1");
+	}
+}
+
 #[test(otheruser)]
 fn other_info() {
 	let reddit = init_reddit();
@@ -104,6 +172,17 @@ fn other_info() {
 	info!("That one guy:\n{}", json::to_string_pretty(&otherguy).unwrap());
 }
 
+#[test(users)]
+fn users_info() {
+	let reddit = init_reddit();
+
+	let users = reddit.get_users(&["DO_U_EVN_SPAGHETTI", "this_user_should_not_exist_orca", "IntrepidPig"]);
+	assert_eq!(users.len(), 3);
+	assert_eq!(users[0].as_ref().unwrap().name, "DO_U_EVN_SPAGHETTI");
+	assert!(users[1].is_err());
+	assert_eq!(users[2].as_ref().unwrap().name, "IntrepidPig");
+}
+
 #[test(stream)]
 fn comment_stream() {
 	let reddit = init_reddit();
@@ -113,7 +192,7 @@ fn comment_stream() {
 
 	for comment in comments {
 		count += 1;
-		trace!("Got comment #{} by {}", count, comment.author);
+		trace!("Got comment #{} by {:?}", count, comment.author);
 
 		if count > 500 {
 			break;
@@ -121,6 +200,16 @@ fn comment_stream() {
 	}
 }
 
+#[test(watch_post)]
+fn watch_post() {
+	let reddit = init_reddit();
+	let tree = reddit.get_comment_tree("7le01h").unwrap().traverse();
+
+	// Comments that are already there are yielded first
+	let first: Vec<Comment> = reddit.watch_post_comments("7le01h").unwrap().take(1).collect();
+	assert!(tree.iter().any(|comment| comment.name == first[0].name));
+}
+
 #[test(tree)]
 fn comment_tree() {
 	let reddit = init_reddit();
@@ -131,7 +220,7 @@ fn comment_tree() {
 			for _ in 0..level {
 				print!("\t");
 			}
-			println!("{} by {} (parent: {})", comment.id, comment.author, comment.parent_id);
+			println!("{} by {:?} (parent: {})", comment.id, comment.author, comment.parent_id);
 			print_tree(comment.replies, level + 1);
 		}
 	};
@@ -139,6 +228,50 @@ fn comment_tree() {
 	print_tree(tree, 0);
 }
 
+#[test(subtree)]
+fn comment_subtree() {
+	let reddit = init_reddit();
+	let tree = reddit.get_comment_tree("7le01h").unwrap();
+	let root = tree.children.front().unwrap();
+
+	let subtree = reddit.get_comment_subtree("7le01h", &root.id, None).unwrap();
+	assert_eq!(subtree.children.len(), 1);
+	assert_eq!(subtree.children[0].id, root.id);
+	assert_eq!(subtree.traverse().len(), tree.children[0].clone().replies.traverse().len() + 1);
+}
+
+#[test(sub_settings)]
+fn sub_settings() {
+	let reddit = init_reddit();
+
+	let settings = reddit.get_subreddit_settings("pigasusland").unwrap();
+	info!("Settings: {:?}", settings);
+}
+
+#[test(is_subscribed)]
+fn is_subscribed() {
+	let reddit = init_reddit();
+
+	let subscribed = reddit.is_subscribed("pigasusland").unwrap();
+	info!("Subscribed to r/pigasusland: {}", subscribed);
+}
+
+#[test(autocomplete)]
+fn autocomplete() {
+	let reddit = init_reddit();
+
+	let entries = reddit.subreddit_autocomplete("askr", false, true).unwrap();
+	assert!(entries.iter().any(|entry| entry.name == "AskReddit" && !entry.is_profile));
+}
+
+#[test(recommend)]
+fn recommend() {
+	let reddit = init_reddit();
+
+	let subs = reddit.recommend_subreddits(&["unixporn", "linux"], &["archlinux"]).unwrap();
+	assert!(!subs.iter().any(|sub| sub == "archlinux"));
+}
+
 //#[test(Stress)]
 fn stress_test() {
 	let requests = 60;
@@ -192,6 +325,14 @@ fn load_post() {
 	info!("Got post: {:?}", post);
 }
 
+#[test(awards)]
+fn awards() {
+	let reddit = init_reddit();
+
+	let awards = reddit.get_available_awards("t3_7am0zo").unwrap();
+	info!("Got awards: {:?}", awards);
+}
+
 #[test(message)]
 fn message() {
 	let reddit = init_reddit();
@@ -199,24 +340,33 @@ fn message() {
 	reddit.message("intrepidpig", "please don't spam me", "oops").unwrap();
 }
 
+#[test(message_as_sub)]
+fn message_as_sub() {
+	let reddit = init_reddit();
+
+	reddit.message_as_subreddit("pigasusland", "intrepidpig", "please don't spam me", "oops").unwrap();
+	assert!(reddit.message_as_subreddit("askreddit", "intrepidpig", "please don't spam me", "oops").is_err());
+}
+
 #[test(submit)]
 fn test_post() {
-	println!("{}", init_reddit().submit_self("pigasusland", "Test Post", "The time is dank-o-clock", true).unwrap());
+	println!("{:?}", init_reddit().submit_self("pigasusland", "Test Post", "The time is dank-o-clock", true).unwrap());
 }
 
 #[test(urlencode)]
 fn urlencode() {
-	println!("{}", init_reddit().submit_self("pigasusland", "Tanks & Banks", "Will it work? Cheese & Rice", true).unwrap());
+	println!("{:?}", init_reddit().submit_self("pigasusland", "Tanks & Banks", "Will it work? Cheese & Rice", true).unwrap());
 }
 
 #[test(force_refresh)]
 fn force_refresh() {
 	init_logging();
 	let (username, password, script_id, secret, installed_id, redirect) = source_env().unwrap();
-	let mut reddit = App::new("Orca Test Installed App", "v0.4.0", "/u/IntrepidPig").unwrap();
+	let reddit = App::new("Orca Test Installed App", "v0.4.0", "/u/IntrepidPig").unwrap();
 	reddit.authorize_installed_app(&installed_id, &redirect, None, &Scopes::all()).unwrap();
 
-	let auth = reddit.conn.auth.as_ref().unwrap();
+	let auth = reddit.conn.auth.borrow();
+	let auth = auth.as_ref().unwrap();
 	let old_auth = auth.clone();
 	thread::sleep(Duration::new(2, 0));
 	auth.refresh(&reddit.conn).unwrap();
@@ -255,7 +405,7 @@ fn force_refresh() {
 fn auto_refresh() {
 	init_logging();
 	let (username, password, script_id, secret, installed_id, redirect) = source_env().unwrap();
-	let mut reddit = App::new("Orca Test Installed App", "v0.4.0", "/u/IntrepidPig").unwrap();
+	let reddit = App::new("Orca Test Installed App", "v0.4.0", "/u/IntrepidPig").unwrap();
 	reddit.authorize_installed_app(&installed_id, &redirect, None, &Scopes::all()).unwrap();
 	reddit.get_self().unwrap();
 
@@ -296,3 +446,2125 @@ fn auto_refresh() {
 		panic!("Test failed")
 	}
 }
+
+fn comment_fixture(id: &str) -> json::Value {
+	json::from_str(&format!(
+		r#"{{"kind": "t1", "data": {{"edited": false, "id": "{}", "parent_id": "t3_7le01h", "link_id": "t3_7le01h", "author": "IntrepidPig", "ups": 3, "downs": 0, "score": 3, "body": "Hello", "is_submitter": false, "stickied": false, "subreddit": "pigasusland", "score_hidden": false, "name": "t1_{}", "created_utc": 1513785600.0, "permalink": "/r/pigasusland/comments/7le01h/_/{}/", "replies": ""}}}}"#,
+		id, id, id
+	))
+	.unwrap()
+}
+
+#[test(edited)]
+fn edited_comments() {
+	init_logging();
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let unedited = comment_fixture("dr0unedited");
+	let mut edited = comment_fixture("dr0edited");
+	edited["data"]["edited"] = json::from_str("1513789200.5").unwrap();
+	let data = json::Value::Array(vec![unedited, edited]);
+
+	let comments: Vec<Comment> = Listing::from_value(&data, "7le01h", &reddit).unwrap().collect();
+	assert_eq!(comments.len(), 2);
+	assert!(comments[0].edited.is_none());
+	let edited = comments[1].edited.unwrap();
+	assert_eq!(edited.timestamp(), 1_513_789_200);
+	assert_eq!(edited.timestamp_subsec_millis(), 500);
+}
+
+#[test(timestamps)]
+fn timestamps() {
+	init_logging();
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut whole = comment_fixture("dr0whole");
+	whole["data"]["edited"] = json::from_str("1234567890.0").unwrap();
+	let whole = Comment::from_value(&whole, &reddit).unwrap();
+	assert_eq!(whole.edited.unwrap().timestamp(), 1_234_567_890);
+	assert_eq!(whole.created_utc.unwrap().timestamp(), 1_513_785_600);
+
+	// Deleted content can have the creation time zeroed or left out
+	let mut zeroed = comment_fixture("dr0zeroed");
+	zeroed["data"]["created_utc"] = json::Value::from(0.0);
+	assert_eq!(Comment::from_value(&zeroed, &reddit).unwrap().created_utc, None);
+	let mut absent = comment_fixture("dr0absent");
+	absent["data"].as_object_mut().unwrap().remove("created_utc");
+	assert_eq!(Comment::from_value(&absent, &reddit).unwrap().created_utc, None);
+
+	let mut bad = comment_fixture("dr0bad");
+	bad["data"]["edited"] = json::Value::from("yesterday");
+	assert!(Comment::from_value(&bad, &reddit).is_err());
+}
+
+#[test(malformed_comment)]
+fn malformed_comment() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut bad = comment_fixture("dr0bad");
+	bad["data"]["ups"] = json::Value::from("many");
+	let data = json::Value::from(vec![comment_fixture("dr0first"), bad, comment_fixture("dr0last")]);
+
+	// The malformed comment is skipped, the others are kept
+	let comments: Vec<String> = Listing::from_value(&data, "7le01h", &reddit).unwrap().map(|comment| comment.id).collect();
+	assert_eq!(comments, vec!["dr0first", "dr0last"]);
+}
+
+#[test(deleted)]
+fn deleted_comments() {
+	init_logging();
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut removed = comment_fixture("dr0removed");
+	removed["data"]["author"] = json::Value::from("[deleted]");
+	removed["data"]["body"] = json::Value::from("[removed]");
+	removed["data"]["author_flair_text"] = json::Value::Null;
+	let mut deleted = comment_fixture("dr0deleted");
+	deleted["data"]["author"] = json::Value::from("[deleted]");
+	deleted["data"]["body"] = json::Value::from("[deleted]");
+	deleted["data"].as_object_mut().unwrap().remove("link_id");
+	let data = json::Value::Array(vec![comment_fixture("dr0first"), removed, comment_fixture("dr0middle"), deleted, comment_fixture("dr0last")]);
+
+	let comments: Vec<Comment> = Listing::from_value(&data, "7le01h", &reddit).unwrap().collect();
+	assert_eq!(comments.len(), 5);
+	assert!(comments[1].author.is_none());
+	assert!(comments[1].is_removed());
+	assert!(!comments[1].is_deleted());
+	assert!(comments[3].is_deleted());
+	assert_eq!(comments[3].link_id, "t3_7le01h");
+	for i in &[0, 2, 4] {
+		assert_eq!(comments[*i].author, Some("IntrepidPig".to_string()));
+		assert!(!comments[*i].is_deleted() && !comments[*i].is_removed());
+	}
+}
+
+#[test(mod_info)]
+fn mod_info() {
+	init_logging();
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut removed = comment_fixture("dr0removed");
+	removed["data"]["removed_by_category"] = json::Value::from("moderator");
+	removed["data"]["banned_by"] = json::Value::from("IntrepidPig");
+	removed["data"]["approved_by"] = json::Value::Null;
+	removed["data"]["num_reports"] = json::Value::from(2);
+
+	let removed = Comment::from_value(&removed, &reddit).unwrap();
+	assert_eq!(removed.removed_by_category, Some("moderator".to_string()));
+	assert_eq!(removed.banned_by, Some("IntrepidPig".to_string()));
+	assert_eq!(removed.approved_by, None);
+	assert_eq!(removed.num_reports, 2);
+
+	let mut reported = comment_fixture("dr0reported");
+	reported["data"]["num_reports"] = json::Value::from(3);
+	reported["data"]["mod_reports"] = json::from_str(r#"[["Rule 1", "IntrepidPig"]]"#).unwrap();
+	reported["data"]["user_reports"] = json::from_str(r#"[["Spam", 2], ["Rude", 1]]"#).unwrap();
+	let reported = Comment::from_value(&reported, &reddit).unwrap();
+	assert_eq!(reported.mod_reports, Some(vec![("Rule 1".to_string(), "IntrepidPig".to_string())]));
+	assert_eq!(reported.user_reports, Some(vec![("Spam".to_string(), 2), ("Rude".to_string(), 1)]));
+
+	let mut things = vec![comment_fixture("dr0plain"), comment_fixture("dr0reported")];
+	things[1]["data"]["num_reports"] = json::Value::from(3);
+	sort_by_reports(&mut things);
+	assert_eq!(things[0]["data"]["id"], "dr0reported");
+
+	let plain = Comment::from_value(&comment_fixture("dr0plain"), &reddit).unwrap();
+	assert_eq!(plain.removed_by_category, None);
+	assert_eq!(plain.banned_by, None);
+	assert_eq!(plain.num_reports, 0);
+	assert_eq!(plain.mod_reports, None);
+	assert_eq!(plain.user_reports, None);
+}
+
+#[test(vote_state)]
+fn vote_state() {
+	init_logging();
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut voted = comment_fixture("dr0voted");
+	voted["data"]["likes"] = json::Value::from(false);
+	voted["data"]["saved"] = json::Value::from(true);
+
+	let voted = Comment::from_value(&voted, &reddit).unwrap();
+	assert_eq!(voted.likes, Some(false));
+	assert!(voted.saved);
+
+	// Anonymous listings omit the user state
+	let anonymous = Comment::from_value(&comment_fixture("dr0anon"), &reddit).unwrap();
+	assert_eq!(anonymous.likes, None);
+	assert!(!anonymous.saved);
+}
+
+#[test(action_outcome)]
+fn action_outcome() {
+	let applied = ActionOutcome::from_response(&json::from_str(r#"{"json": {"errors": [], "data": {"id": "abc123", "name": "t3_abc123"}}}"#).unwrap());
+	let ids = vec!["abc123".to_string(), "t3_abc123".to_string()];
+	assert_eq!(applied, ActionOutcome::Applied { ids });
+	assert!(applied.changed());
+
+	assert_eq!(ActionOutcome::from_response(&json::from_str("{}").unwrap()), ActionOutcome::Applied { ids: Vec::new() });
+	assert!(!ActionOutcome::Unchanged.changed());
+}
+
+#[test(sub_errors)]
+fn sub_errors() {
+	use hyper::StatusCode;
+	use net::error_from_response;
+	use std::collections::HashMap;
+
+	let no_headers = HashMap::new();
+	let error = |path: &str, status: u16, headers: &HashMap<String, String>, body: &str| error_from_response("request", path, false, StatusCode::from_u16(status).unwrap(), headers, "response", body);
+
+	match error("/r/secretsub/.json", 403, &no_headers, r#"{"reason": "private", "message": "Forbidden", "error": 403}"#) {
+		RedditError::SubredditPrivate { sub } => assert_eq!(sub, "secretsub"),
+		e => panic!("Unexpected error {:?}", e),
+	}
+	match error("/r/bannedsub/hot.json", 404, &no_headers, r#"{"reason": "banned", "message": "Not Found", "error": 404}"#) {
+		RedditError::SubredditBanned { sub } => assert_eq!(sub, "bannedsub"),
+		e => panic!("Unexpected error {:?}", e),
+	}
+	match error("/r/typosub/.json", 404, &no_headers, r#"{"message": "Not Found", "error": 404}"#) {
+		RedditError::SubredditNotFound { sub } => assert_eq!(sub, "typosub"),
+		e => panic!("Unexpected error {:?}", e),
+	}
+	let mut redirect = HashMap::new();
+	redirect.insert("location".to_string(), "https://www.reddit.com/subreddits/search.json?q=typosub".to_string());
+	match error("/r/typosub/.json", 302, &redirect, "") {
+		RedditError::SubredditNotFound { sub } => assert_eq!(sub, "typosub"),
+		e => panic!("Unexpected error {:?}", e),
+	}
+	match error("/r/pigasusland/comments/7le01h/.json", 404, &no_headers, r#"{"message": "Not Found", "error": 404}"#) {
+		RedditError::BadRequest { .. } => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+	redirect.insert("location".to_string(), "/r/CasualConversation/.json".to_string());
+	match error("/r/random/.json", 302, &redirect, "") {
+		RedditError::Redirected { location, .. } => assert_eq!(location, "/r/CasualConversation/.json"),
+		e => panic!("Unexpected error {:?}", e),
+	}
+}
+
+#[test(login_redirect)]
+fn login_redirect() {
+	use hyper::StatusCode;
+	use net::{error_from_response, is_login_location};
+	use std::collections::HashMap;
+
+	assert!(is_login_location("https://www.reddit.com/login/?dest=https%3A%2F%2Fwww.reddit.com%2Fprefs%2F"));
+	assert!(is_login_location("/login?dest=%2Fsubmit"));
+	assert!(is_login_location("https://old.reddit.com/account/login"));
+	assert!(!is_login_location("/r/login/.json"));
+	assert!(!is_login_location("/r/CasualConversation/.json"));
+
+	let mut redirect = HashMap::new();
+	redirect.insert("location".to_string(), "https://www.reddit.com/login/?dest=https%3A%2F%2Fwww.reddit.com%2Fr%2Fpigasusland%2Fabout%2Fedit".to_string());
+	redirect.insert("content-type".to_string(), "text/html; charset=utf-8".to_string());
+	match error_from_response("request", "/r/pigasusland/about/edit/.json", false, StatusCode::FOUND, &redirect, "response", "<html></html>") {
+		RedditError::NotAuthenticated { request } => assert_eq!(request, "request"),
+		e => panic!("Unexpected error {:?}", e),
+	}
+}
+
+#[test(redirect_location)]
+fn redirect_location() {
+	use net::subreddit_from_location;
+
+	assert_eq!(subreddit_from_location("/r/CasualConversation/.json"), Some("CasualConversation".to_string()));
+	assert_eq!(subreddit_from_location("https://www.reddit.com/r/aww/.json?utm_source=random"), Some("aww".to_string()));
+	assert_eq!(subreddit_from_location("https://www.reddit.com/subreddits/search.json?q=random"), None);
+}
+
+#[test(random_subreddit)]
+fn random_subreddit() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+
+	let sub = reddit.get_random_subreddit(false).unwrap();
+	assert!(!sub.display_name.is_empty());
+	assert!(!sub.is_user_profile());
+}
+
+#[test(blocked)]
+fn blocked() {
+	use hyper::StatusCode;
+	use net::error_from_response;
+	use std::collections::HashMap;
+
+	let mut headers = HashMap::new();
+	headers.insert("content-type".to_string(), "text/html; charset=UTF-8".to_string());
+	headers.insert("cf-ray".to_string(), "4a1f2b3c4d5e6f70-FRA".to_string());
+	let body = "<!DOCTYPE html><html><head><title>Attention Required! | Cloudflare</title></head><body>Sorry, you have been blocked</body></html>";
+
+	match error_from_response("request", "/r/pigasusland/.json", false, StatusCode::FORBIDDEN, &headers, "response", body) {
+		RedditError::Blocked { cf_ray } => assert_eq!(cf_ray, Some("4a1f2b3c4d5e6f70-FRA".to_string())),
+		e => panic!("Unexpected error {:?}", e),
+	}
+
+	// The page names cloudflare even if the ray id is missing
+	headers.remove("cf-ray");
+	match error_from_response("request", "/r/pigasusland/.json", false, StatusCode::SERVICE_UNAVAILABLE, &headers, "response", body) {
+		RedditError::Blocked { cf_ray: None } => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+
+	// Html pages without a sign of cloudflare aren't blocks
+	let page = "<!DOCTYPE html><html><head><title>reddit.com: forbidden</title></head><body>Forbidden</body></html>";
+	match error_from_response("request", "/api/v1/me", false, StatusCode::FORBIDDEN, &headers, "response", page) {
+		RedditError::Forbidden { .. } => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+	match error_from_response("request", "/api/v1/me", false, StatusCode::INTERNAL_SERVER_ERROR, &headers, "response", page) {
+		RedditError::BadRequest { status: Some(500), .. } => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+
+	headers.insert("content-type".to_string(), "application/json; charset=UTF-8".to_string());
+	match error_from_response("request", "/api/v1/me", false, StatusCode::FORBIDDEN, &headers, "response", r#"{"message": "Forbidden", "error": 403}"#) {
+		RedditError::Forbidden { .. } => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+}
+
+#[test(author_info)]
+fn author_info() {
+	init_logging();
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut deleted = comment_fixture("dr0deleted");
+	deleted["data"]["author"] = json::Value::from("[deleted]");
+	let deleted = Comment::from_value(&deleted, &reddit).unwrap();
+
+	// Doesn't need a request, so works without authorization
+	match deleted.author_info(&reddit).unwrap() {
+		UserLookup::NotAvailable => {}
+		UserLookup::Found(user) => panic!("Found info of deleted author: {:?}", user),
+	}
+}
+
+#[test(useragent)]
+fn useragent() {
+	let useragent = UserAgent::new("OrcaLibTest", "v0.2.0").platform("linux").author("IntrepidPig");
+	assert_eq!(useragent.to_string(), "linux:OrcaLibTest:v0.2.0 (by /u/IntrepidPig)");
+	assert!(useragent.warnings().is_empty());
+	assert_eq!(UserAgent::new("OrcaLibTest", "v0.2.0").platform("linux").author("/u/IntrepidPig").to_string(), useragent.to_string());
+
+	assert_eq!(UserAgent::new("bot", "v0.2.0").author("IntrepidPig").warnings().len(), 1);
+	assert_eq!(UserAgent::new("OrcaLibTest", "v0.2.0").warnings().len(), 1);
+	assert!(UserAgent::custom("bot").warnings().is_empty());
+	assert_eq!(UserAgent::custom("bot").to_string(), "bot");
+
+	let reddit = App::with_user_agent(&useragent).unwrap();
+	assert_eq!(reddit.conn().user_agent(), "linux:OrcaLibTest:v0.2.0 (by /u/IntrepidPig)");
+}
+
+#[test(can_submit)]
+fn can_submit() {
+	init_logging();
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let account = json::from_str(r#"{"name": "OrcaLibTest", "is_suspended": false, "link_karma": 1, "comment_karma": 1}"#).unwrap();
+	let about = |extra: &str| json::from_str::<json::Value>(&format!(r#"{{"kind": "t5", "data": {{"display_name": "pigasusland", "subreddit_type": "public", {}}}}}"#, extra)).unwrap();
+	let requirements = |policy: &str| PostRequirements::from_value(&json::from_str(&format!(r#"{{"body_restriction_policy": "{}", "domain_blacklist": [], "domain_whitelist": []}}"#, policy)).unwrap(), &reddit).unwrap();
+
+	let open = SubmitCheck::evaluate("pigasusland", SubmitKind::Link, &about(r#""submission_type": "any""#), &account, &requirements("none"));
+	assert!(open.allowed());
+
+	let self_only = SubmitCheck::evaluate("pigasusland", SubmitKind::Link, &about(r#""submission_type": "self""#), &account, &requirements("none"));
+	assert!(!self_only.allowed());
+	assert!(SubmitCheck::evaluate("pigasusland", SubmitKind::SelfPost, &about(r#""submission_type": "self""#), &account, &requirements("none")).allowed());
+
+	let banned = SubmitCheck::evaluate("pigasusland", SubmitKind::SelfPost, &about(r#""submission_type": "any", "user_is_banned": true"#), &account, &requirements("none"));
+	let reason = "The account is banned from r/pigasusland".to_string();
+	assert_eq!(banned, SubmitCheck::Denied { reason });
+
+	assert!(!SubmitCheck::evaluate("pigasusland", SubmitKind::Link, &about(r#""submission_type": "any""#), &account, &requirements("required")).allowed());
+}
+
+#[test(modmail_page)]
+fn modmail_page() {
+	init_logging();
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let conversation = |id: &str| {
+		format!(
+			r#""{}": {{"id": "{}", "subject": "Hi", "owner": {{"displayName": "pigasusland"}}, "isHighlighted": false, "numMessages": 1, "lastUpdated": "2018-01-01T12:00:00.000000+00:00"}}"#,
+			id, id
+		)
+	};
+	let resp: json::Value = json::from_str(&format!(r#"{{"conversationIds": ["2b", "1a"], "conversations": {{{}, {}}}, "messages": {{}}}}"#, conversation("1a"), conversation("2b"))).unwrap();
+
+	let page = ModmailPage::from_response(&resp, 2, &reddit).unwrap();
+	assert_eq!(page.conversations.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), vec!["2b", "1a"]);
+	assert_eq!(page.after, Some("1a".to_string()));
+	assert!(page.conversations[0].last_updated.is_some());
+
+	let last = ModmailPage::from_response(&resp, 25, &reddit).unwrap();
+	assert_eq!(last.after, None);
+}
+
+#[test(pages)]
+fn pages() {
+	init_logging();
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut pages = reddit.get_all_posts("pigasusland", Sort::Top(SortTime::All)).unwrap();
+	assert_eq!(pages.page_params(), vec![("t".to_string(), "all".to_string()), ("limit".to_string(), "100".to_string()), ("count".to_string(), "0".to_string())]);
+
+	pages.record_page(&json::from_str(r#"{"kind": "Listing", "data": {"after": "t3_b", "children": [{"kind": "t3", "data": {"name": "t3_a"}}, {"kind": "t3", "data": {"name": "t3_b"}}]}}"#).unwrap());
+	assert_eq!(pages.loaded_count(), 2);
+	assert_eq!(pages.after(), Some("t3_b"));
+	assert!(pages.page_params().contains(&("count".to_string(), "2".to_string())));
+	assert!(pages.page_params().contains(&("after".to_string(), "t3_b".to_string())));
+
+	// The last page ends iteration without another request
+	pages.record_page(&json::from_str(r#"{"kind": "Listing", "data": {"after": null, "children": [{"kind": "t3", "data": {"name": "t3_c"}}]}}"#).unwrap());
+	let names: Vec<String> = pages.map(|thing| thing.unwrap()["data"]["name"].as_str().unwrap().to_string()).collect();
+	assert_eq!(names, vec!["t3_a", "t3_b", "t3_c"]);
+}
+
+#[test(nsfw_gate)]
+fn nsfw_gate() {
+	use hyper::StatusCode;
+	use net::error_from_response;
+	use std::collections::HashMap;
+
+	let mut redirect = HashMap::new();
+	redirect.insert("location".to_string(), "https://www.reddit.com/over18?dest=https%3A%2F%2Fwww.reddit.com%2Fcomments%2F7le01h%2F.json".to_string());
+	match error_from_response("request", "/comments/7le01h/.json", false, StatusCode::FOUND, &redirect, "response", "") {
+		RedditError::NsfwGate { request } => assert_eq!(request, "/comments/7le01h/.json"),
+		e => panic!("Unexpected error {:?}", e),
+	}
+
+	// Gated response in place of the post and comment listings
+	let gated: json::Value = json::from_str(r#"{"reason": "over18", "message": "Forbidden", "error": 403}"#).unwrap();
+	match comment_listing(&gated, "/comments/7le01h/.json").unwrap_err().downcast::<RedditError>() {
+		Ok(RedditError::NsfwGate { .. }) => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+
+	let tree: json::Value = json::from_str(r#"[{"kind": "Listing", "data": {"children": []}}, {"kind": "Listing", "data": {"children": [{"kind": "more", "data": {}}]}}]"#).unwrap();
+	assert_eq!(comment_listing(&tree, "/comments/7le01h/.json").unwrap().as_array().unwrap().len(), 1);
+}
+
+#[test(write_bans)]
+fn write_bans() {
+	use hyper::StatusCode;
+	use net::{check_json_errors, error_from_response};
+	use std::collections::HashMap;
+
+	let no_headers = HashMap::new();
+	let mut html = HashMap::new();
+	html.insert("content-type".to_string(), "text/html; charset=UTF-8".to_string());
+	let error = |path: &str, headers: &HashMap<String, String>, body: &str| error_from_response("request", path, true, StatusCode::FORBIDDEN, headers, "response", body);
+
+	match error("/api/comment", &no_headers, r#"{"reason": "USER_SUSPENDED", "explanation": "This account has been suspended", "message": "Forbidden", "error": 403}"#) {
+		RedditError::AccountSuspended => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+	// Only the reason counts, not bodies that happen to mention suspensions
+	match error("/r/pigasusland/about/.json", &no_headers, r#"{"message": "Forbidden", "error": 403, "description": "Users get suspended for spam"}"#) {
+		RedditError::Forbidden { .. } => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+	match error("/api/submit", &html, "<html><body><h1>Your account has been suspended</h1></body></html>") {
+		RedditError::Forbidden { .. } => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+	// Anonymous requests can't fail because of the account
+	match error_from_response("request", "/api/comment", false, StatusCode::FORBIDDEN, &no_headers, "response", r#"{"reason": "USER_SUSPENDED", "message": "Forbidden", "error": 403}"#) {
+		RedditError::Forbidden { .. } => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+	match error("/r/pigasusland/api/submit", &no_headers, r#"{"reason": "USER_BLOCKED", "message": "Forbidden", "error": 403}"#) {
+		RedditError::BannedFromSubreddit { sub, code: None } => assert_eq!(sub, Some("pigasusland".to_string())),
+		e => panic!("Unexpected error {:?}", e),
+	}
+	match error("/api/compose", &no_headers, r#"{"reason": "USER_BLOCKED", "message": "Forbidden", "error": 403}"#) {
+		RedditError::Forbidden { .. } => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+
+	// Form api endpoints report the ban inside of a successful response
+	let form: json::Value = json::from_str(r#"{"json": {"errors": [["SUBREDDIT_NOTALLOWED", "you aren't allowed to post there.", "sr"]]}}"#).unwrap();
+	match check_json_errors("request", &form).unwrap_err().downcast::<RedditError>() {
+		Ok(RedditError::BannedFromSubreddit { sub: None, .. }) => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+}
+
+#[test(api_error_codes)]
+fn api_error_codes() {
+	use net::check_json_errors;
+
+	let error = |body: &str| check_json_errors("request", &json::from_str(body).unwrap()).unwrap_err().downcast::<RedditError>().unwrap();
+	let fixtures = [
+		(r#"{"json": {"errors": [["NOT_WHITELISTED", "you can't send a message to that user", "to"]]}}"#, ApiErrorCode::NotWhitelisted),
+		(r#"{"json": {"errors": [["TOO_OLD", "that's a piece of history now; it's too late to reply to it", "parent"]]}}"#, ApiErrorCode::TooOld),
+		(r#"{"json": {"errors": [["THREAD_LOCKED", "Comments are locked.", "parent"]]}}"#, ApiErrorCode::ThreadLocked),
+		(r#"{"json": {"errors": [["DELETED_LINK", "the link you are commenting on has been deleted", "parent"]]}}"#, ApiErrorCode::DeletedLink),
+		(r#"{"json": {"errors": [["SOMETHING_IS_BROKEN", "Something is broken, please try again later.", "parent"]]}}"#, ApiErrorCode::SomethingIsBroken),
+	];
+	for &(body, expected) in &fixtures {
+		match error(body) {
+			RedditError::ApiError { code, ref message, .. } => {
+				assert_eq!(code, expected);
+				assert!(!message.is_empty());
+				assert_eq!(ApiErrorCode::from_code(code.as_str()), Some(code));
+			}
+			e => panic!("Unexpected error {:?} for {}", e, body),
+		}
+	}
+
+	// Restricted subreddits stay bans that carry the code, and unknown codes stay bad requests
+	match error(r#"{"json": {"errors": [["SUBREDDIT_NOTALLOWED", "you aren't allowed to post there.", "sr"]]}}"#) {
+		RedditError::BannedFromSubreddit { sub: None, code: Some(code) } => {
+			assert_eq!(code, ApiErrorCode::SubredditNotAllowed);
+			assert_eq!(ApiErrorCode::from_code(code.as_str()), Some(code));
+		}
+		e => panic!("Unexpected error {:?}", e),
+	}
+	match error(r#"{"json": {"errors": [["NO_TEXT", "we need something here", "text"]]}}"#) {
+		RedditError::BadRequest { status: None, .. } => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+}
+
+#[test(search_window)]
+fn search_window() {
+	use chrono::{TimeZone, Utc};
+
+	let start = Utc.timestamp_opt(1_500_000_000, 0).unwrap();
+	let end = Utc.timestamp_opt(1_500_100_000, 0).unwrap();
+	let post = |created: i64| json::from_str::<json::Value>(&format!(r#"{{"kind": "t3", "data": {{"created_utc": {}.0}}}}"#, created)).unwrap();
+
+	let partial = SearchWindow::new(vec![post(1_500_000_500); 10], start, end);
+	assert!(partial.complete);
+	assert_eq!(partial.start, start);
+
+	// At the ceiling the window only reaches back to the oldest post found
+	let posts: Vec<json::Value> = (0..SEARCH_RESULT_CEILING as i64).map(|i| post(1_500_100_000 - i * 10)).collect();
+	let full = SearchWindow::new(posts, start, end);
+	assert!(!full.complete);
+	assert_eq!(full.start, Utc.timestamp_opt(1_500_100_000 - (SEARCH_RESULT_CEILING as i64 - 1) * 10, 0).unwrap());
+	assert_eq!(full.end, end);
+}
+
+#[test(subreddit_history)]
+fn subreddit_history() {
+	use chrono::{TimeZone, Utc};
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let at = |secs: i64| Utc.timestamp_opt(1_500_000_000 + secs, 0).unwrap();
+	// One post every second and a second post at the boundary of the first windows
+	let mut posts: Vec<(String, i64)> = (0..=20).map(|secs| (format!("p{}", secs), secs)).collect();
+	posts.push(("p10b".to_string(), 10));
+	let mut searches = Vec::new();
+	// A search that returns at most 3 posts, like a search with a tiny ceiling
+	let mut search = |start: chrono::DateTime<Utc>, end: chrono::DateTime<Utc>| {
+		searches.push((end - start).num_seconds());
+		let mut found: Vec<&(String, i64)> = posts.iter().filter(|post| at(post.1) >= start && at(post.1) <= end).collect();
+		found.sort_by_key(|post| -post.1);
+		let complete = found.len() <= 3;
+		let found = found
+			.iter()
+			.take(3)
+			.map(|post| {
+				let mut data = post_data(&post.0, &[]);
+				data["created_utc"] = json::Value::from(1_500_000_000 + post.1);
+				json::from_str(&format!(r#"{{"kind": "t3", "data": {}}}"#, data)).unwrap()
+			})
+			.collect();
+		Ok(SearchWindow { posts: found, start, end, complete })
+	};
+
+	let mut history = reddit.walk_subreddit_history("pigasusland", at(0), at(20));
+	let mut ids = Vec::new();
+	for _ in 0..8 {
+		ids.push(history.next_with(&mut search).unwrap().unwrap().id);
+	}
+	assert_eq!(ids, vec!["p0", "p1", "p2", "p3", "p4", "p5", "p6", "p7"]);
+
+	// Resuming from a saved cursor continues after the posts yielded, without repeating any
+	let cursor = HistoryCursor::from_json(&history.cursor().to_json()).unwrap();
+	assert_eq!(&cursor, history.cursor());
+	let mut history = history.resume(cursor);
+	while let Some(post) = history.next_with(&mut search) {
+		ids.push(post.unwrap().id);
+	}
+	let mut expected: Vec<String> = (0..=20).map(|secs| format!("p{}", secs)).collect();
+	expected.insert(11, "p10b".to_string());
+	assert_eq!(ids, expected);
+	// Windows were split until they fit and grew again afterwards
+	assert_eq!(searches[..4], [20, 10, 5, 2]);
+	assert!(searches.iter().skip(4).any(|&secs| secs > 2));
+
+	// A failed search ends the walk, but keeps the cursor
+	let mut history = reddit.walk_subreddit_history("pigasusland", at(0), at(20));
+	assert!(history.next_with(|_, _| Err(format_err!("offline"))).unwrap().is_err());
+	assert!(history.next_with(|_, _| Err(format_err!("offline"))).is_none());
+	assert_eq!(history.cursor().start, at(0));
+	assert!(HistoryCursor::from_json("{}").is_err());
+}
+
+#[test(karma)]
+fn karma_breakdown() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let resp = json::from_str(r#"{"kind": "KarmaList", "data": [{"sr": "pigasusland", "comment_karma": 3, "link_karma": 1}, {"sr": "unixporn", "comment_karma": 10, "link_karma": 40}, {"sr": "rust", "comment_karma": 12, "link_karma": 0}]}"#).unwrap();
+
+	let karma = KarmaBreakdown::from_value(&resp, &reddit).unwrap();
+	assert_eq!(karma.entries.iter().map(|entry| entry.sub.as_str()).collect::<Vec<_>>(), vec!["unixporn", "rust", "pigasusland"]);
+	assert_eq!(karma.total_comment, 25);
+	assert_eq!(karma.total_link, 41);
+}
+
+#[test(mod_permissions)]
+fn mod_permissions() {
+	let resp = json::from_str(r#"{"kind": "UserList", "data": {"children": [{"name": "OrcaLibTest", "id": "t2_abc", "mod_permissions": ["posts", "mail", "chat_operator"]}]}}"#).unwrap();
+
+	let permissions = mod_permissions_of(&resp, "orcalibtest");
+	assert_eq!(permissions, vec![ModPermission::Posts, ModPermission::Mail, ModPermission::ChatOperator]);
+	assert!(ModPermission::Posts.granted_by(&permissions));
+	assert!(!ModPermission::Config.granted_by(&permissions));
+	assert!(ModPermission::Config.granted_by(&[ModPermission::All]));
+
+	let not_mod = json::from_str(r#"{"kind": "UserList", "data": {"children": []}}"#).unwrap();
+	assert!(mod_permissions_of(&not_mod, "OrcaLibTest").is_empty());
+}
+
+#[test(status_code)]
+fn status_code() {
+	use hyper::StatusCode;
+	use net::error_from_response;
+	use std::collections::HashMap;
+
+	let no_headers = HashMap::new();
+	let error = |path: &str, status: StatusCode, body: &str| error_from_response("request", path, false, status, &no_headers, "response", body);
+
+	assert_eq!(error("/api/comment", StatusCode::TOO_MANY_REQUESTS, r#"{"message": "Too Many Requests", "error": 429}"#).status_code(), Some(429));
+	assert_eq!(error("/r/secretsub/.json", StatusCode::FORBIDDEN, r#"{"reason": "private", "message": "Forbidden", "error": 403}"#).status_code(), Some(403));
+	assert_eq!(error("/r/typosub/.json", StatusCode::NOT_FOUND, r#"{"message": "Not Found", "error": 404}"#).status_code(), Some(404));
+	assert_eq!(error("/api/submit", StatusCode::INTERNAL_SERVER_ERROR, r#"{"message": "Internal Server Error", "error": 500}"#).status_code(), Some(500));
+	assert_eq!(RedditError::AuthError.status_code(), None);
+	let bad_response = RedditError::BadResponse {
+		request: "request".to_string(),
+		response: "{".to_string(),
+	};
+	assert_eq!(bad_response.status_code(), None);
+}
+
+#[test(notification_counts)]
+fn notification_counts() {
+	let me = json::from_str(r#"{"name": "OrcaLibTest", "inbox_count": 3, "new_modmail_exists": true}"#).unwrap();
+	let modmail = json::from_str(r#"{"highlighted": 1, "notifications": 0, "archived": 4, "new": 2, "inprogress": 1, "mod": 0}"#).unwrap();
+
+	assert_eq!(NotificationCounts::from_values(&me, Some(&modmail)), NotificationCounts { inbox: 3, modmail: 3 });
+	assert_eq!(NotificationCounts::from_values(&me, None), NotificationCounts { inbox: 3, modmail: 0 });
+}
+
+#[test(watch_notifications)]
+fn watch_notifications() {
+	let reddit = init_reddit();
+	let mut calls = 0;
+	reddit.watch_notifications(Duration::from_secs(1), |_| {
+		calls += 1;
+		false
+	});
+	assert_eq!(calls, 1);
+}
+
+#[test(request_stats)]
+fn request_stats() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let stats = reddit.conn().request_stats();
+	assert_eq!(stats.total, 0);
+	assert!(stats.endpoints.is_empty());
+}
+
+#[test(targets)]
+fn targets() {
+	assert_eq!(Target::from("unixporn"), Target::Subreddit("unixporn".to_string()));
+	assert_eq!(Target::from("/r/unixporn").path(), "/r/unixporn");
+	assert_eq!(Target::from("all"), Target::All { exclude: Vec::new() });
+	assert_eq!(Target::from("all").path(), "/r/all");
+	let exclude = vec!["memes".to_string(), "dankmemes".to_string()];
+	assert_eq!(Target::from("r/all-memes-dankmemes"), Target::All { exclude: exclude.clone() });
+	assert_eq!(Target::All { exclude }.path(), "/r/all-memes-dankmemes");
+	assert_eq!(Target::from("popular"), Target::Popular);
+	assert_eq!(Target::Popular.path(), "/r/popular");
+	assert_eq!(Target::from(""), Target::Frontpage);
+	assert_eq!(Target::Frontpage.path(), "");
+	// Subreddits that only start with "all"
+	assert_eq!(Target::from("allthingsrust"), Target::Subreddit("allthingsrust".to_string()));
+	// Names that aren't ascii, where the fourth byte isn't the end of a character
+	assert_eq!(Target::from("aéé"), Target::Subreddit("aéé".to_string()));
+	assert_eq!(Target::from("all-é"), Target::All { exclude: vec!["é".to_string()] });
+
+	// The best sort is fine for the front page only
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	assert!(reddit.get_all_posts(Target::Frontpage, Sort::Best).is_ok());
+	assert!(reddit.get_all_posts(Target::Popular, Sort::Best).is_err());
+}
+
+#[test(widgets)]
+fn widgets() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let resp = json::from_str(
+		r#"{
+			"items": {
+				"widget_rules": {"id": "widget_rules", "kind": "subreddit-rules", "shortName": "Rules", "data": [{"shortName": "Be nice", "description": "Seriously", "violationReason": "Mean"}]},
+				"widget_related": {"id": "widget_related", "kind": "community-list", "shortName": "Related", "data": [{"name": "rust", "subscribers": 100}, {"name": "linux", "subscribers": 200}]},
+				"widget_calendar": {"id": "widget_calendar", "kind": "calendar", "shortName": "Events", "data": []},
+				"widget_id_card": {"id": "widget_id_card", "kind": "id-card", "shortName": "About", "subscribersCount": 42, "currentlyViewingCount": 3, "description": "Pigs"},
+				"widget_mods": {"id": "widget_mods", "kind": "moderators", "mods": [{"name": "IntrepidPig"}]}
+			},
+			"layout": {"sidebar": {"order": ["widget_rules", "widget_calendar", "widget_related"]}, "idCardWidget": "widget_id_card", "moderatorWidget": "widget_mods"}
+		}"#,
+	)
+	.unwrap();
+
+	let widgets = Widgets::from_value(&resp, &reddit).unwrap();
+	assert_eq!(widgets.sidebar.len(), 3);
+	match widgets.sidebar[0].kind {
+		WidgetKind::Rules(ref rules) => assert_eq!(rules[0].short_name, "Be nice"),
+		ref kind => panic!("Unexpected widget {:?}", kind),
+	}
+	match widgets.sidebar[1].kind {
+		WidgetKind::Other(ref kind, _) => assert_eq!(kind, "calendar"),
+		ref kind => panic!("Unexpected widget {:?}", kind),
+	}
+	match widgets.sidebar[2].kind {
+		WidgetKind::CommunityList(ref subs) => assert_eq!(subs, &["rust", "linux"]),
+		ref kind => panic!("Unexpected widget {:?}", kind),
+	}
+	match widgets.id_card.unwrap().kind {
+		WidgetKind::IdCard { subscribers, .. } => assert_eq!(subscribers, 42),
+		kind => panic!("Unexpected widget {:?}", kind),
+	}
+	match widgets.moderators.unwrap().kind {
+		WidgetKind::Moderators(mods) => assert_eq!(mods, vec!["IntrepidPig"]),
+		kind => panic!("Unexpected widget {:?}", kind),
+	}
+}
+
+#[test(stream_handle)]
+fn stream_handle() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut comments = reddit.create_comment_stream("all");
+	let handle = comments.handle();
+	assert!(!handle.is_stopped());
+
+	// Stopped from another thread before polling, so no request is sent
+	thread::spawn(move || handle.stop()).join().unwrap();
+	assert!(comments.handle().is_stopped());
+	assert!(comments.next().is_none());
+
+	let mut modqueue = reddit.modqueue_stream("pigasusland");
+	modqueue.set_interval(Duration::from_millis(10));
+	modqueue.handle().stop();
+	assert!(modqueue.next().is_none());
+	assert_eq!(reddit.conn().request_stats().total, 0);
+
+	// Streams sleep between polls and retries with the handle, which wakes up once the stream is
+	// stopped instead of waiting out the whole sleep
+	let handle = StreamHandle::new();
+	assert!(!handle.sleep(Duration::from_millis(20)));
+	let stopper = handle.clone();
+	let stopping = thread::spawn(move || {
+		thread::sleep(Duration::from_millis(50));
+		stopper.stop();
+	});
+	let start = Instant::now();
+	assert!(handle.sleep(Duration::from_secs(60)));
+	assert!(start.elapsed() < Duration::from_secs(30));
+	stopping.join().unwrap();
+	assert!(handle.sleep(Duration::from_secs(60)));
+}
+
+#[test(comment_edits)]
+fn comment_edits() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut edits = reddit.create_comment_stream("pigasusland").include_edits();
+	let comment = |id: &str, edited: Option<&str>, body: &str| {
+		let mut val = comment_fixture(id);
+		if let Some(edited) = edited {
+			val["data"]["edited"] = json::from_str(edited).unwrap();
+		}
+		val["data"]["body"] = json::Value::from(body);
+		Comment::from_value(&val, &reddit).unwrap()
+	};
+
+	edits.record(vec![comment("dr0first", None, "Nice"), comment("dr0second", None, "Hello")]);
+	edits.record(vec![comment("dr0first", None, "Nice"), comment("dr0second", Some("1513789200.0"), "Hello, edited")]);
+	edits.handle().stop();
+
+	let events: Vec<CommentEvent> = edits.collect();
+	assert_eq!(events.len(), 3);
+	match events[2] {
+		CommentEvent::Edited { ref before, ref after } => {
+			assert_eq!(before.body, "Hello");
+			assert_eq!(after.body, "Hello, edited");
+		}
+		ref event => panic!("Unexpected event {:?}", event),
+	}
+}
+
+#[test(ratelimited)]
+fn ratelimited() {
+	use net::check_json_errors;
+
+	let with_seconds = json::from_str(r#"{"json": {"ratelimit": 296.4, "errors": [["RATELIMIT", "you are doing that too much. try again in 4 minutes.", "ratelimit"]]}}"#).unwrap();
+	match check_json_errors("request", &with_seconds).unwrap_err().downcast::<RedditError>() {
+		Ok(RedditError::RateLimited { wait }) => assert_eq!(wait, Duration::from_millis(296_400)),
+		e => panic!("Unexpected error {:?}", e),
+	}
+	let message_only = json::from_str(r#"{"json": {"errors": [["RATELIMIT", "you are doing that too much. try again in 9 seconds.", "ratelimit"]]}}"#).unwrap();
+	match check_json_errors("request", &message_only).unwrap_err().downcast::<RedditError>() {
+		Ok(RedditError::RateLimited { wait }) => assert_eq!(wait, Duration::from_secs(9)),
+		e => panic!("Unexpected error {:?}", e),
+	}
+}
+
+#[test(pacing)]
+fn pacing() {
+	use app::pacing::paced;
+
+	// The second item is ratelimited once, the third fails for good
+	let mut calls = Vec::new();
+	let results = paced(&[1, 2, 3], |item| {
+		calls.push(*item);
+		match (*item, calls.iter().filter(|call| **call == *item).count()) {
+			(2, 1) => Err(failure::Error::from(RedditError::RateLimited { wait: Duration::from_millis(5) })),
+			(3, _) => Err(failure::Error::from(RedditError::AuthError)),
+			(item, _) => Ok(item * 10),
+		}
+	});
+
+	assert_eq!(calls, vec![1, 2, 2, 3]);
+	assert_eq!(results[0].as_ref().unwrap(), &10);
+	assert_eq!(results[1].as_ref().unwrap(), &20);
+	assert!(results[2].is_err());
+}
+
+#[test(backoff_jitter)]
+fn backoff_jitter() {
+	use app::pacing::Backoff;
+
+	let delays = |seed: u32| {
+		let mut backoff = Backoff::with_seed(seed);
+		(0..12).map(|_| backoff.next_delay()).collect::<Vec<Duration>>()
+	};
+
+	// The same seed gives the same delays, other seeds spread them out
+	assert_eq!(delays(7), delays(7));
+	assert_ne!(delays(7), delays(8));
+	for (fails, delay) in delays(7).into_iter().enumerate() {
+		let base = Duration::from_secs((fails as u64 + 1).min(10));
+		assert!(delay >= base / 2 && delay < base * 3 / 2, "{:?} outside the jitter of {:?}", delay, base);
+	}
+}
+
+#[test(banned_user)]
+fn banned_user() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let banned = BannedUser::from_value(&json::from_str(r#"{"date": 1513785600.0, "days_left": 3, "rel_id": "rb_1abc", "note": "spam", "id": "t2_abc", "name": "spammer"}"#).unwrap(), &reddit).unwrap();
+	assert_eq!(banned.name, "spammer");
+	assert_eq!(banned.note, "spam");
+	assert_eq!(banned.days_left, Some(3));
+	assert_eq!(banned.date.unwrap().timestamp(), 1_513_785_600);
+
+	let muted = BannedUser::from_value(&json::from_str(r#"{"date": 1513785600.0, "rel_id": "Mute_1abc", "id": "t2_abc", "name": "shouter"}"#).unwrap(), &reddit).unwrap();
+	assert_eq!(muted.note, "");
+	assert_eq!(muted.days_left, None);
+}
+
+#[test(preview)]
+fn preview() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let preview = Preview::from_value(
+		&json::from_str(
+			r#"{"images": [{"source": {"url": "https://preview.redd.it/a.jpg?width=1920&amp;s=1", "width": 1920, "height": 1080}, "resolutions": [{"url": "https://preview.redd.it/a.jpg?width=108&amp;s=2", "width": 108, "height": 60}, {"url": "https://preview.redd.it/a.jpg?width=640&amp;s=3", "width": 640, "height": 360}, {"url": "https://preview.redd.it/a.jpg?width=960&amp;s=4", "width": 960, "height": 540}], "id": "a"}], "enabled": true}"#,
+		)
+		.unwrap(),
+		&reddit,
+	)
+	.unwrap();
+	assert_eq!(preview.source.url, "https://preview.redd.it/a.jpg?width=1920&s=1");
+	assert_eq!(preview.resolutions.len(), 3);
+	assert_eq!(preview.fitting(500).width, 640);
+	assert_eq!(preview.fitting(2000), &preview.source);
+
+	assert_eq!(Thumbnail::from_value(&json::Value::from("self")), Some(Thumbnail::SelfPost));
+	assert_eq!(Thumbnail::from_value(&json::Value::from("nsfw")), Some(Thumbnail::Nsfw));
+	assert_eq!(Thumbnail::from_value(&json::Value::from("https://b.thumbs.redditmedia.com/x.jpg")), Some(Thumbnail::Url("https://b.thumbs.redditmedia.com/x.jpg".to_string())));
+	assert_eq!(Thumbnail::from_value(&json::Value::from("")), None);
+
+	// A post with a preview that can't be parsed is kept without it
+	let mut data = post_data("7le01h", &[]);
+	data["preview"] = json::from_str(r#"{"images": [], "enabled": false}"#).unwrap();
+	let post = Post::from_data(&data, &reddit, MAX_CROSSPOST_DEPTH).unwrap();
+	assert_eq!(post.id, "7le01h");
+	assert!(post.preview.is_none());
+}
+
+#[test(set_oc)]
+fn set_oc() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	match reddit.set_oc("t1_dr0plain", true).unwrap_err().downcast::<RedditError>() {
+		Ok(RedditError::BadRequest { status: None, .. }) => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+}
+
+fn post_data(id: &str, parents: &[json::Value]) -> json::Value {
+	let mut post: json::Value = json::from_str(&format!(
+		r#"{{"id": "{}", "title": "Hello", "author": "IntrepidPig", "subreddit": "pigasusland", "ups": 3, "downs": 0, "score": 3, "num_comments": 0, "url": "https://www.reddit.com/r/pigasusland/comments/{}/", "selftext": "", "stickied": false, "gilded": 0, "edited": false, "created_utc": 1513785600.0}}"#,
+		id, id
+	))
+	.unwrap();
+	if let Some(parent) = parents.first() {
+		post["crosspost_parent"] = json::Value::from(format!("t3_{}", parent["id"].as_str().unwrap()));
+		post["crosspost_parent_list"] = json::Value::from(parents.to_vec());
+	}
+	post
+}
+
+#[test(crosspost)]
+fn crosspost() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let root = post_data("root", &[]);
+	let middle = post_data("middle", &[root]);
+	let post = Post::from_data(&post_data("leaf", &[middle]), &reddit, 8).unwrap();
+	assert_eq!(post.crosspost_parent, Some("t3_middle".to_string()));
+	assert_eq!(post.original().id, "root");
+
+	let cut = Post::from_data(&post_data("leaf", &[post_data("middle", &[post_data("root", &[])])]), &reddit, 1).unwrap();
+	assert_eq!(cut.original().id, "middle");
+	assert_eq!(cut.crosspost_parent_list[0].crosspost_parent, Some("t3_root".to_string()));
+
+	let plain = Post::from_data(&post_data("plain", &[]), &reddit, 8).unwrap();
+	assert_eq!(plain.crosspost_parent, None);
+	assert_eq!(plain.original().id, "plain");
+
+	// A parent that can't be parsed is skipped, keeping the crosspost and the other parents
+	let mut removed = post_data("removed", &[]);
+	removed.as_object_mut().unwrap().remove("title");
+	let post = Post::from_data(&post_data("leaf", &[removed, post_data("root", &[])]), &reddit, 8).unwrap();
+	assert_eq!(post.id, "leaf");
+	assert_eq!(post.crosspost_parent_list.len(), 1);
+	assert_eq!(post.original().id, "root");
+}
+
+#[test(empty_listing)]
+fn empty_listing() {
+	use data::check_listing;
+	use hyper::StatusCode;
+	use net::error_from_response;
+	use std::collections::HashMap;
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+
+	let empty = check_listing(json::from_str(r#"{"kind": "Listing", "data": {"after": null, "dist": 0, "children": [], "before": null}}"#).unwrap(), "request").unwrap();
+	assert!(Listing::from_value(&empty["data"]["children"], "", &reddit).unwrap().is_empty());
+
+	match error_from_response("request", "/r/secretsub/.json", false, StatusCode::FORBIDDEN, &HashMap::new(), "response", r#"{"reason": "private", "message": "Forbidden", "error": 403}"#) {
+		RedditError::SubredditPrivate { .. } => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+
+	match check_listing(json::from_str("{}").unwrap(), "request").unwrap_err().downcast::<RedditError>() {
+		Ok(RedditError::BadResponse { .. }) => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+}
+
+#[test(empty_subreddit_listing)]
+fn empty_subreddit_listing() {
+	use data::empty_listing_error;
+
+	let empty: json::Value = json::from_str(r#"{"kind": "Listing", "data": {"after": null, "dist": 0, "children": [], "before": null}}"#).unwrap();
+	let about = |fields: &str| Ok(json::from_str::<json::Value>(&format!(r#"{{"kind": "t5", "data": {{"display_name": "secretsub", {}}}}}"#, fields)).unwrap());
+	let error = |listing: &json::Value, about: Result<json::Value, failure::Error>| empty_listing_error(listing, "secretsub", about).map(|e| e.downcast::<RedditError>().unwrap());
+
+	// A private subreddit that sends an empty listing instead of an error
+	match error(&empty, about(r#""subreddit_type": "private""#)) {
+		Some(RedditError::SubredditPrivate { sub }) => assert_eq!(sub, "secretsub"),
+		e => panic!("Unexpected error {:?}", e),
+	}
+	match error(&empty, Err(failure::Error::from(RedditError::SubredditBanned { sub: "secretsub".to_string() }))) {
+		Some(RedditError::SubredditBanned { .. }) => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+	let mut with_reason = empty.clone();
+	with_reason["reason"] = json::Value::from("banned");
+	match error(&with_reason, about(r#""subreddit_type": "public""#)) {
+		Some(RedditError::SubredditBanned { .. }) => {}
+		e => panic!("Unexpected error {:?}", e),
+	}
+
+	// Subreddits that can be read are just empty, also if the check itself fails
+	assert!(error(&empty, about(r#""subreddit_type": "public""#)).is_none());
+	assert!(error(&empty, about(r#""subreddit_type": "private", "user_is_contributor": true"#)).is_none());
+	assert!(error(&empty, Err(failure::Error::from(RedditError::Timeout { request: "request".to_string() }))).is_none());
+}
+
+#[test(poll_data)]
+fn poll_data() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut post = post_data("poll", &[]);
+	post["poll_data"] = json::from_str(r#"{"options": [{"id": "1", "text": "Yes", "vote_count": 10}, {"id": "2", "text": "No", "vote_count": 4}], "total_vote_count": 14, "voting_end_timestamp": 1513785600000, "user_selection": "2"}"#).unwrap();
+	let poll = Post::from_data(&post, &reddit, 8).unwrap().poll_data.unwrap();
+	assert_eq!(poll.options[0].vote_count, Some(10));
+	assert_eq!(poll.total_vote_count, 14);
+	assert_eq!(poll.voting_end.timestamp(), 1_513_785_600);
+	assert_eq!(poll.user_selection, Some("2".to_string()));
+	assert!(poll.is_closed());
+
+	let open = PollData::from_value(
+		&json::from_str(r#"{"options": [{"id": "1", "text": "Yes"}, {"id": "2", "text": "No", "vote_count": null}], "total_vote_count": 14, "voting_end_timestamp": 4102444800000, "user_selection": null}"#).unwrap(),
+		&reddit,
+	)
+	.unwrap();
+	assert_eq!(open.options[0].vote_count, None);
+	assert_eq!(open.options[1].vote_count, None);
+	assert_eq!(open.user_selection, None);
+	assert!(!open.is_closed());
+
+	assert!(Post::from_data(&post_data("plain", &[]), &reddit, 8).unwrap().poll_data.is_none());
+
+	// A malformed poll doesn't lose the post
+	post["poll_data"] = json::from_str(r#"{"options": "many", "total_vote_count": 14}"#).unwrap();
+	let post = Post::from_data(&post, &reddit, 8).unwrap();
+	assert_eq!(post.id, "poll");
+	assert!(post.poll_data.is_none());
+}
+
+#[test(user_list)]
+fn user_list() {
+	use data::user_list_names;
+
+	let blocked = json::from_str(r#"{"kind": "UserList", "data": {"children": [{"date": 1513785600.0, "rel_id": "r9_1abc", "name": "spammer", "id": "t2_abc"}, {"date": 1513785600.0, "rel_id": "r9_1abd", "name": "shouter", "id": "t2_abd"}]}}"#).unwrap();
+	assert_eq!(user_list_names(&blocked).unwrap(), vec!["spammer".to_string(), "shouter".to_string()]);
+
+	let empty = json::from_str(r#"{"kind": "UserList", "data": {"children": []}}"#).unwrap();
+	assert!(user_list_names(&empty).unwrap().is_empty());
+
+	let listing = json::from_str(r#"{"kind": "Listing", "data": {"children": []}}"#).unwrap();
+	assert!(user_list_names(&listing).is_err());
+}
+
+#[test(scope_infos)]
+fn scope_infos() {
+	use net::auth::scope_infos;
+
+	let scopes = scope_infos(&json::from_str(r#"{"identity": {"description": "Access my reddit username and signup date.", "id": "identity", "name": "My Identity"}, "read": {"description": "Access posts and comments through my account.", "id": "read", "name": "Read Content"}}"#).unwrap()).unwrap();
+	assert_eq!(scopes["identity"].name, "My Identity");
+	assert_eq!(scopes["read"].description, "Access posts and comments through my account.");
+	assert!(scope_infos(&json::from_str("[]").unwrap()).is_err());
+
+	assert_eq!(Scopes::all().names().len(), 20);
+	assert!(Scopes::empty().names().is_empty());
+}
+
+// Only run when REDDIT_CHECK_SCOPES is set, since new scopes on reddit shouldn't break the build
+#[test(available_scopes)]
+fn available_scopes() {
+	if std::env::var("REDDIT_CHECK_SCOPES").is_err() {
+		return;
+	}
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let scopes = reddit.available_scopes().unwrap();
+	let known = Scopes::all().names();
+	for name in &known {
+		assert!(scopes.contains_key(*name), "Scope {} is not supported by reddit", name);
+	}
+	for id in scopes.keys() {
+		assert!(known.contains(&id.as_str()), "Scope {} is missing from Scopes", id);
+	}
+}
+
+#[test(submit_result)]
+fn submit_result() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let submitted = json::from_str(r#"{"json": {"errors": [], "data": {"url": "https://www.reddit.com/r/pigasusland/comments/7le01h/test_post/", "drafts_count": 0, "id": "7le01h", "name": "t3_7le01h"}}}"#).unwrap();
+	let result = SubmitResult::from_value(&submitted, &reddit).unwrap();
+	assert_eq!(result.fullname, "t3_7le01h");
+	assert_eq!(result.url, "https://www.reddit.com/r/pigasusland/comments/7le01h/test_post/");
+	assert_eq!(result.send_replies, None);
+
+	let submitted = json::from_str(r#"{"json": {"errors": [], "data": {"url": "https://www.reddit.com/r/pigasusland/comments/7le01h/test_post/", "id": "7le01h", "name": "t3_7le01h", "send_replies": false}}}"#).unwrap();
+	assert_eq!(SubmitResult::from_value(&submitted, &reddit).unwrap().send_replies, Some(false));
+
+	assert!(SubmitResult::from_value(&json::from_str(r#"{"json": {"errors": []}}"#).unwrap(), &reddit).is_err());
+}
+
+#[test(bot_runner)]
+fn bot_runner() {
+	use bot::{run_handler, BotRunner, Handler};
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut failing: Handler<i32> = Box::new(|_, n| Err(format_err!("failed on {}", n)));
+	assert_eq!(run_handler(&reddit, &mut failing, 3).unwrap_err().to_string(), "failed on 3");
+
+	let mut panicking: Handler<i32> = Box::new(|_, n| panic!("panicked on {}", n));
+	assert_eq!(run_handler(&reddit, &mut panicking, 4).unwrap_err().to_string(), "Handler panicked: panicked on 4");
+
+	let mentions = Message::from_value(
+		&json::from_str(r#"{"kind": "t1", "data": {"id": "dr0abc", "name": "t1_dr0abc", "author": "IntrepidPig", "subject": "username mention", "body": "hi u/orcabot", "subreddit": "pigasusland", "context": "/r/pigasusland/comments/7le01h/_/dr0abc/?context=3", "was_comment": true, "new": true, "created_utc": 1513785600.0}}"#).unwrap(),
+		&reddit,
+	)
+	.unwrap();
+	assert_eq!(mentions.name, "t1_dr0abc");
+	assert!(mentions.was_comment);
+	assert_eq!(mentions.subreddit, Some("pigasusland".to_string()));
+
+	// A stopped runner returns without polling
+	let mut runner = BotRunner::new(reddit);
+	runner.on_comment("pigasusland", |_, _| Ok(())).on_mention(|_, _| Ok(()));
+	runner.handle().stop();
+	runner.run();
+
+	// New posts are polled from the new listing, not the hot one
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	assert_eq!(reddit.new_posts_url(&Target::from("pigasusland")).unwrap().as_str(), "https://www.reddit.com/r/pigasusland/new.json?limit=100");
+	assert_eq!(reddit.new_posts_url(&Target::Frontpage).unwrap().as_str(), "https://www.reddit.com/new.json?limit=100");
+	reddit.set_include_hidden(true);
+	assert_eq!(reddit.new_posts_url(&Target::from("pigasusland")).unwrap().as_str(), "https://www.reddit.com/r/pigasusland/new.json?limit=100&show=all");
+}
+
+#[test(bot_delivery)]
+fn bot_delivery() {
+	use bot::{deliver, new_posts, poll_order, Handler};
+	use std::cell::RefCell;
+	use std::rc::Rc;
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let handled = Rc::new(RefCell::new(Vec::new()));
+	let errors = Rc::new(RefCell::new(Vec::new()));
+	let record = handled.clone();
+	let mut handler: Handler<i32> = Box::new(move |_, n| {
+		record.borrow_mut().push(n);
+		if n == 4 {
+			Err(format_err!("failed on {}", n))
+		} else {
+			Ok(())
+		}
+	});
+	let record = errors.clone();
+	let mut on_error: Box<dyn FnMut(&failure::Error)> = Box::new(move |e| record.borrow_mut().push(e.to_string()));
+	let handle = StreamHandle::new();
+	let mut seen = SeenNames::new();
+	let items = |ns: &[i32]| ns.iter().map(|n| (format!("t1_{}", n), *n)).collect::<Vec<_>>();
+
+	// The first poll only primes the seen names
+	deliver(&reddit, items(&[1, 2]), &mut seen, false, &handle, &mut handler, &mut on_error);
+	assert!(handled.borrow().is_empty());
+
+	// Later polls dispatch the new items in order, once each, and report failed handlers
+	deliver(&reddit, items(&[2, 3, 4]), &mut seen, true, &handle, &mut handler, &mut on_error);
+	deliver(&reddit, items(&[3, 4, 5]), &mut seen, true, &handle, &mut handler, &mut on_error);
+	assert_eq!(*handled.borrow(), vec![3, 4, 5]);
+	assert_eq!(*errors.borrow(), vec!["failed on 4".to_string()]);
+
+	// Nothing is dispatched once the runner is stopped
+	handle.stop();
+	deliver(&reddit, items(&[6]), &mut seen, true, &handle, &mut handler, &mut on_error);
+	assert_eq!(handled.borrow().len(), 3);
+
+	// A post that doesn't parse is reported and skipped
+	let good = post_data("7le01h", &[]);
+	let listing: json::Value = json::from_str(&format!(r#"{{"kind": "Listing", "data": {{"children": [{{"kind": "t3", "data": {}}}, {{"kind": "t3", "data": {{"id": 5}}}}]}}}}"#, good)).unwrap();
+	let posts = new_posts(&listing, &reddit, &mut on_error);
+	assert_eq!(posts.iter().map(|post| post.0.as_str()).collect::<Vec<_>>(), vec!["t3_7le01h"]);
+	assert_eq!(errors.borrow().len(), 2);
+
+	// Each round starts with the next source
+	assert_eq!(poll_order(0, 3), vec![0, 1, 2]);
+	assert_eq!(poll_order(1, 3), vec![1, 2, 0]);
+	assert_eq!(poll_order(2, 3), vec![2, 0, 1]);
+	assert!(poll_order(0, 0).is_empty());
+}
+
+#[test(is_available)]
+fn is_available() {
+	use data::info_available;
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let info = |thing: json::Value| -> json::Value { json::from_str(&format!(r#"{{"kind": "Listing", "data": {{"children": [{}]}}}}"#, thing)).unwrap() };
+
+	assert!(info_available(&info(comment_fixture("dr0plain")), &reddit).unwrap());
+
+	let mut removed = comment_fixture("dr0removed");
+	removed["data"]["body"] = json::Value::from("[removed]");
+	assert!(!info_available(&info(removed), &reddit).unwrap());
+
+	let mut deleted = post_data("deleted", &[]);
+	deleted["author"] = json::Value::from("[deleted]");
+	assert!(!info_available(&info(json::from_str(&format!(r#"{{"kind": "t3", "data": {}}}"#, deleted)).unwrap()), &reddit).unwrap());
+	assert!(info_available(&info(json::from_str(&format!(r#"{{"kind": "t3", "data": {}}}"#, post_data("plain", &[]))).unwrap()), &reddit).unwrap());
+
+	assert!(!info_available(&json::from_str(r#"{"kind": "Listing", "data": {"children": []}}"#).unwrap(), &reddit).unwrap());
+}
+
+#[test(archive)]
+fn archive() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut post = Post::from_data(&post_data("7le01h", &[]), &reddit, 8).unwrap();
+
+	// 20 top level comments with 3 levels of 4 replies each
+	fn replies(id: &str, depth: usize, reddit: &App) -> Listing<Comment> {
+		let mut listing = Listing::new();
+		for i in 0..4 {
+			let mut comment = Comment::from_value(&comment_fixture(&format!("{}_{}", id, i)), reddit).unwrap();
+			comment.edited = if i == 0 { comment.created_utc } else { None };
+			comment.author = if i == 1 { None } else { comment.author };
+			if depth > 0 {
+				comment.replies = replies(&comment.id, depth - 1, reddit);
+			}
+			listing.children.push_back(comment);
+		}
+		listing
+	}
+	for i in 0..20 {
+		let mut comment = Comment::from_value(&comment_fixture(&format!("c{}", i)), &reddit).unwrap();
+		comment.body = format!("Comment {} with \"quotes\" & unicode ✓", i);
+		comment.replies = replies(&comment.id, 2, &reddit);
+		post.comments.children.push_back(comment);
+	}
+	post.num_comments = 20 * (1 + 4 + 16 + 64) + 3;
+
+	let tree = CommentTree::new(post);
+	assert_eq!(tree.missing, 3);
+	let archived = tree.to_archive_json();
+	let restored = CommentTree::from_archive_json(&archived).unwrap();
+	assert_eq!(restored.to_archive_json(), archived);
+	assert_eq!(restored.fetched, tree.fetched);
+	assert_eq!(restored.missing, 3);
+	assert_eq!(restored.post.title, tree.post.title);
+
+	let ids = |tree: &CommentTree| tree.post.comments.clone().traverse().into_iter().map(|c| (c.id, c.body, c.author, c.edited)).collect::<Vec<_>>();
+	assert_eq!(ids(&restored).len(), 20 * (1 + 4 + 16 + 64));
+	assert_eq!(ids(&restored), ids(&tree));
+
+	assert!(CommentTree::from_archive_json(&archived.replace(r#""version":1"#, r#""version":2"#)).is_err());
+}
+
+#[test(json_urls)]
+fn json_urls() {
+	use hyper::Method;
+	use net::auth::token_url;
+	use net::{json_url, EndpointClass};
+	use url::Url;
+
+	assert_eq!(json_url("https://www.reddit.com/r/rust"), "https://www.reddit.com/r/rust.json");
+	assert_eq!(json_url("https://www.reddit.com/r/rust/"), "https://www.reddit.com/r/rust.json");
+	assert_eq!(json_url("https://www.reddit.com/r/rust/.json"), "https://www.reddit.com/r/rust.json");
+	assert_eq!(json_url("https://www.reddit.com/r/rust.json"), "https://www.reddit.com/r/rust.json");
+	assert_eq!(json_url("https://oauth.reddit.com/api/v1/me"), "https://oauth.reddit.com/api/v1/me.json");
+	assert_eq!(json_url("https://oauth.reddit.com/api/mod/conversations/1a2b3/highlight"), "https://oauth.reddit.com/api/mod/conversations/1a2b3/highlight.json");
+	assert_eq!(json_url("https://oauth.reddit.com/r/rust/api/widgets"), "https://oauth.reddit.com/r/rust/api/widgets.json");
+
+	// Every kind of target, with and without a sort
+	let targets = vec![Target::from("rust"), Target::from("all"), Target::from("all-funny-pics"), Target::Popular, Target::Frontpage];
+	for target in targets {
+		for sort in &["", "/hot", "/new", "/search", "/comments"] {
+			let url = json_url(&format!("https://www.reddit.com{}{}", target.path(), sort));
+			assert!(!url.contains(char::is_whitespace), "{}", url);
+			assert!(!url.contains("/.json") || url == "https://www.reddit.com/.json", "{}", url);
+			assert!(!url.contains("//r") && url.ends_with(".json"), "{}", url);
+			assert_eq!(Url::parse(&url).unwrap().as_str(), url);
+		}
+	}
+	assert_eq!(json_url(&format!("https://www.reddit.com{}", Target::Frontpage.path())), "https://www.reddit.com/.json");
+	assert_eq!(json_url(&format!("https://www.reddit.com{}/hot", Target::Frontpage.path())), "https://www.reddit.com/hot.json");
+
+	// Access tokens, which are requested without the connection and so without its base url
+	assert_eq!(token_url(true), "https://www.reddit.com/api/v1/access_token.json");
+	assert_eq!(token_url(false), "https://ssl.reddit.com/api/v1/access_token.json");
+	for url in &[token_url(true), token_url(false)] {
+		let path = Url::parse(url).unwrap().path().to_string();
+		assert_eq!(EndpointClass::of(&Method::POST, &path), EndpointClass::Auth);
+	}
+}
+
+#[test(duplicate_guard)]
+fn duplicate_guard() {
+	use chrono::{Duration, TimeZone, Utc};
+	use data::find_duplicate;
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let submitted = |posts: Vec<json::Value>| -> json::Value { json::from_str(&format!(r#"{{"kind": "Listing", "data": {{"children": [{}]}}}}"#, posts.iter().map(|post| format!(r#"{{"kind": "t3", "data": {}}}"#, post)).collect::<Vec<_>>().join(", "))).unwrap() };
+	let created = Utc.timestamp_opt(1_513_785_600, 0).unwrap();
+
+	// The earlier attempt succeeded even though its response was lost, so it is in the listing
+	let mut other_sub = post_data("other", &[]);
+	other_sub["subreddit"] = json::Value::from("rust");
+	let listing = submitted(vec![other_sub, post_data("earlier", &[])]);
+	let existing = find_duplicate(&listing, "r/PigasusLand", "Hello", Some(created - Duration::hours(1)), &reddit).unwrap().unwrap();
+	assert_eq!(existing.id, "earlier");
+	assert!(find_duplicate(&listing, "pigasusland", "Hello", None, &reddit).unwrap().is_some());
+
+	assert!(find_duplicate(&listing, "pigasusland", "Hello", Some(created + Duration::hours(1)), &reddit).unwrap().is_none());
+	assert!(find_duplicate(&listing, "pigasusland", "Hello again", None, &reddit).unwrap().is_none());
+	assert!(find_duplicate(&submitted(Vec::new()), "pigasusland", "Hello", None, &reddit).unwrap().is_none());
+
+	let error = RedditError::AlreadySubmitted { existing: Box::new(existing) };
+	assert_eq!(error.status_code(), None);
+}
+
+#[test(post_stats)]
+fn post_stats() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut post = post_data("7le01h", &[]);
+	post["upvote_ratio"] = json::Value::from(0.93);
+	post["num_comments"] = json::Value::from(12);
+	let stats = PostStats::from_value(&json::from_str(&format!(r#"{{"kind": "t3", "data": {}}}"#, post)).unwrap(), &reddit).unwrap();
+	assert_eq!(stats.num_comments, 12);
+	assert_eq!(stats.score, 3);
+	assert_eq!(stats.upvote_ratio, 0.93);
+	assert_eq!(stats.created_utc.unwrap().timestamp(), 1_513_785_600);
+}
+
+#[test(comment_cooldown)]
+fn comment_cooldown() {
+	use std::time::Instant;
+
+	let start = Instant::now();
+	let mut cooldown = CommentCooldown::default();
+	assert_eq!(cooldown.estimated_wait_at(start), None);
+
+	cooldown.record_ratelimit(Duration::from_secs(540), start);
+	assert_eq!(cooldown.estimated_wait_at(start + Duration::from_secs(40)), Some(Duration::from_secs(500)));
+	assert_eq!(cooldown.estimated_wait_at(start + Duration::from_secs(600)), None);
+
+	// After a successful comment the next one is expected to wait the whole cooldown
+	cooldown.record_success(start + Duration::from_secs(600));
+	assert_eq!(cooldown.estimated_wait_at(start + Duration::from_secs(660)), Some(Duration::from_secs(480)));
+	cooldown.record_ratelimit(Duration::from_secs(60), start + Duration::from_secs(660));
+	assert_eq!(cooldown.cooldown, Some(Duration::from_secs(540)));
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	assert_eq!(reddit.estimated_comment_wait("pigasusland"), None);
+	reddit.record_comment_cooldown("PigasusLand", |cooldown| cooldown.record_ratelimit(Duration::from_secs(600), Instant::now()));
+	assert!(reddit.estimated_comment_wait("pigasusland").unwrap() > Duration::from_secs(590));
+	reddit.reset_comment_cooldowns(Some("pigasusland"));
+	assert_eq!(reddit.comment_cooldown("pigasusland"), None);
+}
+
+#[test(geo_filter)]
+fn geo_filter() {
+	assert!(Target::Popular.check_geo_filter("GLOBAL").is_ok());
+	assert!(Target::from("all").check_geo_filter("DE").is_ok());
+	assert!(Target::Popular.check_geo_filter("US_WA").is_ok());
+	for (target, geo_filter) in [(Target::Popular, "XX"), (Target::Popular, "de"), (Target::from("rust"), "DE"), (Target::Frontpage, "GLOBAL")] {
+		match target.check_geo_filter(geo_filter).unwrap_err().downcast::<RedditError>() {
+			Ok(RedditError::BadRequest { status: None, .. }) => {}
+			e => panic!("Unexpected error {:?}", e),
+		}
+	}
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	assert!(reddit.get_regional_posts("rust", Sort::Hot, Some("DE")).is_err());
+}
+
+#[test(settings_diff)]
+fn settings_diff() {
+	use std::collections::HashMap;
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let current = SubredditSettings::from_value(
+		&json::from_str(
+			r#"{"kind": "subreddit_settings", "data": {"title": "Pigasus", "subreddit_id": "t5_abc", "subreddit_type": "public", "content_options": "any", "over_18": false, "description": null, "wiki_edit_age": 5, "allow_images": true, "spam_links": "high", "suggested_comment_sort": null}}"#,
+		)
+		.unwrap(),
+		&reddit,
+	)
+	.unwrap();
+	assert!(current.diff(&current.clone()).is_empty());
+
+	let mut desired = current.clone();
+	desired.title = "Pigasus Land".to_string();
+	desired.over_18 = true;
+	let changes = current.diff(&desired);
+	assert_eq!(changes.len(), 2);
+	let title = SettingChange {
+		field: "title".to_string(),
+		old: json::Value::from("Pigasus"),
+		new: json::Value::from("Pigasus Land"),
+	};
+	assert_eq!(changes[0], title);
+	assert_eq!(changes[1].field, "over_18");
+
+	let form: HashMap<_, _> = current.site_admin_form(&changes).into_iter().collect();
+	assert_eq!(form["title"], "Pigasus Land");
+	assert_eq!(form["over_18"], "true");
+	// Settings that aren't changed keep their current values
+	assert_eq!(form["sr"], "t5_abc");
+	assert_eq!(form["type"], "public");
+	assert_eq!(form["link_type"], "any");
+	assert_eq!(form["spam_links"], "high");
+	assert_eq!(form["wiki_edit_age"], "5");
+	assert_eq!(form["allow_images"], "true");
+	assert_eq!(form["api_type"], "json");
+	assert!(!form.contains_key("description"));
+	assert!(!form.contains_key("suggested_comment_sort"));
+}
+
+#[test(settings_form_keys)]
+fn settings_form_keys() {
+	use std::collections::HashMap;
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let current = SubredditSettings::from_value(
+		&json::from_str(r#"{"kind": "subreddit_settings", "data": {"title": "Pigasus", "subreddit_type": "public", "content_options": "any", "over_18": false, "language": "de", "default_set": false, "header_hover_text": "Oink"}}"#).unwrap(),
+		&reddit,
+	)
+	.unwrap();
+
+	// Settings whose json key differs from their form key are sent under the form key unchanged
+	let form: HashMap<_, _> = current.site_admin_form(&[]).into_iter().collect();
+	assert_eq!(form["lang"], "de");
+	assert_eq!(form["allow_top"], "false");
+	assert_eq!(form["header-title"], "Oink");
+	assert!(!form.contains_key("language"));
+	assert!(!form.contains_key("default_set"));
+	assert!(!form.contains_key("header_hover_text"));
+}
+
+#[test(comment_context)]
+fn comment_context() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	match reddit.get_comment_context("t3_7le01h", "drh5ok6", 9).unwrap_err().downcast::<RedditError>() {
+		Ok(RedditError::BadRequest { status: None, .. }) => (),
+		other => panic!("Context above 8 was not rejected: {:?}", other),
+	}
+}
+
+#[test(comment_split)]
+fn comment_split() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let comments: Vec<Comment> = (0..5).map(|i| Comment::from_value(&comment_fixture(&format!("c{}", i)), &reddit).unwrap()).collect();
+	let handle = StreamHandle::new();
+	let mut receivers = split_comments(comments.into_iter(), handle.clone(), 3);
+	let unused = receivers.pop().unwrap();
+	let mut slow = receivers.pop().unwrap();
+	let mut fast = receivers.pop().unwrap();
+	slow.set_capacity(2);
+
+	let ids: Vec<String> = fast.by_ref().take(5).map(|comment| comment.id).collect();
+	assert_eq!(ids, vec!["c0", "c1", "c2", "c3", "c4"]);
+	assert_eq!(fast.dropped(), 0);
+	// The slow receiver only kept the newest comments, the others didn't wait for it
+	assert_eq!(slow.lag(), 2);
+	assert_eq!(slow.dropped(), 3);
+	assert_eq!(unused.lag(), 5);
+	let ids: Vec<String> = slow.by_ref().map(|comment| comment.id).collect();
+	assert_eq!(ids, vec!["c3", "c4"]);
+	assert!(fast.next().is_none());
+
+	drop(unused);
+	drop(slow);
+	assert!(!handle.is_stopped());
+	drop(fast);
+	assert!(handle.is_stopped());
+}
+
+#[cfg(feature = "dangerous")]
+#[test(accept_invalid_certs)]
+fn accept_invalid_certs() {
+	let mut conn = Connection::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	conn.danger_accept_invalid_certs(true).unwrap();
+	conn.danger_accept_invalid_certs(false).unwrap();
+}
+
+#[test(profile_subreddits)]
+fn profile_subreddits() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let listing: json::Value = json::from_str(
+		r#"{"kind": "Listing", "data": {"children": [
+			{"kind": "t5", "data": {"display_name": "rust", "name": "t5_2s7lj", "title": "Rust", "subreddit_type": "public", "subscribers": 200000, "over18": false}},
+			{"kind": "t5", "data": {"display_name": "u_IntrepidPig", "name": "t5_abc", "title": "", "subreddit_type": "user", "subscribers": 3, "over_18": true}}
+		]}}"#,
+	)
+	.unwrap();
+	let subs: Vec<Subreddit> = listing["data"]["children"].as_array().unwrap().iter().map(|sub| Subreddit::from_value(sub, &reddit).unwrap()).collect();
+	assert!(!subs[0].is_user_profile());
+	assert_eq!(subs[0].profile_user(), None);
+	assert!(subs[1].is_user_profile());
+	assert_eq!(subs[1].profile_user(), Some("IntrepidPig"));
+	assert!(subs[1].over_18);
+
+	let entry = AutocompleteEntry::from_value(&listing["data"]["children"][1], &reddit).unwrap();
+	assert!(entry.is_profile);
+	assert_eq!(entry.name, "IntrepidPig");
+}
+
+#[test(flair_and_approve)]
+fn flair_and_approve() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+
+	// Without authorization flairing fails before a request is sent, so approving isn't tried
+	let error = reddit.flair_and_approve("pigasusland", "t3_7l2wn9", "template").unwrap_err();
+	let description = error.to_string();
+	match error.downcast::<RedditError>() {
+		Ok(RedditError::StepFailed { step, error }) => {
+			assert_eq!(step, "flair");
+			assert!(matches!(error.downcast_ref::<RedditError>(), Some(RedditError::Forbidden { .. })));
+			assert_eq!(description, format!("Step \"flair\" failed: {}", error));
+		}
+		e => panic!("Unexpected error {:?}", e),
+	}
+	assert_eq!(reddit.conn().request_stats().total, 0);
+}
+
+#[test(subreddit_counts)]
+fn subreddit_counts() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let sub = |counts: &str| Subreddit::from_value(&json::from_str(&format!(r#"{{"kind": "t5", "data": {{"display_name": "rust", "name": "t5_2s7lj", "title": "Rust", "subreddit_type": "public"{}}}}}"#, counts)).unwrap(), &reddit).unwrap();
+
+	let rust = sub(r#", "subscribers": 200000, "accounts_active": 1200"#);
+	assert_eq!(rust.subscribers, Some(200_000));
+	assert_eq!(rust.accounts_active, Some(1200));
+
+	// Some listings only have the newer name of the count
+	let rust = sub(r#", "subscribers": 200000, "active_user_count": 900"#);
+	assert_eq!(rust.accounts_active, Some(900));
+	let rust = sub(r#", "accounts_active": 1200, "active_user_count": 900"#);
+	assert_eq!(rust.accounts_active, Some(1200));
+
+	let rust = sub("");
+	assert_eq!(rust.subscribers, None);
+	assert_eq!(rust.accounts_active, None);
+}
+
+#[test(subreddits_about)]
+fn subreddits_about() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let sub = |name: &str| format!(r#"{{"kind": "t5", "data": {{"display_name": "{}", "name": "t5_{}", "title": "", "subreddit_type": "public", "over18": false}}}}"#, name, name.to_lowercase());
+	let first: json::Value = json::from_str(&format!(r#"{{"kind": "Listing", "data": {{"children": [{}, {}]}}}}"#, sub("rust"), sub("AskReddit"))).unwrap();
+	let second: json::Value = json::from_str(&format!(r#"{{"kind": "Listing", "data": {{"children": [{}]}}}}"#, sub("pigasusland"))).unwrap();
+
+	let subs = data::subreddits_by_name(&[first, second], &["pigasusland", "askreddit", "doesnotexist", "rust"], &reddit).unwrap();
+	let names: Vec<&str> = subs.iter().map(|sub| sub.display_name.as_str()).collect();
+	assert_eq!(names, vec!["pigasusland", "AskReddit", "rust"]);
+}
+
+#[test(profile_post)]
+fn profile_post() {
+	let reddit = init_reddit();
+
+	let profile = reddit.profile_subreddit().unwrap();
+	assert!(profile.starts_with("u_"));
+	let result = reddit.submit_self_to_profile("Profile post", "Posted to the profile", false).unwrap();
+	reddit.get_post_stats(&result.fullname).unwrap();
+	assert!(result.url.contains(&format!("/user/{}/", profile.trim_start_matches("u_"))));
+}
+
+#[test(gildings)]
+fn gildings() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let gildings = Gildings::from_value(
+		&json::from_str(
+			r#"{"kind": "Listing", "data": {"children": [
+				{"kind": "award", "data": {"id": "gid_2", "name": "Gold", "coin_price": 500, "icon_url": "https://www.redditstatic.com/gold.png?a=1&amp;b=2", "thing_id": "t1_drh5ok6", "created_utc": 1513785600.0}},
+				{"kind": "award", "data": {"id": "gid_1", "name": "Silver", "coin_price": 100, "icon_url": "https://www.redditstatic.com/silver.png"}}
+			]}}"#,
+		)
+		.unwrap(),
+		&reddit,
+	)
+	.unwrap();
+	assert_eq!(gildings.given.len(), 2);
+	assert_eq!(gildings.given[0].award.icon_url, "https://www.redditstatic.com/gold.png?a=1&b=2");
+	assert_eq!(gildings.given[0].thing, Some("t1_drh5ok6".to_string()));
+	assert_eq!(gildings.given[0].created_utc.unwrap().timestamp(), 1_513_785_600);
+	assert_eq!(gildings.given[1].thing, None);
+	assert_eq!(gildings.total_coins(), 600);
+}
+
+#[test(trophies)]
+fn trophies() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let trophies = data::trophy_list(
+		&json::from_str(
+			r#"{"kind": "TrophyList", "data": {"trophies": [
+				{"kind": "t6", "data": {"icon_70": "https://www.redditstatic.com/awards2/3_year_club-70.png?a=1&amp;b=2", "granted_at": 1513785600, "url": null, "icon_40": "https://www.redditstatic.com/awards2/3_year_club-40.png", "name": "Three-Year Club", "award_id": null, "id": null, "description": null}},
+				{"kind": "t6", "data": {"icon_70": null, "granted_at": null, "icon_40": "https://www.redditstatic.com/awards2/verified_email-40.png", "name": "Verified Email", "award_id": "o", "id": "1qr5eq", "description": ""}}
+			]}}"#,
+		)
+		.unwrap(),
+		&reddit,
+	)
+	.unwrap();
+	assert_eq!(trophies.len(), 2);
+	assert_eq!(trophies[0].name, "Three-Year Club");
+	assert_eq!(trophies[0].icon_url, Some("https://www.redditstatic.com/awards2/3_year_club-70.png?a=1&b=2".to_string()));
+	assert_eq!(trophies[0].granted_at.unwrap().timestamp(), 1_513_785_600);
+	assert_eq!(trophies[0].award_id, None);
+	assert_eq!(trophies[1].icon_url, Some("https://www.redditstatic.com/awards2/verified_email-40.png".to_string()));
+	assert_eq!(trophies[1].description, None);
+	assert_eq!(trophies[1].award_id, Some("o".to_string()));
+	assert!(trophies[1].granted_at.is_none());
+}
+
+#[test(subreddit_assets)]
+fn subreddit_assets() {
+	let about = json::from_str(
+		r#"{"kind": "t5", "data": {
+			"icon_img": "",
+			"community_icon": "https://styles.redditmedia.com/t5_2s7lj/styles/communityIcon_5ejpqs.png?width=256&amp;s=5b0c",
+			"banner_img": null,
+			"banner_background_image": "https://styles.redditmedia.com/t5_2s7lj/styles/bannerBackgroundImage_7glkb.JPG",
+			"header_img": "https://b.thumbs.redditmedia.com/header"
+		}}"#,
+	)
+	.unwrap();
+	let stylesheet = json::from_str(
+		r#"{"kind": "stylesheet", "data": {"images": [
+			{"url": "//b.thumbs.redditmedia.com/xyz.jpg", "link": "url(%%ferris%%)", "name": "ferris"},
+			{"url": "https://b.thumbs.redditmedia.com/abc.gif", "link": "url(%%../up%%)", "name": "../up"},
+			{"url": "https://b.thumbs.redditmedia.com/def.gif", "link": "url(%%___up%%)", "name": "___up"}
+		]}}"#,
+	)
+	.unwrap();
+
+	let assets = data::subreddit_assets(&about, &stylesheet);
+	let files: Vec<(AssetKind, &str, &str)> = assets.iter().map(|asset| (asset.kind, asset.url.as_str(), asset.file_name.as_str())).collect();
+	assert_eq!(
+		files,
+		vec![
+			(AssetKind::CommunityIcon, "https://styles.redditmedia.com/t5_2s7lj/styles/communityIcon_5ejpqs.png?width=256&s=5b0c", "community_icon.png"),
+			(AssetKind::BannerBackground, "https://styles.redditmedia.com/t5_2s7lj/styles/bannerBackgroundImage_7glkb.JPG", "banner_background.jpg"),
+			(AssetKind::Header, "https://b.thumbs.redditmedia.com/header", "header.png"),
+			(AssetKind::StylesheetImage, "https://b.thumbs.redditmedia.com/xyz.jpg", "stylesheet/ferris.jpg"),
+			(AssetKind::StylesheetImage, "https://b.thumbs.redditmedia.com/abc.gif", "stylesheet/___up.gif"),
+			(AssetKind::StylesheetImage, "https://b.thumbs.redditmedia.com/def.gif", "stylesheet/___up_2.gif"),
+		]
+	);
+
+	// Without a stylesheet the images of the about are still listed
+	assert_eq!(data::subreddit_assets(&about, &json::Value::Null).len(), 3);
+}
+
+#[test(ids)]
+fn ids() {
+	use app::ids::{self, Kind};
+	use rand::{Rng, SeedableRng, XorShiftRng};
+
+	// Seeded, so a failure can be reproduced
+	const BASE36: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+	let mut rng = XorShiftRng::from_seed([0x5eed, 0x9e37_79b9, 1, 2]);
+	let random_id = |rng: &mut XorShiftRng| {
+		let len = rng.gen_range(1, 14);
+		(0..len).map(|_| BASE36[rng.gen_range(0, BASE36.len())] as char).collect::<String>()
+	};
+	let rejected = |result: Result<String, failure::Error>, param: &str| match result.map_err(|e| e.downcast::<RedditError>()) {
+		Err(Ok(RedditError::BadRequest { request, status: None, .. })) => assert!(request.contains(param), "{} doesn't name {}", request, param),
+		other => panic!("Not rejected: {:?}", other),
+	};
+
+	for _ in 0..500 {
+		let id = random_id(&mut rng);
+		let fullname = format!("t3_{}", id);
+		assert_eq!(ids::bare_id("post", Kind::Link, &id).unwrap(), id);
+		assert_eq!(ids::bare_id("post", Kind::Link, &fullname).unwrap(), id);
+		assert_eq!(ids::fullname("post", Kind::Link, &id).unwrap(), fullname);
+		assert_eq!(ids::fullname("post", Kind::Link, &fullname).unwrap(), fullname);
+		assert_eq!(ids::fullname_of("thing", &[Kind::Link, Kind::Comment], &fullname).unwrap(), fullname);
+
+		// A fullname of another kind can't be converted, and an id could be any kind
+		rejected(ids::fullname("post", Kind::Link, &format!("t1_{}", id)), "post");
+		rejected(ids::fullname_of("thing", &[Kind::Link, Kind::Comment], &id), "thing");
+		assert_eq!(ids::fullname_of("thing", &[Kind::Comment], &id).unwrap(), format!("t1_{}", id));
+
+		// Anything that isn't lowercase base36 of at most 13 digits is not an id
+		let mut invalid = id.clone();
+		let pos = rng.gen_range(0, invalid.len() + 1);
+		invalid.insert(pos, *rng.choose(&['A', '.', ' ', ',', '/', '-']).unwrap());
+		rejected(ids::fullname("post", Kind::Link, &invalid), "post");
+		rejected(ids::fullname("post", Kind::Link, &format!("t3_{}", invalid)), "post");
+		rejected(ids::fullname("post", Kind::Link, &format!("{}{}", id, "0".repeat(14 - id.len()))), "post");
+	}
+	rejected(ids::fullname("post", Kind::Link, ""), "post");
+	rejected(ids::fullname("post", Kind::Link, "t3_"), "post");
+	assert_eq!(ids::parse_fullname("t5_2qh1i"), Some((Kind::Subreddit, "2qh1i")));
+	assert_eq!(ids::bare_id_list("comments", Kind::Comment, &["t1_drh5ok6", "dr0plain"]).unwrap(), "drh5ok6,dr0plain");
+	rejected(ids::bare_id_list("comments", Kind::Comment, &["drh5ok6", "t3_7le01h"]), "comments");
+	assert_eq!(ids::plain_id("id", "1a2b3").unwrap(), "1a2b3");
+	rejected(ids::plain_id("id", "t3_1a2b3").map(|id| id.to_string()), "id");
+
+	// Methods check their ids before sending anything
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	rejected(reddit.get_comment_context("t3_7le01h", "t3_drh5ok6", 3).map(|_| String::new()), "comment_id");
+	rejected(reddit.delete_message("t1_drh5ok6").map(|_| String::new()), "fullname");
+	rejected(reddit.modmail_highlight("abc/../../me", true).map(|_| String::new()), "id");
+	// Streams are checked when they are created, not on every poll
+	rejected(reddit.watch_post_comments("t1_drh5ok6").map(|_| String::new()), "post");
+	assert_eq!(reddit.conn().request_stats().total, 0);
+}
+
+#[test(listing_dist)]
+fn listing_dist() {
+	let envelope: json::Value = json::from_str(r#"{"after": null, "dist": 2, "modhash": "", "geo_filter": "GB", "children": []}"#).unwrap();
+	let mut listing: Listing<u32> = Listing::new();
+	assert_eq!(listing.verify_dist(), Ok(()));
+
+	listing.read_envelope(&envelope);
+	assert_eq!(listing.dist, Some(2));
+	assert_eq!(listing.geo_filter, Some("GB".to_string()));
+	assert_eq!(listing.modhash, None);
+	listing.children.push_back(1);
+	assert_eq!(listing.verify_dist(), Err(errors::DistMismatch { dist: 2, parsed: 1 }));
+	listing.children.push_back(2);
+	assert_eq!(listing.verify_dist(), Ok(()));
+}
+
+#[test(listing_skipped_children)]
+fn listing_skipped_children() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let resp: json::Value = json::from_str(
+		r#"{"kind": "Listing", "data": {"after": null, "dist": 2, "children": [
+			{"kind": "t4", "data": {"id": "abc", "name": "t4_abc", "author": "IntrepidPig", "dest": "orcabot", "subject": "hi", "body": "hello", "was_comment": false, "new": true, "created_utc": 1513785600.0}},
+			{"kind": "t4", "data": {"id": 5}}
+		]}}"#,
+	)
+	.unwrap();
+
+	// The child that doesn't parse is skipped, which the dist reveals
+	let mut messages: Listing<Message> = Listing::new();
+	messages.read_envelope(&resp["data"]);
+	for child in resp["data"]["children"].as_array().unwrap() {
+		messages.push_parsed(Message::from_value(child, &reddit));
+	}
+	assert_eq!(messages.children.len(), 1);
+	assert_eq!(messages.verify_dist(), Err(errors::DistMismatch { dist: 2, parsed: 1 }));
+
+	// The dist of paged listings is summed over the pages
+	let mut pages = Pages::new(&reddit, "https://oauth.reddit.com/r/pigasusland/about/banned/.json", Vec::new());
+	pages.record_page(&json::from_str(r#"{"kind": "Listing", "data": {"after": "rb_b", "dist": 2, "children": [{"name": "a"}, {"name": "b"}]}}"#).unwrap());
+	pages.record_page(&json::from_str(r#"{"kind": "Listing", "data": {"after": null, "dist": 1, "children": [{"name": "c"}]}}"#).unwrap());
+	assert_eq!(pages.dist(), Some(3));
+	pages.record_page(&json::from_str(r#"{"kind": "Listing", "data": {"after": null, "children": []}}"#).unwrap());
+	assert_eq!(pages.dist(), None);
+}
+
+#[test(crosspost_targets)]
+fn crosspost_targets() {
+	let sub = |fields: &str| json::from_str::<json::Value>(&format!(r#"{{"display_name": "rust", "subreddit_type": "public", {}}}"#, fields)).unwrap();
+
+	assert!(data::can_crosspost_to(&sub(r#""is_crosspostable_subreddit": true, "submission_type": "any""#)));
+	assert!(data::can_crosspost_to(&sub(r#""submission_type": "link""#)));
+	assert!(!data::can_crosspost_to(&sub(r#""is_crosspostable_subreddit": false"#)));
+	assert!(!data::can_crosspost_to(&sub(r#""submission_type": "self""#)));
+	assert!(!data::can_crosspost_to(&sub(r#""user_is_banned": true"#)));
+	assert!(!data::can_crosspost_to(&sub(r#""subreddit_type": "restricted""#)));
+	assert!(data::can_crosspost_to(&sub(r#""subreddit_type": "restricted", "user_is_contributor": true"#)));
+}
+
+#[test(top_posts)]
+fn top_posts() {
+	use chrono::{TimeZone, Utc};
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let thing = |id: &str, score: i64, created: f64, stickied: bool| {
+		let mut data = post_data(id, &[]);
+		data["score"] = json::Value::from(score);
+		data["created_utc"] = json::Value::from(created);
+		data["stickied"] = json::Value::from(stickied);
+		Ok(json::from_str(&format!(r#"{{"kind": "t3", "data": {}}}"#, data)).unwrap())
+	};
+	let since = Utc.timestamp_opt(1000, 0).unwrap();
+
+	// Newest first, with an old stickied post before the end of the window
+	let new = vec![
+		thing("d", 5, 1400.0, false),
+		thing("old_sticky", 99, 10.0, true),
+		thing("c", 7, 1300.0, false),
+		thing("b", 5, 1200.0, false),
+		thing("a", 1, 900.0, false),
+		thing("never", 100, 1500.0, false),
+	];
+	let mut posts = data::posts_since(new.into_iter(), since, &reddit).unwrap();
+	let ids: Vec<&str> = posts.iter().map(|post| post.id.as_str()).collect();
+	assert_eq!(ids, vec!["d", "c", "b"]);
+
+	data::rank_by_score(&mut posts);
+	let ids: Vec<&str> = posts.iter().map(|post| post.id.as_str()).collect();
+	assert_eq!(ids, vec!["c", "b", "d"]);
+}
+
+#[test(latency_stats)]
+fn latency_stats() {
+	use hyper::Method;
+	use net::LatencyTracker;
+
+	assert_eq!(EndpointClass::of(&Method::GET, "/r/rust/new/.json"), EndpointClass::Listing);
+	assert_eq!(EndpointClass::of(&Method::POST, "/api/comment/.json"), EndpointClass::Mutation);
+	assert_eq!(EndpointClass::of(&Method::POST, "/api/v1/access_token/.json"), EndpointClass::Auth);
+
+	let mut tracker = LatencyTracker::new(20);
+	for millis in 1..=25 {
+		tracker.record(EndpointClass::Listing, Duration::from_millis(millis));
+	}
+	tracker.record(EndpointClass::Auth, Duration::from_millis(300));
+
+	// Only the last 20 listings are in the window
+	let stats = tracker.stats();
+	let listing = stats[&EndpointClass::Listing];
+	assert_eq!(listing.count, 20);
+	assert_eq!(listing.p50, Duration::from_millis(15));
+	assert_eq!(listing.p95, Duration::from_millis(24));
+	assert_eq!(listing.max, Duration::from_millis(25));
+	let auth = stats[&EndpointClass::Auth];
+	assert_eq!((auth.count, auth.p50, auth.p95, auth.max), (1, Duration::from_millis(300), Duration::from_millis(300), Duration::from_millis(300)));
+	assert!(!stats.contains_key(&EndpointClass::Mutation));
+
+	tracker.reset();
+	assert!(tracker.stats().is_empty());
+
+	// Tracking is opt-in
+	let conn = Connection::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	assert!(conn.latency_stats().is_empty());
+	conn.track_latency(10);
+	conn.reset_latency_stats();
+	assert!(conn.latency_stats().is_empty());
+}
+
+#[test(self_test_report)]
+fn self_test_report() {
+	use net::check_ratelimit;
+
+	assert!(check_ratelimit(Some(598), Some(Duration::from_secs(300))).is_ok());
+	assert!(check_ratelimit(None, None).is_err());
+	assert!(check_ratelimit(Some(0), Some(Duration::from_secs(300))).is_err());
+	assert!(check_ratelimit(Some(598), Some(Duration::from_secs(3600))).is_err());
+
+	let passed = SelfTestStep::run("anonymous_read", || Ok(()));
+	let failed = SelfTestStep::run("token", || Err(failure::Error::from(RedditError::AuthError)));
+	let report = SelfTestReport {
+		steps: vec![passed, failed],
+		ratelimit_used: 2,
+		ratelimit_remaining: Some(598),
+		ratelimit_reset: Some(Duration::from_secs(300)),
+	};
+	assert!(!report.passed());
+	assert_eq!(report.failures().iter().map(|step| step.name).collect::<Vec<_>>(), vec!["token"]);
+	let line = report.to_string();
+	assert!(line.starts_with("anonymous_read ok ("), "{}", line);
+	assert!(line.contains("token FAILED ("), "{}", line);
+	assert!(line.contains("Failed to authorize"), "{}", line);
+	assert!(line.ends_with("ratelimit 2 used, 598 remaining, resets in 300 s"), "{}", line);
+}
+
+#[test(flair_choices)]
+fn flair_choices() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let choices = FlairChoices::from_value(
+		&json::from_str(
+			r#"{"current": {"flair_css_class": "", "flair_template_id": "b1f3c2d0-1111", "flair_text": "Question", "flair_position": "right"}, "choices": [
+				{"flair_css_class": "question", "flair_template_id": "b1f3c2d0-1111", "flair_text_editable": false, "flair_position": "right", "flair_text": "Question"},
+				{"flair_css_class": "", "flair_template_id": "b1f3c2d0-2222", "flair_text_editable": true, "flair_position": "right", "flair_text": "Other"}
+			]}"#,
+		)
+		.unwrap(),
+		&reddit,
+	)
+	.unwrap();
+	assert_eq!(
+		choices.current,
+		Some(AppliedFlair {
+			template_id: Some("b1f3c2d0-1111".to_string()),
+			text: Some("Question".to_string()),
+			css_class: None,
+		})
+	);
+	assert_eq!(choices.choices.len(), 2);
+	assert!(!choices.choices[0].text_editable);
+	assert_eq!(choices.choices[1].template_id, "b1f3c2d0-2222");
+	assert!(choices.choices[1].text_editable);
+
+	let unflaired = FlairChoices::from_value(&json::from_str(r#"{"current": {"flair_css_class": null, "flair_template_id": null, "flair_text": null}, "choices": []}"#).unwrap(), &reddit).unwrap();
+	assert_eq!(unflaired.current, None);
+	assert!(unflaired.choices.is_empty());
+}
+
+#[test(jsonl_export)]
+fn jsonl_export() {
+	use export::JsonlWriter;
+	use std::fs;
+
+	let dir = std::env::temp_dir().join(format!("orca-export-{}", rand::random::<u64>()));
+	fs::create_dir_all(&dir).unwrap();
+	let path = dir.join("posts.jsonl");
+	let post = |name: &str| json::from_str::<json::Value>(&format!(r#"{{"kind": "t3", "data": {{"name": "{}"}}}}"#, name)).unwrap();
+	let line_len = json::to_string(&post("t3_a")).unwrap().len() as u64 + 1;
+
+	{
+		let mut writer = JsonlWriter::create(&path).unwrap();
+		writer.rotate_at(line_len * 2);
+		for name in &["t3_a", "t3_b", "t3_c"] {
+			writer.write_named(name, &post(name)).unwrap();
+		}
+		assert_eq!(writer.resume_token(), Some("t3_c"));
+		assert_eq!(writer.paths(), vec![path.clone(), dir.join("posts.1.jsonl")]);
+	}
+
+	// Dropping the writer flushed the items and saved the resume token
+	let lines: Vec<json::Value> = fs::read_to_string(&path).unwrap().lines().map(|line| json::from_str(line).unwrap()).collect();
+	assert_eq!(lines, vec![post("t3_a"), post("t3_b")]);
+	assert_eq!(fs::read_to_string(dir.join("posts.1.jsonl")).unwrap(), format!("{}\n", post("t3_c")));
+
+	// A new writer continues the export where it stopped
+	let mut writer = JsonlWriter::create(&path).unwrap();
+	assert_eq!(writer.resume_token(), Some("t3_c"));
+	writer.write_named("t3_d", &post("t3_d")).unwrap();
+	writer.flush().unwrap();
+	assert_eq!(fs::read_to_string(dir.join("posts.1.jsonl")).unwrap().lines().count(), 2);
+	assert_eq!(fs::read_to_string(dir.join("posts.jsonl.resume")).unwrap(), "t3_d");
+	drop(writer);
+
+	fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test(jsonl_export_truncated)]
+fn jsonl_export_truncated() {
+	use export::JsonlWriter;
+	use std::fs;
+
+	let dir = std::env::temp_dir().join(format!("orca-export-{}", rand::random::<u64>()));
+	fs::create_dir_all(&dir).unwrap();
+	let path = dir.join("posts.jsonl");
+	let post = |name: &str| json::from_str::<json::Value>(&format!(r#"{{"kind": "t3", "data": {{"name": "{}"}}}}"#, name)).unwrap();
+
+	// A crash cut off the last line while it was written
+	fs::write(&path, format!("{}\n{{\"kind\": \"t3\", \"da", post("t3_a"))).unwrap();
+	fs::write(dir.join("posts.jsonl.resume"), "t3_a").unwrap();
+
+	let mut writer = JsonlWriter::create(&path).unwrap();
+	assert_eq!(writer.resume_token(), Some("t3_a"));
+	writer.write_named("t3_b", &post("t3_b")).unwrap();
+	drop(writer);
+	let lines: Vec<json::Value> = fs::read_to_string(&path).unwrap().lines().map(|line| json::from_str(line).unwrap()).collect();
+	assert_eq!(lines, vec![post("t3_a"), post("t3_b")]);
+
+	// A file that is only a cut off line is emptied
+	fs::write(&path, "{\"kind").unwrap();
+	drop(JsonlWriter::<json::Value>::create(&path).unwrap());
+	assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+	fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test(pages_resume)]
+fn pages_resume() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let pages = reddit.get_all_posts("rust", Sort::New).unwrap().start_after(Some("t3_b"));
+	assert_eq!(pages.after(), Some("t3_b"));
+	assert!(pages.page_params().contains(&("after".to_string(), "t3_b".to_string())));
+}
+
+#[test(network_errors)]
+fn network_errors() {
+	use net::classify_network_error;
+	use std::io;
+
+	let io_error = |kind, msg: &str| io::Error::new(kind, msg.to_string());
+	match classify_network_error("GET /", false, Some(&io_error(io::ErrorKind::TimedOut, "timed out"))) {
+		Some(RedditError::Timeout { .. }) => (),
+		other => panic!("Expected a timeout: {:?}", other),
+	}
+	match classify_network_error("GET /", true, Some(&io_error(io::ErrorKind::Other, "failed to lookup address information: Name or service not known"))) {
+		Some(RedditError::Dns { ref error, .. }) if error.contains("Name or service not known") => (),
+		other => panic!("Expected a dns error: {:?}", other),
+	}
+	match classify_network_error("GET /", true, Some(&io_error(io::ErrorKind::ConnectionRefused, "connection refused"))) {
+		Some(RedditError::ConnectionFailed { .. }) => (),
+		other => panic!("Expected a failed connection: {:?}", other),
+	}
+	match classify_network_error("GET /", false, Some(&io_error(io::ErrorKind::ConnectionReset, "connection reset by peer"))) {
+		Some(RedditError::ConnectionFailed { .. }) => (),
+		other => panic!("Expected a failed connection: {:?}", other),
+	}
+	assert!(classify_network_error("GET /", true, None).is_some());
+	assert!(classify_network_error("GET /", false, None).is_none());
+	assert_eq!(RedditError::Timeout { request: "GET /".to_string() }.status_code(), None);
+}
+
+#[test(find_sent)]
+fn find_sent_messages() {
+	use chrono::{TimeZone, Utc};
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let message = |id: &str, dest: &str, subject: &str, created: f64| {
+		let val = json::from_str(&format!(
+			r##"{{"kind": "t4", "data": {{"id": "{}", "name": "t4_{}", "author": "IntrepidPig", "dest": "{}", "subject": "{}", "body": "Hello", "created_utc": {}}}}}"##,
+			id, id, dest, subject, created
+		))
+		.unwrap();
+		Message::from_value(&val, &reddit).unwrap()
+	};
+	let mut sent = Listing::new();
+	sent.children.push_back(message("c", "someone", "Hi", 1_513_785_900.0));
+	sent.children.push_back(message("b", "#pigasusland", "Modmail", 1_513_785_800.0));
+	sent.children.push_back(message("a", "Gallowboob", "Hi", 1_513_785_500.0));
+	let since = Utc.timestamp_opt(1_513_785_600, 0).unwrap();
+
+	assert_eq!(find_sent(&sent, "someone", "Hi", since).unwrap().id, "c");
+	assert_eq!(find_sent(&sent, "/r/pigasusland", "Modmail", since).unwrap().id, "b");
+	assert!(find_sent(&sent, "someone", "Other", since).is_none());
+	// Messages sent before the window don't count
+	assert!(find_sent(&sent, "gallowboob", "Hi", since).is_none());
+	assert_eq!(find_sent(&sent, "gallowboob", "Hi", Utc.timestamp_opt(1_513_785_000, 0).unwrap()).unwrap().id, "a");
+	assert_eq!(sent.children[0].dest, Some("someone".to_string()));
+	assert!(!SendOutcome::AlreadySent { existing: Box::new(sent.children[0].clone()) }.sent());
+}
+
+#[test(more_stubs)]
+fn more_stubs() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let mut parent = comment_fixture("drh5ok6");
+	parent["data"]["replies"] = json::from_str(r#"{"kind": "Listing", "data": {"children": [{"kind": "more", "data": {"id": "_", "name": "t1__", "parent_id": "t1_drh5ok6", "count": 0, "depth": 10, "children": []}}]}}"#).unwrap();
+	let listing = json::Value::Array(vec![
+		parent,
+		json::from_str(r#"{"kind": "more", "data": {"id": "drh6aaa", "name": "t1_drh6aaa", "parent_id": "t3_7le01h", "count": 57, "depth": 0, "children": ["drh6aaa", "drh6bbb"]}}"#).unwrap(),
+		json::from_str(r#"{"kind": "more", "data": {"id": "drh7ccc", "name": "t1_drh7ccc", "parent_id": "t3_7le01h", "count": 3, "depth": 0, "children": ["drh7ccc"]}}"#).unwrap(),
+	]);
+
+	let stubs = MoreComments::collect(&listing, &reddit).unwrap();
+	assert_eq!(stubs.iter().map(|stub| stub.count).collect::<Vec<_>>(), vec![57, 3, 0]);
+	assert_eq!(stubs[0].children, vec!["drh6aaa", "drh6bbb"]);
+	assert_eq!(stubs[0].parent_id, "t3_7le01h");
+	assert!(!stubs[0].is_continue_thread());
+	assert!(stubs[2].is_continue_thread());
+	assert_eq!(stubs[2].parent_id, "t1_drh5ok6");
+}
+
+#[test(include_hidden)]
+fn include_hidden() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let show_all = ("show".to_string(), "all".to_string());
+	assert!(!reddit.get_all_posts("rust", Sort::New).unwrap().page_params().contains(&show_all));
+
+	reddit.set_include_hidden(true);
+	assert!(reddit.get_all_posts("rust", Sort::New).unwrap().page_params().contains(&show_all));
+}
+
+#[test(read_host)]
+fn read_host() {
+	use std::cell::{Cell, RefCell};
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	assert_eq!(reddit.conn.read_host(), "https://www.reddit.com");
+	assert_eq!(reddit.get_all_posts("rust", Sort::New).unwrap().url(), "https://www.reddit.com/r/rust/new.json");
+
+	reddit.conn.auth.replace(Some(OAuth::Script {
+		id: "id".to_string(),
+		secret: "secret".to_string(),
+		username: "user".to_string(),
+		password: "password".to_string(),
+		token: RefCell::new("token".to_string()),
+		expire_instant: Cell::new(None),
+	}));
+	assert_eq!(reddit.conn.read_host(), "https://oauth.reddit.com");
+	assert_eq!(reddit.get_all_posts("rust", Sort::New).unwrap().url(), "https://oauth.reddit.com/r/rust/new.json");
+
+	// Reads can be kept anonymous while authorized
+	reddit.conn.set_anonymous_reads(true);
+	assert_eq!(reddit.conn.read_host(), "https://www.reddit.com");
+	reddit.conn.set_anonymous_reads(false);
+	assert_eq!(reddit.conn.read_host(), "https://oauth.reddit.com");
+
+	// The hosts the requests of each read were sent to. The token is made up, so the requests
+	// themselves fail.
+	let sent_to = |read: &dyn Fn()| {
+		let before = reddit.conn.request_stats().hosts;
+		read();
+		let mut hosts: Vec<String> = reddit.conn.request_stats().hosts.into_iter().filter(|(host, count)| before.get(host) != Some(count)).map(|(host, _)| host).collect();
+		hosts.sort();
+		hosts
+	};
+	let reads: [(&str, &dyn Fn()); 8] = [
+		("get_posts", &|| drop(reddit.get_posts("rust", Sort::New))),
+		("get_user", &|| drop(reddit.get_user("IntrepidPig"))),
+		("get_comment_tree", &|| drop(reddit.get_comment_tree("t3_7l2wn9"))),
+		("more_children", &|| drop(reddit.more_children("t3_7l2wn9", "t1_drjsx6v", &["t1_drjt0ql"]))),
+		("get_post_stats", &|| drop(reddit.get_post_stats("t3_7l2wn9"))),
+		("get_available_awards", &|| drop(reddit.get_available_awards("t3_7l2wn9"))),
+		("is_available", &|| drop(reddit.is_available("t3_7l2wn9"))),
+		("get_subreddits_about", &|| drop(reddit.get_subreddits_about(&["rust"]))),
+	];
+	for &(anonymous, host) in &[(false, "oauth.reddit.com"), (true, "www.reddit.com")] {
+		reddit.conn.set_anonymous_reads(anonymous);
+		for (name, read) in &reads {
+			assert_eq!(sent_to(*read), vec![host.to_string()], "{} with anonymous reads {}", name, anonymous);
+		}
+	}
+}
+
+#[test(message_thread)]
+fn message_thread() {
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	let message = |id: &str, first: &str, created: f64, replies: &str| {
+		format!(
+			r#"{{"kind": "t4", "data": {{"id": "{}", "name": "t4_{}", "author": "IntrepidPig", "subject": "Ticket", "body": "Hello", "first_message_name": {}, "created_utc": {}, "replies": {}}}}}"#,
+			id, id, first, created, replies
+		)
+	};
+	let listing = |children: &[String]| format!(r#"{{"kind": "Listing", "data": {{"children": [{}]}}}}"#, children.join(", "));
+
+	let nested = listing(&[message("c", r#""t4_a""#, 1_513_785_900.0, r#""""#)]);
+	let replies = listing(&[message("d", r#""t4_a""#, 1_513_786_000.0, r#""""#), message("b", r#""t4_a""#, 1_513_785_700.0, &nested)]);
+	let thread = MessageThread::from_value(&json::from_str(&listing(&[message("a", "null", 1_513_785_600.0, &replies)])).unwrap(), &reddit).unwrap();
+
+	assert_eq!(thread.root.name, "t4_a");
+	assert_eq!(thread.root.first_message_name, None);
+	assert_eq!(thread.replies.iter().map(|reply| reply.id.as_str()).collect::<Vec<_>>(), vec!["b", "c", "d"]);
+	assert_eq!(thread.replies[0].first_message_name, Some("t4_a".to_string()));
+
+	let single = MessageThread::from_value(&json::from_str(&listing(&[message("e", "null", 1_513_785_600.0, r#""""#)])).unwrap(), &reddit).unwrap();
+	assert!(single.replies.is_empty());
+	assert!(MessageThread::from_value(&json::from_str(&listing(&[])).unwrap(), &reddit).is_err());
+}
+
+#[test(on_behalf_of)]
+fn on_behalf_of() {
+	use std::cell::{Cell, RefCell};
+
+	let script = |username: &str| OAuth::Script {
+		id: "id".to_string(),
+		secret: "secret".to_string(),
+		username: username.to_string(),
+		password: "password".to_string(),
+		token: RefCell::new(format!("token of {}", username)),
+		expire_instant: Cell::new(None),
+	};
+	let username = |auth: Option<&OAuth>| match auth {
+		Some(OAuth::Script { username, .. }) => Some(username.clone()),
+		_ => None,
+	};
+
+	let reddit = App::new("OrcaLibTest", "v0.2.0", "/u/IntrepidPig").unwrap();
+	reddit.conn.auth.replace(Some(script("service")));
+	let mut delegate = Delegate::new(script("moderator"));
+	delegate.limits.remaining = Some(12);
+
+	let seen = reddit.on_behalf_of(&mut delegate, |app| (username(app.conn.auth.borrow().as_ref()), app.conn.ratelimit_remaining()));
+	assert_eq!(seen, (Some("moderator".to_string()), Some(12)));
+	assert_eq!(username(reddit.conn.auth.borrow().as_ref()), Some("service".to_string()));
+	assert_eq!(reddit.conn.ratelimit_remaining(), None);
+	assert_eq!(username(delegate.auth()), Some("moderator".to_string()));
+	assert_eq!(delegate.ratelimit_remaining(), Some(12));
+
+	// The app is restored when the function panics
+	let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| reddit.on_behalf_of(&mut delegate, |_| panic!("failed"))));
+	assert!(result.is_err());
+	assert_eq!(username(reddit.conn.auth.borrow().as_ref()), Some("service".to_string()));
+	assert_eq!(username(delegate.auth()), Some("moderator".to_string()));
+
+	// Comment cooldowns and the settings of comments and submissions belong to the account
+	let guard = DuplicateGuard { lookback: Duration::from_secs(60), limit: 10 };
+	reddit.set_duplicate_guard(Some(DuplicateGuard::default()));
+	reddit.record_comment_cooldown("pigasusland", |cooldown| cooldown.record_ratelimit(Duration::from_secs(600), Instant::now()));
+	delegate.set_duplicate_guard(Some(guard));
+	delegate.set_wait_for_comment_cooldown(true);
+	let seen = reddit.on_behalf_of(&mut delegate, |app| {
+		app.record_comment_cooldown("rust", |cooldown| cooldown.record_ratelimit(Duration::from_secs(60), Instant::now()));
+		(app.comment_cooldown("pigasusland").is_some(), app.duplicate_guard.get(), app.wait_for_comment_cooldown.get())
+	});
+	assert_eq!(seen, (false, Some(guard), true));
+	assert!(reddit.comment_cooldown("pigasusland").is_some());
+	assert_eq!(reddit.comment_cooldown("rust"), None);
+	assert_eq!(reddit.duplicate_guard.get(), Some(DuplicateGuard::default()));
+	assert!(!reddit.wait_for_comment_cooldown.get());
+	assert!(delegate.comment_cooldown("Rust").is_some());
+	assert_eq!(delegate.comment_cooldown("pigasusland"), None);
+}